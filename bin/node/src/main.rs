@@ -3,7 +3,7 @@
 #![allow(clippy::similar_names)]
 #![allow(clippy::items_after_statements)]
 
-use std::str::FromStr;
+use std::{fs, path::PathBuf, str::FromStr, sync::Arc, time::Duration};
 
 use alloy_primitives::{Address, U256};
 use alloy_signer_local::PrivateKeySigner;
@@ -14,9 +14,22 @@ use tabled::{settings::Style, Table, Tabled};
 use tracing::info;
 
 use sp1_sdk::{include_elf, SP1Stdin};
+use spn_artifact_types::ArtifactType;
+use spn_artifacts::{
+    set_download_config, ArtifactSecretKey, DownloadConfig, ProofUploadPolicy,
+    DEFAULT_INLINE_PROOF_LIMIT,
+};
 use spn_calibrator::{Calibrator, SinglePassCalibrator};
-use spn_network_types::prover_network_client::ProverNetworkClient;
-use spn_node_core::{Node, NodeContext, SerialBidder, SerialContext, SerialMonitor, SerialProver};
+use spn_node_core::{
+    CommandSink, Node, NodeConfig, NodeContext, Notifier, NotifierConfig, SerialBidder,
+    SerialContext, SerialMonitor, SerialProver, SlackSink, WebhookSink, WorkerBackend,
+    DEFAULT_BID_AND_PROVE_INTERVAL, DEFAULT_ERROR_BUDGET, DEFAULT_MAX_BACKOFF,
+    DEFAULT_MONITOR_INTERVAL, DEFAULT_NOTIFY_RATE_WINDOW,
+};
+use spn_rpc::{
+    EndpointConfig, EndpointPool, EndpointPoolConfig, EndpointStrategy, RateLimit, RateLimitConfig,
+    RpcAuth, DEFAULT_EJECTION_COOLDOWN, DEFAULT_HEALTH_CHECK_INTERVAL,
+};
 
 /// The CLI application that defines all available commands.
 #[derive(Parser)]
@@ -48,9 +61,77 @@ struct CalibrateArgs {
 /// The arguments for the `prove` command.
 #[derive(Debug, Clone, Parser)]
 struct ProveArgs {
-    /// The RPC URL for the network.
+    /// The RPC URLs for the network, in order of preference. Can be repeated or comma-separated.
+    #[arg(long, required = true, value_delimiter = ',')]
+    rpc_url: Vec<String>,
+    /// Balance requests over all healthy RPC URLs instead of failing over between them in order.
     #[arg(long)]
-    rpc_url: String,
+    rpc_balance: bool,
+    /// The interval in seconds between health checks of each RPC URL.
+    #[arg(long, default_value_t = DEFAULT_HEALTH_CHECK_INTERVAL.as_secs())]
+    rpc_health_check_secs: u64,
+    /// The minimum time in seconds an unhealthy RPC URL is kept out of rotation.
+    #[arg(long, default_value_t = DEFAULT_EJECTION_COOLDOWN.as_secs())]
+    rpc_cooldown_secs: u64,
+    /// A PEM file with the CA certificate that the RPC server certificates must chain to.
+    #[arg(long)]
+    rpc_ca_cert: Option<PathBuf>,
+    /// A PEM file with the client certificate presented to the RPC servers for mutual TLS.
+    #[arg(long, requires = "rpc_client_key")]
+    rpc_client_cert: Option<PathBuf>,
+    /// A PEM file with the private key of the client certificate.
+    #[arg(long, requires = "rpc_client_cert")]
+    rpc_client_key: Option<PathBuf>,
+    /// A bearer token sent with every RPC request.
+    #[arg(long, conflicts_with = "rpc_api_key")]
+    rpc_bearer_token: Option<String>,
+    /// An API key sent with every RPC request.
+    #[arg(long)]
+    rpc_api_key: Option<String>,
+    /// The header the API key is sent in.
+    #[arg(long, default_value = "x-api-key")]
+    rpc_api_key_header: String,
+    /// Compress RPC requests with gzip.
+    #[arg(long)]
+    rpc_gzip: bool,
+    /// The maximum size in bytes of an RPC request or response.
+    #[arg(long)]
+    rpc_max_message_size: Option<usize>,
+    /// The maximum number of calls per second to each RPC method.
+    #[arg(long, value_parser = parse_rate)]
+    rpc_rate_limit: Option<f64>,
+    /// The maximum number of calls per second to a single RPC method, as `Method=rate`, e.g.
+    /// `GetNonce=5`. Can be repeated or comma-separated.
+    #[arg(long, value_delimiter = ',', value_parser = parse_method_rate_limit)]
+    rpc_method_rate_limit: Vec<(String, f64)>,
+    /// The number of calls to an RPC method that may be made at once before rate limiting.
+    #[arg(long, default_value_t = 10)]
+    rpc_rate_limit_burst: u32,
+    /// The directory downloaded programs and stdins are cached in.
+    #[arg(long)]
+    artifact_cache_dir: Option<PathBuf>,
+    /// The maximum size in bytes of a downloaded program.
+    #[arg(long)]
+    max_program_size: Option<u64>,
+    /// The maximum size in bytes of a downloaded stdin.
+    #[arg(long)]
+    max_stdin_size: Option<u64>,
+    /// Where proofs too large to send inline are uploaded, e.g. s3://bucket.
+    #[arg(long)]
+    proof_upload_uri: Option<String>,
+    /// The AWS region of the proof upload bucket.
+    #[arg(long, default_value = "us-east-1")]
+    proof_upload_region: String,
+    /// The size in bytes above which proofs are uploaded instead of sent inline.
+    #[arg(long, default_value_t = DEFAULT_INLINE_PROOF_LIMIT)]
+    inline_proof_limit: usize,
+    /// Worker binaries that prove the requests of other SP1 versions, as `<version>=<path>`, e.g.
+    /// `sp1-v4.0.0=/usr/local/bin/sp1-v4-worker`.
+    #[arg(long, value_name = "VERSION=PATH")]
+    prover_worker: Vec<String>,
+    /// The hex-encoded X25519 secret key that encrypted stdins are decrypted with.
+    #[arg(long)]
+    stdin_key: Option<String>,
     /// The amount of proving gas units (PGUs) per second your prover can process.
     #[arg(long)]
     throughput: f64,
@@ -63,6 +144,39 @@ struct ProveArgs {
     /// The address of the prover.
     #[arg(long)]
     prover: Address,
+    /// Webhook URLs that receive node notifications as JSON.
+    #[arg(long)]
+    webhook_url: Vec<String>,
+    /// Slack-compatible webhook URLs that receive node notifications.
+    #[arg(long)]
+    slack_webhook_url: Vec<String>,
+    /// A shell command that is run for each node notification.
+    #[arg(long)]
+    notify_command: Option<String>,
+    /// The window in seconds during which duplicate notifications are suppressed.
+    #[arg(long, default_value_t = 600)]
+    notify_dedup_secs: u64,
+    /// The maximum number of notifications sent per minute.
+    #[arg(long, default_value_t = 10)]
+    notify_rate_limit: usize,
+    /// Notify when the balance of the prover owner drops below this amount of $PROVE (in wei).
+    #[arg(long)]
+    min_balance: Option<String>,
+    /// Notify when the stake of the prover drops below this amount of $PROVE (in wei).
+    #[arg(long)]
+    min_stake: Option<String>,
+    /// The interval in seconds between bidding and proving rounds.
+    #[arg(long, default_value_t = DEFAULT_BID_AND_PROVE_INTERVAL.as_secs())]
+    bid_and_prove_interval_secs: u64,
+    /// The interval in seconds between monitoring rounds.
+    #[arg(long, default_value_t = DEFAULT_MONITOR_INTERVAL.as_secs())]
+    monitor_interval_secs: u64,
+    /// The number of consecutive failed rounds after which the node exits, or 0 to never exit.
+    #[arg(long, default_value_t = DEFAULT_ERROR_BUDGET)]
+    error_budget: u32,
+    /// The maximum delay in seconds between failed rounds.
+    #[arg(long, default_value_t = DEFAULT_MAX_BACKOFF.as_secs())]
+    max_backoff_secs: u64,
 }
 
 /// The main entry point for the CLI.
@@ -175,33 +289,137 @@ async fn main() -> Result<()> {
             spn_utils::init_logger(spn_utils::LogFormat::Pretty);
 
             // Setup the connection to the network.
-            let endpoint = spn_rpc::configure_endpoint(&args.rpc_url)?;
-            let network = ProverNetworkClient::connect(endpoint).await?;
+            let mut rpc = EndpointConfig::default()
+                .with_user_agent(concat!("spn-node/", env!("CARGO_PKG_VERSION")));
+            if let Some(path) = &args.rpc_ca_cert {
+                rpc = rpc.with_ca_certificate(fs::read(path)?);
+            }
+            if let (Some(cert), Some(key)) = (&args.rpc_client_cert, &args.rpc_client_key) {
+                rpc = rpc.with_identity(fs::read(cert)?, fs::read(key)?);
+            }
+            if let Some(token) = &args.rpc_bearer_token {
+                rpc = rpc.with_auth(RpcAuth::Bearer(token.clone()));
+            }
+            if let Some(key) = &args.rpc_api_key {
+                rpc = rpc.with_auth(RpcAuth::ApiKey {
+                    header: args.rpc_api_key_header.clone(),
+                    key: key.clone(),
+                });
+            }
+            if args.rpc_gzip {
+                rpc = rpc.with_gzip();
+            }
+            if let Some(size) = args.rpc_max_message_size {
+                rpc = rpc.with_max_message_size(size);
+            }
+            let mut rate_limits = RateLimitConfig::default();
+            if let Some(rate) = args.rpc_rate_limit {
+                rate_limits =
+                    rate_limits.with_default(RateLimit::new(rate, args.rpc_rate_limit_burst)?);
+            }
+            for (method, rate) in &args.rpc_method_rate_limit {
+                rate_limits = rate_limits
+                    .with_method(method, RateLimit::new(*rate, args.rpc_rate_limit_burst)?);
+            }
+            rpc = rpc.with_rate_limits(rate_limits);
+            let endpoints = EndpointPool::connect(
+                &args.rpc_url,
+                EndpointPoolConfig {
+                    strategy: if args.rpc_balance {
+                        EndpointStrategy::Balanced
+                    } else {
+                        EndpointStrategy::Failover
+                    },
+                    health_check_interval: Duration::from_secs(args.rpc_health_check_secs),
+                    cooldown: Duration::from_secs(args.rpc_cooldown_secs),
+                    endpoint: rpc.clone(),
+                },
+            )
+            .await?;
+            let network = rpc.client(endpoints.channel())?;
+
+            // Setup the artifact downloads.
+            let mut downloads = DownloadConfig::default();
+            if let Some(dir) = &args.artifact_cache_dir {
+                downloads = downloads.with_cache_dir(dir);
+            }
+            if let Some(size) = args.max_program_size {
+                downloads = downloads.with_max_size(ArtifactType::Program, size);
+            }
+            if let Some(size) = args.max_stdin_size {
+                downloads = downloads.with_max_size(ArtifactType::Stdin, size);
+            }
+            set_download_config(downloads);
 
             // Setup the signer.
             let signer = PrivateKeySigner::from_str(&args.private_key)?;
 
-            // Setup the context.
-            let ctx = SerialContext::new(network, signer);
+            // Setup the notifier.
+            let mut notifier = Notifier::new(NotifierConfig {
+                dedup_window: Duration::from_secs(args.notify_dedup_secs),
+                rate_limit: args.notify_rate_limit,
+                rate_window: DEFAULT_NOTIFY_RATE_WINDOW,
+            });
+            for url in &args.webhook_url {
+                notifier = notifier.with_sink(WebhookSink::new(url));
+            }
+            for url in &args.slack_webhook_url {
+                notifier = notifier.with_sink(SlackSink::new(url));
+            }
+            if let Some(command) = &args.notify_command {
+                notifier = notifier.with_sink(CommandSink::new(command));
+            }
 
-            // Setup the bidder.
-            let bidder = SerialBidder::new(U256::from(args.bid), args.throughput, args.prover);
+            // Setup the context.
+            let ctx = SerialContext::new(network, signer).with_notifier(notifier);
 
             // Setup the prover
-            let prover = SerialProver::new();
+            let mut proof_upload =
+                ProofUploadPolicy::default().with_inline_limit(args.inline_proof_limit);
+            if let Some(uri) = &args.proof_upload_uri {
+                proof_upload = proof_upload.with_destination(uri, &args.proof_upload_region);
+            }
+            let mut prover = SerialProver::new().with_proof_upload(proof_upload);
+            if let Some(stdin_key) = &args.stdin_key {
+                let stdin_key = ArtifactSecretKey::from_hex(stdin_key)?;
+                info!(public_key = %stdin_key.public_key(), "Decrypting stdins encrypted to this key.");
+                prover = prover.with_stdin_key(stdin_key);
+            }
+            for worker in &args.prover_worker {
+                let (version, path) = worker.split_once('=').ok_or_else(|| {
+                    anyhow!("invalid prover worker {worker}: expected VERSION=PATH")
+                })?;
+                prover = prover.with_backend(version, Arc::new(WorkerBackend::new(path)));
+            }
+
+            // Setup the bidder, for every version the prover can prove.
+            let bidder = SerialBidder::new(U256::from(args.bid), args.throughput, args.prover)
+                .with_versions(prover.versions());
 
             // Setup the monitor.
-            let monitor = SerialMonitor::new();
+            let mut monitor = SerialMonitor::new();
+            if let Some(min_balance) = &args.min_balance {
+                monitor = monitor.with_min_balance(U256::from_str(min_balance)?);
+            }
+            if let Some(min_stake) = &args.min_stake {
+                monitor = monitor.with_min_stake(args.prover, U256::from_str(min_stake)?);
+            }
 
             // Setup the node.
             info!(
                 wallet = %ctx.signer().address(),
-                rpc = %args.rpc_url,
+                rpc = ?endpoints.active(),
                 throughput = %args.throughput,
                 bid = %args.bid,
+                versions = ?bidder.versions,
                 "Starting Node on Succinct Network..."
             );
-            let node = Node::new(ctx, bidder, prover, monitor);
+            let node = Node::new(ctx, bidder, prover, monitor).with_config(NodeConfig {
+                bid_and_prove_interval: Duration::from_secs(args.bid_and_prove_interval_secs),
+                monitor_interval: Duration::from_secs(args.monitor_interval_secs),
+                error_budget: (args.error_budget > 0).then_some(args.error_budget),
+                max_backoff: Duration::from_secs(args.max_backoff_secs),
+            });
 
             // Run the node.
             node.run().await?;
@@ -210,3 +428,19 @@ async fn main() -> Result<()> {
 
     Ok(())
 }
+
+/// Parse a rate limit, a positive number of calls per second.
+fn parse_rate(rate: &str) -> Result<f64, String> {
+    let parsed: f64 = rate.parse().map_err(|e| format!("invalid rate `{rate}`: {e}"))?;
+    if !parsed.is_finite() || parsed <= 0.0 {
+        return Err(format!("invalid rate `{rate}`: must be a positive number"));
+    }
+    Ok(parsed)
+}
+
+/// Parse a per-method rate limit given as `Method=rate`.
+fn parse_method_rate_limit(value: &str) -> Result<(String, f64), String> {
+    let (method, rate) =
+        value.split_once('=').ok_or_else(|| format!("expected `Method=rate`, got `{value}`"))?;
+    Ok((method.to_string(), parse_rate(rate)?))
+}
@@ -0,0 +1,198 @@
+use std::{
+    collections::HashMap,
+    fmt::Debug,
+    path::Path,
+    sync::{Arc, LazyLock, RwLock},
+    time::SystemTime,
+};
+
+use anyhow::{anyhow, bail, Context, Result};
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures::{future, stream::BoxStream, StreamExt, TryStreamExt};
+use url::Url;
+
+use crate::{ArtifactErrorKind, BackendError, ContentEncoding, HttpBackend, S3Backend};
+
+/// The backends registered for each URI scheme, in addition to the default ones.
+static BACKENDS: LazyLock<RwLock<HashMap<String, Arc<dyn ArtifactBackend>>>> =
+    LazyLock::new(|| RwLock::new(HashMap::new()));
+
+/// The metadata of a stored artifact.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ArtifactMetadata {
+    /// The size of the artifact in bytes, if known.
+    pub size: Option<u64>,
+    /// The hex-encoded sha256 digest of the stored bytes, if recorded.
+    pub sha256: Option<String>,
+    /// The encoding of the stored bytes, if recorded.
+    pub encoding: Option<ContentEncoding>,
+    /// When the artifact expires, as a Unix timestamp, if recorded.
+    pub expires_at: Option<u64>,
+    /// When the artifact was last written, if known.
+    pub last_modified: Option<SystemTime>,
+    /// The entity tag of the stored bytes, if known, so downloads only resume while the artifact
+    /// is unchanged.
+    pub etag: Option<String>,
+}
+
+/// The body of an artifact, streamed in chunks.
+pub struct ArtifactStream {
+    /// The position in the artifact the body starts at.
+    ///
+    /// This is below the requested offset if the artifact changed since an interrupted download
+    /// started, in which case the body is the new artifact from its start.
+    pub offset: u64,
+    /// The number of bytes in the body, if announced before the body.
+    pub size: Option<u64>,
+    /// The metadata announced with the body, other than its size.
+    pub metadata: ArtifactMetadata,
+    /// The chunks of the body.
+    pub body: BoxStream<'static, Result<Bytes>>,
+}
+
+impl Debug for ArtifactStream {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ArtifactStream")
+            .field("offset", &self.offset)
+            .field("size", &self.size)
+            .field("metadata", &self.metadata)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ArtifactStream {
+    /// Drop the first `offset` bytes of the body.
+    #[must_use]
+    pub fn skip(self, offset: u64) -> Self {
+        if offset == 0 {
+            return self;
+        }
+        let mut remaining = offset;
+        let body = self.body.try_filter_map(move |mut chunk| {
+            let skipped = remaining.min(chunk.len() as u64);
+            remaining -= skipped;
+            let chunk = chunk.split_off(skipped as usize);
+            future::ready(Ok((!chunk.is_empty()).then_some(chunk)))
+        });
+        Self {
+            offset: self.offset + offset,
+            size: self.size.map(|size| size.saturating_sub(offset)),
+            metadata: self.metadata,
+            body: body.boxed(),
+        }
+    }
+}
+
+/// A place artifacts can be stored in, addressed by URI.
+///
+/// Backends are picked by URI scheme with [`backend_for`]: `s3://bucket/key` for S3 and
+/// `http(s)://...` for read-only HTTP(S) downloads. `file:///path` for the local filesystem and
+/// `memory://bucket/key` for an in-process store are only available once registered.
+#[async_trait]
+pub trait ArtifactBackend: Debug + Send + Sync {
+    /// Get the contents of the artifact at `uri`.
+    async fn get(&self, uri: &Url) -> Result<Bytes>;
+
+    /// Stream the contents of the artifact at `uri`.
+    ///
+    /// Backends that can download an artifact without holding it in memory should override this,
+    /// so that [`crate::download`] can enforce size limits while the body is being received.
+    async fn get_stream(&self, uri: &Url) -> Result<ArtifactStream> {
+        let data = self.get(uri).await?;
+        Ok(ArtifactStream {
+            offset: 0,
+            size: Some(data.len() as u64),
+            metadata: ArtifactMetadata::default(),
+            body: futures::stream::once(async move { Ok(data) }).boxed(),
+        })
+    }
+
+    /// Stream the contents of the artifact at `uri`, starting at byte `offset`.
+    ///
+    /// This is used to resume interrupted downloads. Backends that can start a download at an
+    /// offset should override this, as by default the first `offset` bytes are downloaded again
+    /// and skipped. `etag` is the [`ArtifactMetadata::etag`] of the artifact the first `offset`
+    /// bytes came from, and backends that support it send the changed artifact from its start if
+    /// it changed since, with an [`ArtifactStream::offset`] of zero, instead of resuming into a
+    /// different artifact.
+    async fn get_stream_from(
+        &self,
+        uri: &Url,
+        offset: u64,
+        _etag: Option<&str>,
+    ) -> Result<ArtifactStream> {
+        Ok(self.get_stream(uri).await?.skip(offset))
+    }
+
+    /// Store `data` as the artifact at `uri`, replacing any existing artifact.
+    async fn put(&self, uri: &Url, data: Bytes) -> Result<()>;
+
+    /// Store `data` as the artifact at `uri` along with its digest and encoding.
+    ///
+    /// Backends that cannot store metadata drop it, in which case the digest is not checked on
+    /// download and the encoding is detected from the stored bytes.
+    async fn put_with_metadata(
+        &self,
+        uri: &Url,
+        data: Bytes,
+        _metadata: &ArtifactMetadata,
+    ) -> Result<()> {
+        self.put(uri, data).await
+    }
+
+    /// Store the file at `path` as the artifact at `uri` along with its digest and encoding.
+    ///
+    /// Backends that can upload a file without holding it in memory should override this.
+    async fn put_file(&self, uri: &Url, path: &Path, metadata: &ArtifactMetadata) -> Result<()> {
+        let data = tokio::fs::read(path)
+            .await
+            .with_context(|| format!("Failed to read artifact file {}", path.display()))?;
+        self.put_with_metadata(uri, Bytes::from(data), metadata).await
+    }
+
+    /// Get the metadata of the artifact at `uri`, or `None` if it does not exist.
+    async fn head(&self, uri: &Url) -> Result<Option<ArtifactMetadata>>;
+
+    /// Copy the artifact at `src` to `dst`, both handled by this backend.
+    async fn copy(&self, src: &Url, dst: &Url) -> Result<()> {
+        let data = self.get(src).await?;
+        self.put(dst, data).await
+    }
+
+    /// Delete the artifact at `uri`. Deleting an artifact that does not exist is not an error.
+    async fn delete(&self, uri: &Url) -> Result<()>;
+
+    /// List the URIs of the artifacts under `prefix`, such as `s3://bucket/programs/`.
+    async fn list(&self, prefix: &Url) -> Result<Vec<Url>> {
+        bail!("Listing artifacts is not supported for {prefix}")
+    }
+}
+
+/// Use `backend` for every URI with the given scheme, instead of the default backend.
+pub fn register_backend(scheme: &str, backend: Arc<dyn ArtifactBackend>) {
+    BACKENDS.write().unwrap().insert(scheme.to_string(), backend);
+}
+
+/// Get the backend that handles `uri`.
+///
+/// Backends registered with [`register_backend`] take precedence. Otherwise, `s3` URIs are
+/// handled by an [`S3Backend`] for `s3_region` and `http` and `https` URIs by an [`HttpBackend`].
+///
+/// Request URIs are chosen by requesters, so local schemes are not handled by default: a stdin at
+/// `file:///` could otherwise expose the files of the node. Devnets and tests opt in with
+/// [`crate::FileBackend::register`] and [`crate::MemoryBackend::register`].
+pub fn backend_for(uri: &Url, s3_region: &str) -> Result<Arc<dyn ArtifactBackend>> {
+    if let Some(backend) = BACKENDS.read().unwrap().get(uri.scheme()) {
+        return Ok(backend.clone());
+    }
+    match uri.scheme() {
+        "s3" => Ok(Arc::new(S3Backend::new(s3_region))),
+        "http" | "https" => Ok(Arc::new(HttpBackend::default())),
+        scheme => Err(BackendError::new(
+            ArtifactErrorKind::NotFound,
+            format!("Unsupported artifact URI scheme: {scheme}"),
+        )
+        .into()),
+    }
+}
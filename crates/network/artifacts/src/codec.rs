@@ -0,0 +1,208 @@
+use std::{
+    io::Read,
+    path::Path,
+    sync::{LazyLock, RwLock},
+};
+
+use anyhow::{Context, Result};
+use bytes::Bytes;
+use sha2::{Digest, Sha256};
+use tokio::io::AsyncReadExt;
+
+use crate::{ArtifactErrorKind, ArtifactMetadata, BackendError, MultipartConfig};
+
+/// The upload configuration used by [`crate::Artifact`].
+static UPLOAD_CONFIG: LazyLock<RwLock<UploadConfig>> =
+    LazyLock::new(|| RwLock::new(UploadConfig::default()));
+
+/// The magic bytes at the start of every zstd frame.
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+/// How the payload of a stored artifact is encoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContentEncoding {
+    /// The payload is stored as is.
+    Identity,
+    /// The payload is compressed with zstd.
+    Zstd,
+}
+
+impl ContentEncoding {
+    /// The name of the encoding, as stored in artifact metadata.
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Identity => "identity",
+            Self::Zstd => "zstd",
+        }
+    }
+
+    /// Parse the name of an encoding, as stored in artifact metadata.
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "identity" => Some(Self::Identity),
+            "zstd" => Some(Self::Zstd),
+            _ => None,
+        }
+    }
+}
+
+/// How artifacts are uploaded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UploadConfig {
+    /// The size above which payloads are compressed with zstd, or `None` to never compress.
+    pub compression_threshold: Option<u64>,
+    /// The zstd compression level.
+    pub compression_level: i32,
+    /// How large artifacts are uploaded to S3 in parts.
+    pub multipart: MultipartConfig,
+}
+
+impl Default for UploadConfig {
+    fn default() -> Self {
+        Self {
+            compression_threshold: Some(1024 * 1024),
+            compression_level: 3,
+            multipart: MultipartConfig::default(),
+        }
+    }
+}
+
+/// Set the upload configuration used by [`crate::Artifact`].
+pub fn set_upload_config(config: UploadConfig) {
+    *UPLOAD_CONFIG.write().unwrap() = config;
+}
+
+/// The upload configuration used by [`crate::Artifact`].
+#[must_use]
+pub fn upload_config() -> UploadConfig {
+    *UPLOAD_CONFIG.read().unwrap()
+}
+
+/// The hex-encoded sha256 digest of `data`.
+#[must_use]
+pub fn sha256_hex(data: &[u8]) -> String {
+    hex::encode(Sha256::digest(data))
+}
+
+/// The size and hex-encoded sha256 digest of the file at `path`, read in chunks.
+pub async fn sha256_file(path: &Path) -> Result<(u64, String)> {
+    let mut file = tokio::fs::File::open(path)
+        .await
+        .with_context(|| format!("Failed to open artifact file {}", path.display()))?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0; 1024 * 1024];
+    let mut size = 0;
+    loop {
+        let n = file.read(&mut buf).await?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+        size += n as u64;
+    }
+    Ok((size, hex::encode(hasher.finalize())))
+}
+
+/// Encode a payload for storage, compressing it if it is over the threshold of `config`.
+///
+/// Returns the stored bytes and their metadata, including their digest.
+pub fn encode(data: Bytes, config: &UploadConfig) -> Result<(Bytes, ArtifactMetadata)> {
+    let compress =
+        config.compression_threshold.is_some_and(|threshold| data.len() as u64 > threshold);
+    let (data, encoding) = if compress {
+        let compressed = zstd::bulk::compress(&data, config.compression_level)
+            .context("Failed to compress artifact")?;
+        (Bytes::from(compressed), ContentEncoding::Zstd)
+    } else {
+        (data, ContentEncoding::Identity)
+    };
+    let metadata = ArtifactMetadata {
+        size: Some(data.len() as u64),
+        sha256: Some(sha256_hex(&data)),
+        encoding: Some(encoding),
+        ..Default::default()
+    };
+    Ok((data, metadata))
+}
+
+/// Decode stored bytes back into the payload.
+///
+/// The digest is checked when `metadata` has one. The payload is decompressed when `metadata`
+/// says so or, for artifacts stored without an encoding, when it starts with the zstd magic
+/// bytes. Decompression is aborted once the payload is over `max_size`.
+pub fn decode(data: Bytes, metadata: &ArtifactMetadata, max_size: Option<u64>) -> Result<Bytes> {
+    if let Some(expected) = &metadata.sha256 {
+        let actual = sha256_hex(&data);
+        if !actual.eq_ignore_ascii_case(expected) {
+            let message =
+                format!("Artifact digest mismatch: expected sha256 {expected}, got {actual}");
+            return Err(BackendError::new(ArtifactErrorKind::Corrupt, message).into());
+        }
+    }
+
+    let encoding = metadata.encoding.unwrap_or(if data.starts_with(&ZSTD_MAGIC) {
+        ContentEncoding::Zstd
+    } else {
+        ContentEncoding::Identity
+    });
+    match encoding {
+        ContentEncoding::Identity => Ok(data),
+        ContentEncoding::Zstd => {
+            let corrupt = |e: std::io::Error| {
+                BackendError::new(ArtifactErrorKind::Corrupt, format!("Invalid zstd artifact: {e}"))
+            };
+            let decoder = zstd::stream::read::Decoder::new(&data[..]).map_err(corrupt)?;
+            let mut payload = Vec::new();
+            decoder
+                .take(max_size.map_or(u64::MAX, |max_size| max_size.saturating_add(1)))
+                .read_to_end(&mut payload)
+                .map_err(corrupt)?;
+            if let Some(max_size) = max_size.filter(|&max_size| payload.len() as u64 > max_size) {
+                let message =
+                    format!("Decompressed artifact is larger than the limit of {max_size} bytes");
+                return Err(BackendError::new(ArtifactErrorKind::TooLarge, message).into());
+            }
+            Ok(Bytes::from(payload))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let config = UploadConfig { compression_threshold: Some(1024), ..Default::default() };
+
+        let small = Bytes::from(vec![7; 100]);
+        let (stored, metadata) = encode(small.clone(), &config).unwrap();
+        assert_eq!(metadata.encoding, Some(ContentEncoding::Identity));
+        assert_eq!(decode(stored, &metadata, None).unwrap(), small);
+
+        let large = Bytes::from(vec![7; 100_000]);
+        let (stored, metadata) = encode(large.clone(), &config).unwrap();
+        assert_eq!(metadata.encoding, Some(ContentEncoding::Zstd));
+        assert!(stored.len() < large.len());
+        assert_eq!(decode(stored.clone(), &metadata, None).unwrap(), large);
+
+        // Compressed payloads are detected without metadata, and bounded.
+        let unknown = ArtifactMetadata::default();
+        assert_eq!(decode(stored.clone(), &unknown, None).unwrap(), large);
+        assert!(decode(stored, &unknown, Some(1000)).is_err());
+    }
+
+    #[test]
+    fn test_legacy_and_corrupt_artifacts() {
+        // Artifacts stored before digests and compression are read as is.
+        let legacy = Bytes::from(bincode::serialize(&vec![1u8, 2, 3]).unwrap());
+        assert_eq!(decode(legacy.clone(), &ArtifactMetadata::default(), None).unwrap(), legacy);
+
+        // A truncated download fails the digest check.
+        let (stored, metadata) = encode(legacy, &UploadConfig::default()).unwrap();
+        let error = decode(stored.slice(..stored.len() - 1), &metadata, None).unwrap_err();
+        assert!(error.to_string().contains("digest mismatch"));
+    }
+}
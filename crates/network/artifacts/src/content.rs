@@ -0,0 +1,199 @@
+use std::time::SystemTime;
+
+use anyhow::Result;
+use bytes::Bytes;
+use serde::Serialize;
+use spn_artifact_types::ArtifactType;
+use tracing::info;
+use url::Url;
+
+use crate::{
+    backend_for, get_s3_key, sha256_hex, with_retry, Artifact, ArtifactError, ArtifactErrorKind,
+    BackendError,
+};
+
+/// How the ID of a content-addressed program artifact is derived.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProgramKey<'a> {
+    /// The sha256 digest of the serialized program.
+    Sha256,
+    /// The hash of the verifying key of the program, so it can be found again with
+    /// [`find_program_by_vk_hash`].
+    ///
+    /// The hash is taken as given and not checked against the program, so the destination must
+    /// only be writable by trusted uploaders. Otherwise, verify the verifying key of programs found
+    /// by their hash before using them.
+    VkHash(&'a [u8]),
+}
+
+/// A content-addressed program artifact.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProgramArtifact {
+    /// The artifact, whose ID is derived from the program.
+    pub artifact: Artifact,
+    /// The URI of the artifact.
+    pub uri: String,
+    /// Whether the artifact was uploaded, rather than found already stored.
+    pub uploaded: bool,
+}
+
+/// The ID of the content-addressed program artifact with the serialized bytes `program`.
+#[must_use]
+pub fn program_artifact_id(program: &[u8]) -> String {
+    format!("program_sha256_{}", sha256_hex(program))
+}
+
+/// The ID of the content-addressed program artifact with the verifying key hash `vk_hash`.
+#[must_use]
+pub fn vk_program_artifact_id(vk_hash: &[u8]) -> String {
+    format!("program_vk_{}", hex::encode(vk_hash))
+}
+
+/// The program artifact `id` under `destination`, and the URI it is uploaded to.
+fn program_artifact(destination: &str, id: String) -> Result<(Artifact, Url), ArtifactError> {
+    let artifact = Artifact { id, label: "program".to_string(), expiry: None };
+    let uri = program_uri(&artifact, destination)
+        .map_err(|e| ArtifactError::new(&artifact.id, ArtifactType::Program, e))?;
+    Ok((artifact, uri))
+}
+
+/// The URI of the program `artifact` under `destination`.
+///
+/// The URI is resolved like uploads resolve it, so programs are looked up where they are written.
+/// S3 uploads always go to the prefix of programs at the root of the bucket, so S3 destinations
+/// with a path are rejected rather than silently ignored.
+fn program_uri(artifact: &Artifact, destination: &str) -> Result<Url> {
+    let parsed = Url::parse(destination).map_err(|e| {
+        let message = format!("Failed to parse URI {destination}: {e}");
+        BackendError::new(ArtifactErrorKind::NotFound, message)
+    })?;
+    if parsed.scheme() == "s3" && !matches!(parsed.path(), "" | "/") {
+        let message = format!("S3 program destination {destination} must be a bucket");
+        return Err(BackendError::new(ArtifactErrorKind::NotFound, message).into());
+    }
+    let uri = format!(
+        "{}/{}",
+        destination.trim_end_matches('/'),
+        get_s3_key(ArtifactType::Program, &artifact.id)
+    );
+    artifact.resolve_uri(&uri, ArtifactType::Program)
+}
+
+/// Whether the artifact at `uri` is stored and not expired.
+async fn is_stored(artifact: &Artifact, uri: &Url, s3_region: &str) -> Result<bool, ArtifactError> {
+    let result: Result<bool> = async {
+        let backend = backend_for(uri, s3_region)?;
+        let metadata = with_retry("head artifact", || backend.head(uri)).await?;
+        Ok(metadata.is_some_and(|metadata| !metadata.is_expired(SystemTime::now(), None)))
+    }
+    .await;
+    result.map_err(|e| ArtifactError::new(&artifact.id, ArtifactType::Program, e))
+}
+
+/// Upload `program` as a content-addressed program artifact under `destination`, such as
+/// `s3://bucket`, unless it is already stored there.
+///
+/// The program is serialized with bincode and stored under the prefix of programs, with an ID
+/// derived from `key`, so identical programs share one artifact and the caches of provers hit
+/// across requests. Content-addressed programs are shared, so they never expire. S3 destinations
+/// must name a bucket without a path.
+pub async fn upload_program_deduplicated<T: Serialize>(
+    program: &T,
+    destination: &str,
+    s3_region: &str,
+    key: ProgramKey<'_>,
+) -> Result<ProgramArtifact, ArtifactError> {
+    let data =
+        bincode::serialize(program).map(Bytes::from).map_err(|e| ArtifactError::Corrupt {
+            id: "program".to_string(),
+            artifact_type: ArtifactType::Program,
+            source: anyhow::Error::new(e).context("Failed to serialize data"),
+        })?;
+    let id = match key {
+        ProgramKey::Sha256 => program_artifact_id(&data),
+        ProgramKey::VkHash(vk_hash) => vk_program_artifact_id(vk_hash),
+    };
+    let (artifact, uri) = program_artifact(destination, id)?;
+
+    if is_stored(&artifact, &uri, s3_region).await? {
+        info!(%uri, "program artifact already stored, skipping upload");
+        return Ok(ProgramArtifact { artifact, uri: uri.to_string(), uploaded: false });
+    }
+    artifact.upload_raw_to_uri(data, uri.as_str(), s3_region, ArtifactType::Program).await?;
+    info!(%uri, "uploaded program artifact");
+    Ok(ProgramArtifact { artifact, uri: uri.to_string(), uploaded: true })
+}
+
+/// The program artifact uploaded under `destination` with [`ProgramKey::VkHash`] for `vk_hash`,
+/// if it is stored.
+///
+/// The program is whatever was uploaded under `vk_hash`, which is only trustworthy if everyone who
+/// can write to `destination` is.
+pub async fn find_program_by_vk_hash(
+    destination: &str,
+    s3_region: &str,
+    vk_hash: &[u8],
+) -> Result<Option<ProgramArtifact>, ArtifactError> {
+    let (artifact, uri) = program_artifact(destination, vk_program_artifact_id(vk_hash))?;
+    let stored = is_stored(&artifact, &uri, s3_region).await?;
+    Ok(stored.then(|| ProgramArtifact { artifact, uri: uri.to_string(), uploaded: false }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MemoryBackend;
+
+    #[tokio::test]
+    async fn test_deduplicates_programs() {
+        MemoryBackend::register();
+        let elf = vec![7u8; 64];
+        let destination = "memory://content-bucket";
+
+        let first =
+            upload_program_deduplicated(&elf, destination, "", ProgramKey::Sha256).await.unwrap();
+        assert!(first.uploaded);
+        let id = program_artifact_id(&bincode::serialize(&elf).unwrap());
+        assert_eq!(first.uri, format!("{destination}/programs/{id}"));
+        let second =
+            upload_program_deduplicated(&elf, destination, "", ProgramKey::Sha256).await.unwrap();
+        assert_eq!(second, ProgramArtifact { uploaded: false, ..first.clone() });
+
+        let downloaded: Vec<u8> =
+            first.artifact.download_program_from_uri(&first.uri, "").await.unwrap();
+        assert_eq!(downloaded, elf);
+        let stored = MemoryBackend::shared().uris();
+        assert_eq!(stored.iter().filter(|uri| uri.starts_with(destination)).count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_finds_programs_by_vk_hash() {
+        MemoryBackend::register();
+        let destination = "memory://vk-bucket";
+        let vk_hash = [9u8; 32];
+        assert_eq!(find_program_by_vk_hash(destination, "", &vk_hash).await.unwrap(), None);
+
+        let uploaded = upload_program_deduplicated(
+            &vec![1u8; 8],
+            destination,
+            "",
+            ProgramKey::VkHash(&vk_hash),
+        )
+        .await
+        .unwrap();
+        let found = find_program_by_vk_hash(destination, "", &vk_hash).await.unwrap().unwrap();
+        assert_eq!(found, ProgramArtifact { uploaded: false, ..uploaded });
+        assert_eq!(found.artifact.id, vk_program_artifact_id(&vk_hash));
+    }
+
+    #[test]
+    fn test_resolves_program_uris_like_uploads() {
+        let id = program_artifact_id(b"elf");
+        let (_, uri) = program_artifact("s3://bucket/", id.clone()).unwrap();
+        assert_eq!(uri.as_str(), format!("s3://bucket/programs/{id}"));
+
+        // S3 uploads ignore the path of the destination, so it is rejected.
+        let err = program_artifact("s3://bucket/prefix", id).unwrap_err();
+        assert_eq!(err.kind(), ArtifactErrorKind::NotFound);
+    }
+}
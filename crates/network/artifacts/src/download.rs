@@ -0,0 +1,738 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        LazyLock, RwLock,
+    },
+    time::{Duration, SystemTime},
+};
+
+use anyhow::{anyhow, bail, Context, Result};
+use bytes::{Bytes, BytesMut};
+use futures::StreamExt;
+use spn_artifact_types::ArtifactType;
+use tokio::{
+    io::{AsyncSeekExt, AsyncWriteExt},
+    time::timeout,
+};
+use tracing::{debug, info, warn};
+use url::Url;
+
+use crate::{
+    artifact_retry_policy, check_expiry, decode, get_s3_prefix, is_transient, sha256_hex,
+    ArtifactBackend, ArtifactError, ArtifactErrorKind, ArtifactMetadata, BackendError,
+    ContentEncoding,
+};
+
+/// The download configuration used by [`crate::Artifact`].
+static DOWNLOAD_CONFIG: LazyLock<RwLock<DownloadConfig>> =
+    LazyLock::new(|| RwLock::new(DownloadConfig::default()));
+
+/// A counter that keeps the names of concurrent downloads apart.
+static TEMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// One mebibyte.
+const MIB: u64 = 1024 * 1024;
+
+/// How artifacts are downloaded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DownloadConfig {
+    /// The maximum size of each type of artifact, in bytes. Types without a limit are unlimited.
+    pub max_sizes: HashMap<ArtifactType, u64>,
+    /// The size above which the progress of a download is logged.
+    pub progress_threshold: u64,
+    /// How long a download may go without receiving any data before it is retried, and how long
+    /// the metadata of an artifact is waited for before downloading it without.
+    pub idle_timeout: Duration,
+    /// The directory downloaded artifacts are cached in, or `None` to keep them in memory only.
+    ///
+    /// Artifacts are written there as they are received, under the prefix of their type and named
+    /// after the digest of their full URI, and moved into place once they are verified, with their
+    /// digest, encoding and expiry in a `.meta` file beside them. Later downloads of the same
+    /// artifact are served from the cache, after checking the cached bytes against the recorded
+    /// digest again and the artifact against its recorded expiry.
+    pub cache_dir: Option<PathBuf>,
+}
+
+impl Default for DownloadConfig {
+    fn default() -> Self {
+        Self {
+            max_sizes: HashMap::from([
+                (ArtifactType::UnspecifiedArtifactType, 1024 * MIB),
+                (ArtifactType::Program, 256 * MIB),
+                (ArtifactType::Stdin, 1024 * MIB),
+                (ArtifactType::Proof, 1024 * MIB),
+                (ArtifactType::Transaction, 16 * MIB),
+            ]),
+            progress_threshold: 16 * MIB,
+            idle_timeout: Duration::from_secs(60),
+            cache_dir: None,
+        }
+    }
+}
+
+impl DownloadConfig {
+    /// Limit artifacts of `artifact_type` to `max_size` bytes.
+    #[must_use]
+    pub fn with_max_size(mut self, artifact_type: ArtifactType, max_size: u64) -> Self {
+        self.max_sizes.insert(artifact_type, max_size);
+        self
+    }
+
+    /// Retry downloads that receive no data for `idle_timeout`.
+    #[must_use]
+    pub fn with_idle_timeout(mut self, idle_timeout: Duration) -> Self {
+        self.idle_timeout = idle_timeout;
+        self
+    }
+
+    /// Cache downloaded artifacts in `dir`.
+    #[must_use]
+    pub fn with_cache_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.cache_dir = Some(dir.into());
+        self
+    }
+
+    /// The maximum size of artifacts of `artifact_type`, if any.
+    #[must_use]
+    pub fn max_size(&self, artifact_type: ArtifactType) -> Option<u64> {
+        self.max_sizes.get(&artifact_type).copied()
+    }
+}
+
+/// Set the download configuration used by [`crate::Artifact`].
+pub fn set_download_config(config: DownloadConfig) {
+    *DOWNLOAD_CONFIG.write().unwrap() = config;
+}
+
+/// The download configuration used by [`crate::Artifact`].
+#[must_use]
+pub fn download_config() -> DownloadConfig {
+    DOWNLOAD_CONFIG.read().unwrap().clone()
+}
+
+/// Download the artifact `id` of `artifact_type` at `uri` from `backend`.
+///
+/// The size announced by the backend, first through its metadata and then with the body, is
+/// checked against the limit of the artifact type, and the download is aborted as soon as more
+/// bytes than the limit are received. Interrupted or idle downloads are retried with the shared
+/// [`crate::ArtifactRetryPolicy`], resuming after the bytes already received. The stored bytes are then
+/// checked against their recorded digest and decompressed with [`decode`]. When a cache directory
+/// is configured, the artifact is read from the cache if present and intact, and otherwise written
+/// to it as it is received and only kept once it decodes. Failures are categorized into an
+/// [`ArtifactError`], for example [`ArtifactError::Expired`] for artifacts past their recorded
+/// expiry.
+pub async fn download(
+    backend: &dyn ArtifactBackend,
+    uri: &Url,
+    artifact_type: ArtifactType,
+    id: &str,
+    config: &DownloadConfig,
+) -> Result<Bytes, ArtifactError> {
+    try_download(backend, uri, artifact_type, id, config)
+        .await
+        .map_err(|e| ArtifactError::new(id, artifact_type, e))
+}
+
+/// [`download`], with the failure left uncategorized.
+async fn try_download(
+    backend: &dyn ArtifactBackend,
+    uri: &Url,
+    artifact_type: ArtifactType,
+    id: &str,
+    config: &DownloadConfig,
+) -> Result<Bytes> {
+    let max_size = config.max_size(artifact_type);
+    let cache_path = config.cache_dir.as_ref().map(|dir| cache_path(dir, artifact_type, uri));
+    if let Some(path) = &cache_path {
+        if let Some((data, metadata)) = read_cached(path, max_size).await? {
+            if let Err(e) = check_expiry(id, metadata.expires_at, SystemTime::now()) {
+                remove_cached(path).await;
+                return Err(e.into());
+            }
+            match decode(data, &metadata, max_size) {
+                Ok(data) => {
+                    debug!(path = %path.display(), "serving artifact from cache");
+                    return Ok(data);
+                }
+                Err(e) => {
+                    warn!(path = %path.display(), "discarding cached artifact: {e:#}");
+                    remove_cached(path).await;
+                }
+            }
+        }
+    }
+
+    // The metadata is only advisory: presigned URLs, for example, may not allow HEAD requests.
+    let idle_timeout = config.idle_timeout;
+    let head = timeout(idle_timeout, backend.head(uri)).await.unwrap_or_else(|_| {
+        Err(anyhow!("Timed out getting the metadata of {uri} after {idle_timeout:?}"))
+    });
+    let mut metadata = match head {
+        Ok(Some(metadata)) => {
+            if let Some(size) = metadata.size {
+                check_size(uri, size, max_size)?;
+            }
+            check_expiry(id, metadata.expires_at, SystemTime::now())?;
+            metadata
+        }
+        Ok(None) => return Err(BackendError::not_found(uri).into()),
+        Err(e) => {
+            debug!(%uri, "failed to get artifact metadata: {e:#}");
+            ArtifactMetadata::default()
+        }
+    };
+
+    let mut sink = match &cache_path {
+        Some(path) => Sink::file(path).await?,
+        None => Sink::Memory(BytesMut::with_capacity(
+            metadata.size.unwrap_or_default().min(max_size.unwrap_or(u64::MAX)) as usize,
+        )),
+    };
+    let mut progress = Progress::new(uri, config.progress_threshold);
+    let policy = artifact_retry_policy();
+    let mut attempt = 1;
+    loop {
+        let result = receive(
+            backend,
+            uri,
+            &mut sink,
+            &mut progress,
+            &mut metadata,
+            max_size,
+            config.idle_timeout,
+        )
+        .await;
+        match result {
+            Ok(()) => break,
+            Err(e) if attempt < policy.max_attempts && is_transient(&e) => {
+                let delay = policy.delay(attempt);
+                warn!(
+                    %uri,
+                    attempt,
+                    received = progress.received,
+                    ?delay,
+                    "resuming artifact download: {e:#}"
+                );
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    progress.finish();
+
+    let verify = |data| {
+        decode(data, &metadata, max_size)
+            .with_context(|| format!("Failed to decode artifact {uri}"))
+    };
+    match (sink, cache_path) {
+        (Sink::Memory(data), _) => verify(data.freeze()),
+        (Sink::File(mut temp), Some(path)) => {
+            // Only verified artifacts are moved into the cache, along with their digest.
+            let data = temp.read().await?;
+            let cached = ArtifactMetadata {
+                sha256: Some(sha256_hex(&data)),
+                encoding: metadata.encoding,
+                expires_at: metadata.expires_at,
+                ..Default::default()
+            };
+            let payload = verify(data)?;
+            write_cache_metadata(&path, &cached).await?;
+            temp.persist(&path).await?;
+            Ok(payload)
+        }
+        (Sink::File(_), None) => unreachable!("artifacts are only written to the cache"),
+    }
+}
+
+/// Receive the rest of an artifact into `sink`, starting after the bytes received so far.
+async fn receive(
+    backend: &dyn ArtifactBackend,
+    uri: &Url,
+    sink: &mut Sink,
+    progress: &mut Progress<'_>,
+    metadata: &mut ArtifactMetadata,
+    max_size: Option<u64>,
+    idle_timeout: Duration,
+) -> Result<()> {
+    let mut offset = progress.received;
+    let mut etag = metadata.etag.clone();
+    let mut stream = timeout(idle_timeout, backend.get_stream_from(uri, offset, etag.as_deref()))
+        .await
+        .map_err(|_| anyhow!("Timed out opening {uri} after {idle_timeout:?}"))??;
+    if stream.offset < offset {
+        // The artifact changed since the first bytes were received, so they are dropped and the
+        // download restarts with the new artifact, along with its metadata.
+        warn!(%uri, received = offset, "artifact changed during download, restarting it");
+        sink.clear().await?;
+        progress.restart();
+        offset = stream.offset;
+        etag = None;
+        *metadata = ArtifactMetadata::default();
+    }
+    let size = stream.size.map(|size| offset + size);
+    if let Some(size) = size {
+        check_size(uri, size, max_size)?;
+        progress.start(size);
+    }
+    metadata.sha256 = stream.metadata.sha256.take().or(metadata.sha256.take());
+    metadata.encoding = stream.metadata.encoding.or(metadata.encoding);
+    metadata.expires_at = stream.metadata.expires_at.or(metadata.expires_at);
+    // Resumed downloads must come from the artifact that the first bytes came from.
+    metadata.etag = etag.or(stream.metadata.etag.take());
+    check_expiry(uri.as_str(), metadata.expires_at, SystemTime::now())?;
+
+    loop {
+        let chunk = match timeout(idle_timeout, stream.body.next()).await {
+            Ok(Some(chunk)) => chunk?,
+            Ok(None) => break,
+            Err(_) => bail!("No data received from {uri} for {idle_timeout:?}"),
+        };
+        progress.received += chunk.len() as u64;
+        check_size(uri, progress.received, max_size)?;
+        sink.write(&chunk).await?;
+        progress.report();
+    }
+    if let Some(size) = size.filter(|&size| progress.received < size) {
+        bail!("Download of {uri} ended after {} of {size} bytes", progress.received);
+    }
+    Ok(())
+}
+
+/// Fail if `size` is over `max_size`.
+fn check_size(uri: &Url, size: u64, max_size: Option<u64>) -> Result<()> {
+    match max_size {
+        Some(max_size) if size > max_size => {
+            let message = format!("Artifact {uri} is larger than the limit of {max_size} bytes");
+            Err(BackendError::new(ArtifactErrorKind::TooLarge, message).into())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// The path of the metadata of the cached artifact at `path`.
+pub(crate) fn cache_metadata_path(path: &Path) -> PathBuf {
+    let mut meta = path.as_os_str().to_owned();
+    meta.push(".meta");
+    PathBuf::from(meta)
+}
+
+/// A unique temporary path next to `path`.
+fn temp_path(path: &Path) -> PathBuf {
+    let mut temp = path.as_os_str().to_owned();
+    temp.push(format!(
+        ".{}.{}.part",
+        std::process::id(),
+        TEMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    PathBuf::from(temp)
+}
+
+/// Where the artifact of `artifact_type` at `uri` is cached in `dir`.
+///
+/// Artifacts are named after the digest of their full URI rather than their ID, which is only the
+/// last segment of the URI, so that artifacts of different requesters never share an entry.
+pub(crate) fn cache_path(dir: &Path, artifact_type: ArtifactType, uri: &Url) -> PathBuf {
+    dir.join(get_s3_prefix(artifact_type)).join(sha256_hex(uri.as_str().as_bytes()))
+}
+
+/// Record the digest, encoding and expiry of the cached artifact at `path`, one `key=value` per
+/// line.
+pub(crate) async fn write_cache_metadata(path: &Path, metadata: &ArtifactMetadata) -> Result<()> {
+    let mut contents = String::new();
+    if let Some(sha256) = &metadata.sha256 {
+        contents.push_str(&format!("sha256={sha256}\n"));
+    }
+    if let Some(encoding) = metadata.encoding {
+        contents.push_str(&format!("encoding={}\n", encoding.as_str()));
+    }
+    if let Some(expires_at) = metadata.expires_at {
+        contents.push_str(&format!("expires_at={expires_at}\n"));
+    }
+    let meta = cache_metadata_path(path);
+    let temp = temp_path(&meta);
+    tokio::fs::write(&temp, contents)
+        .await
+        .with_context(|| format!("Failed to write {}", temp.display()))?;
+    tokio::fs::rename(&temp, &meta)
+        .await
+        .with_context(|| format!("Failed to move artifact metadata into {}", meta.display()))
+}
+
+/// Parse the metadata of a cached artifact, if it records a digest.
+pub(crate) fn parse_cache_metadata(contents: &str) -> Option<ArtifactMetadata> {
+    let mut metadata = ArtifactMetadata::default();
+    for line in contents.lines() {
+        match line.split_once('=')? {
+            ("sha256", sha256) => metadata.sha256 = Some(sha256.to_string()),
+            ("encoding", name) => metadata.encoding = Some(ContentEncoding::from_name(name)?),
+            ("expires_at", expires_at) => metadata.expires_at = Some(expires_at.parse().ok()?),
+            _ => {}
+        }
+    }
+    metadata.sha256.is_some().then_some(metadata)
+}
+
+/// Read the cached artifact at `path` and its metadata, if both are present and the artifact is
+/// within `max_size`.
+async fn read_cached(
+    path: &Path,
+    max_size: Option<u64>,
+) -> Result<Option<(Bytes, ArtifactMetadata)>> {
+    let contents = match tokio::fs::read_to_string(cache_metadata_path(path)).await {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e).context("Failed to read cached artifact metadata"),
+    };
+    let Some(metadata) = parse_cache_metadata(&contents) else {
+        return Ok(None);
+    };
+    let size = match tokio::fs::metadata(path).await {
+        Ok(file) => file.len(),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e).context("Failed to stat cached artifact"),
+    };
+    if max_size.is_some_and(|max_size| size > max_size) {
+        return Ok(None);
+    }
+    let data = tokio::fs::read(path).await.context("Failed to read cached artifact")?;
+    Ok(Some((Bytes::from(data), metadata)))
+}
+
+/// Remove the cached artifact at `path` and its metadata.
+async fn remove_cached(path: &Path) {
+    let _ = tokio::fs::remove_file(cache_metadata_path(path)).await;
+    let _ = tokio::fs::remove_file(path).await;
+}
+
+/// Where a download is written to.
+enum Sink {
+    /// A buffer in memory.
+    Memory(BytesMut),
+    /// A temporary file in the cache.
+    File(TempFile),
+}
+
+impl Sink {
+    /// Create a temporary file next to `path`.
+    async fn file(path: &Path) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await.context("Failed to create cache directory")?;
+        }
+        let path = temp_path(path);
+        let file = tokio::fs::File::create(&path)
+            .await
+            .with_context(|| format!("Failed to create {}", path.display()))?;
+        Ok(Self::File(TempFile { file, path, persisted: false }))
+    }
+
+    /// Drop everything written so far.
+    async fn clear(&mut self) -> Result<()> {
+        match self {
+            Self::Memory(data) => data.clear(),
+            Self::File(temp) => {
+                let file = &mut temp.file;
+                let cleared = async {
+                    file.flush().await?;
+                    file.set_len(0).await?;
+                    file.rewind().await?;
+                    Ok::<_, std::io::Error>(())
+                };
+                cleared
+                    .await
+                    .with_context(|| format!("Failed to clear {}", temp.path.display()))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Write a chunk of the download.
+    async fn write(&mut self, chunk: &[u8]) -> Result<()> {
+        match self {
+            Self::Memory(data) => data.extend_from_slice(chunk),
+            Self::File(temp) => temp
+                .file
+                .write_all(chunk)
+                .await
+                .with_context(|| format!("Failed to write {}", temp.path.display()))?,
+        }
+        Ok(())
+    }
+}
+
+/// A temporary file that is removed when dropped, unless it was persisted.
+struct TempFile {
+    /// The open file.
+    file: tokio::fs::File,
+    /// The path of the file.
+    path: PathBuf,
+    /// Whether the file was moved into place.
+    persisted: bool,
+}
+
+impl TempFile {
+    /// Flush the file and read it back.
+    async fn read(&mut self) -> Result<Bytes> {
+        self.file
+            .flush()
+            .await
+            .with_context(|| format!("Failed to write {}", self.path.display()))?;
+        let data = tokio::fs::read(&self.path)
+            .await
+            .with_context(|| format!("Failed to read {}", self.path.display()))?;
+        Ok(Bytes::from(data))
+    }
+
+    /// Move the file to `path`.
+    async fn persist(mut self, path: &Path) -> Result<()> {
+        tokio::fs::rename(&self.path, path)
+            .await
+            .with_context(|| format!("Failed to move artifact into {}", path.display()))?;
+        self.persisted = true;
+        Ok(())
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        // Clean up downloads that were aborted.
+        if !self.persisted {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+}
+
+/// The progress of a large download, logged every tenth of the way.
+struct Progress<'a> {
+    /// The URI being downloaded.
+    uri: &'a Url,
+    /// The size above which downloads are logged.
+    threshold: u64,
+    /// The announced size, if it is over the threshold.
+    size: Option<u64>,
+    /// The number of bytes received so far.
+    received: u64,
+    /// The last tenth that was logged.
+    reported: u64,
+}
+
+impl<'a> Progress<'a> {
+    /// Track a download that is only logged above `threshold`.
+    fn new(uri: &'a Url, threshold: u64) -> Self {
+        Self { uri, threshold, size: None, received: 0, reported: 0 }
+    }
+
+    /// Record the announced size of the download.
+    fn start(&mut self, size: u64) {
+        if self.size.is_none() && size > self.threshold {
+            info!(uri = %self.uri, size, "downloading large artifact");
+            self.size = Some(size);
+        }
+    }
+
+    /// Start over, after the bytes received so far were dropped.
+    fn restart(&mut self) {
+        *self = Self::new(self.uri, self.threshold);
+    }
+
+    /// Log the progress if another tenth was received.
+    fn report(&mut self) {
+        let Some(size) = self.size else {
+            return;
+        };
+        let tenth = self.received * 10 / size.max(1);
+        if tenth > self.reported && tenth < 10 {
+            self.reported = tenth;
+            info!(uri = %self.uri, received = self.received, size, "downloaded {}%", tenth * 10);
+        }
+    }
+
+    /// Log the end of the download.
+    fn finish(&self) {
+        if self.size.is_some() {
+            info!(uri = %self.uri, size = self.received, "downloaded large artifact");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_trait::async_trait;
+    use futures::stream;
+
+    use super::*;
+    use crate::{encode, ArtifactStream, MemoryBackend, UploadConfig};
+
+    /// A backend that streams `chunks` chunks of 1 KiB without announcing a size.
+    #[derive(Debug)]
+    struct Unannounced {
+        chunks: usize,
+    }
+
+    #[async_trait]
+    impl ArtifactBackend for Unannounced {
+        async fn get(&self, _uri: &Url) -> Result<Bytes> {
+            unimplemented!()
+        }
+
+        async fn get_stream(&self, _uri: &Url) -> Result<ArtifactStream> {
+            let body = stream::iter((0..self.chunks).map(|_| Ok(Bytes::from(vec![0; 1024]))));
+            Ok(ArtifactStream {
+                offset: 0,
+                size: None,
+                metadata: ArtifactMetadata::default(),
+                body: body.boxed(),
+            })
+        }
+
+        async fn put(&self, _uri: &Url, _data: Bytes) -> Result<()> {
+            unimplemented!()
+        }
+
+        async fn head(&self, _uri: &Url) -> Result<Option<ArtifactMetadata>> {
+            Err(anyhow!("HEAD is not allowed"))
+        }
+
+        async fn delete(&self, _uri: &Url) -> Result<()> {
+            unimplemented!()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rejects_announced_size_over_limit() {
+        let backend = MemoryBackend::default();
+        let uri = Url::parse("memory://test/stdins/large").unwrap();
+        backend.put(&uri, Bytes::from(vec![0; 2048])).await.unwrap();
+
+        let config = DownloadConfig::default().with_max_size(ArtifactType::Stdin, 1024);
+        let result = download(&backend, &uri, ArtifactType::Stdin, "large", &config).await;
+        assert_eq!(result.unwrap_err().kind(), ArtifactErrorKind::TooLarge);
+
+        let config = DownloadConfig::default().with_max_size(ArtifactType::Stdin, 2048);
+        let data = download(&backend, &uri, ArtifactType::Stdin, "large", &config).await.unwrap();
+        assert_eq!(data.len(), 2048);
+    }
+
+    #[tokio::test]
+    async fn test_aborts_stream_over_limit() {
+        let uri = Url::parse("https://example.com/program").unwrap();
+        let config = DownloadConfig::default().with_max_size(ArtifactType::Program, 10 * 1024);
+
+        let ok = Unannounced { chunks: 10 };
+        let data = download(&ok, &uri, ArtifactType::Program, "program", &config).await.unwrap();
+        assert_eq!(data.len(), 10 * 1024);
+
+        let too_large = Unannounced { chunks: 11 };
+        let result = download(&too_large, &uri, ArtifactType::Program, "program", &config).await;
+        assert_eq!(result.unwrap_err().kind(), ArtifactErrorKind::TooLarge);
+    }
+
+    #[tokio::test]
+    async fn test_checks_digest_and_decompresses() {
+        let backend = MemoryBackend::default();
+        let uri = Url::parse("memory://test/proofs/compressed").unwrap();
+        let payload = Bytes::from(vec![3; 10_000]);
+        let config = UploadConfig { compression_threshold: Some(0), ..Default::default() };
+        let (stored, metadata) = encode(payload.clone(), &config).unwrap();
+        backend.put_with_metadata(&uri, stored.clone(), &metadata).await.unwrap();
+
+        let config = DownloadConfig::default();
+        let data = download(&backend, &uri, ArtifactType::Proof, "compressed", &config).await;
+        assert_eq!(data.unwrap(), payload);
+
+        // A corrupted artifact fails the digest check.
+        let corrupted = Bytes::from([&stored[..stored.len() - 1], &[0]].concat());
+        backend.put_with_metadata(&uri, corrupted, &metadata).await.unwrap();
+        let result = download(&backend, &uri, ArtifactType::Proof, "compressed", &config).await;
+        assert!(matches!(result, Err(ArtifactError::Corrupt { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_caches_downloads() {
+        let dir = std::env::temp_dir().join(format!("spn-artifacts-cache-{}", std::process::id()));
+        let config = DownloadConfig::default().with_cache_dir(&dir);
+        let uri = Url::parse("https://example.com/program").unwrap();
+
+        let data = download(&Unannounced { chunks: 3 }, &uri, ArtifactType::Program, "p", &config)
+            .await
+            .unwrap();
+        assert_eq!(data.len(), 3 * 1024);
+        let path = cache_path(&dir, ArtifactType::Program, &uri);
+        assert!(path.starts_with(dir.join("programs")));
+        assert_eq!(std::fs::read(&path).unwrap().len(), 3 * 1024);
+
+        // The second download is served from the cache.
+        let data = download(&Unannounced { chunks: 5 }, &uri, ArtifactType::Program, "p", &config)
+            .await
+            .unwrap();
+        assert_eq!(data.len(), 3 * 1024);
+
+        // A tampered cache entry fails its recorded digest and is downloaded again.
+        std::fs::write(&path, vec![1; 3 * 1024]).unwrap();
+        let data = download(&Unannounced { chunks: 4 }, &uri, ArtifactType::Program, "p", &config)
+            .await
+            .unwrap();
+        assert_eq!(data, vec![0; 4 * 1024]);
+        assert_eq!(std::fs::read(&path).unwrap(), vec![0; 4 * 1024]);
+
+        // An artifact with the same ID at another URI is not served from the same entry.
+        let other = Url::parse("https://other.example.com/program").unwrap();
+        let data =
+            download(&Unannounced { chunks: 2 }, &other, ArtifactType::Program, "p", &config)
+                .await
+                .unwrap();
+        assert_eq!(data.len(), 2 * 1024);
+        assert_ne!(cache_path(&dir, ArtifactType::Program, &other), path);
+        assert_eq!(std::fs::read(&path).unwrap().len(), 4 * 1024);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_cache_hits_check_expiry() {
+        let dir =
+            std::env::temp_dir().join(format!("spn-artifacts-cache-expiry-{}", std::process::id()));
+        let config = DownloadConfig::default().with_cache_dir(&dir);
+        let backend = MemoryBackend::default();
+        let uri = Url::parse("memory://test/stdins/expiring").unwrap();
+        let (stored, mut metadata) =
+            encode(Bytes::from(vec![4; 100]), &UploadConfig::default()).unwrap();
+        let now = SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs();
+        metadata.expires_at = Some(now + 3600);
+        backend.put_with_metadata(&uri, stored, &metadata).await.unwrap();
+        download(&backend, &uri, ArtifactType::Stdin, "expiring", &config).await.unwrap();
+
+        // The cached copy outlives its expiry, but is not served past it.
+        let path = cache_path(&dir, ArtifactType::Stdin, &uri);
+        let meta = cache_metadata_path(&path);
+        let contents = std::fs::read_to_string(&meta).unwrap();
+        let expires_at = metadata.expires_at.unwrap();
+        std::fs::write(&meta, contents.replace(&expires_at.to_string(), "1")).unwrap();
+        let result = download(&backend, &uri, ArtifactType::Stdin, "expiring", &config).await;
+        assert!(matches!(result, Err(ArtifactError::Expired { .. })));
+        assert!(!path.exists());
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn test_does_not_cache_corrupt_downloads() {
+        let dir =
+            std::env::temp_dir().join(format!("spn-artifacts-corrupt-{}", std::process::id()));
+        let config = DownloadConfig::default().with_cache_dir(&dir);
+        let backend = MemoryBackend::default();
+        let uri = Url::parse("memory://test/proofs/corrupt").unwrap();
+        let (stored, metadata) =
+            encode(Bytes::from(vec![5; 100]), &UploadConfig::default()).unwrap();
+        backend.put_with_metadata(&uri, Bytes::from(vec![6; 100]), &metadata).await.unwrap();
+        assert_ne!(stored, Bytes::from(vec![6; 100]));
+
+        let result = download(&backend, &uri, ArtifactType::Proof, "corrupt", &config).await;
+        assert!(matches!(result, Err(ArtifactError::Corrupt { .. })));
+        assert!(!cache_path(&dir, ArtifactType::Proof, &uri).exists());
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,219 @@
+use std::fmt;
+
+use anyhow::{anyhow, Context, Result};
+use bytes::{BufMut, Bytes, BytesMut};
+use chacha20poly1305::{
+    aead::{Aead, KeyInit, OsRng, Payload},
+    ChaCha20Poly1305, Key, Nonce,
+};
+use hkdf::Hkdf;
+use sha2::Sha256;
+use x25519_dalek::{PublicKey, StaticSecret};
+
+use crate::{ArtifactErrorKind, BackendError};
+
+/// The bytes every encrypted artifact starts with, including the version of the envelope format.
+const ENVELOPE_MAGIC: [u8; 8] = *b"SPNENC\x00\x01";
+
+/// The context the key of an envelope is derived with.
+const KDF_INFO: &[u8] = b"spn-artifacts envelope v1";
+
+/// The size of an X25519 key.
+const KEY_SIZE: usize = 32;
+
+/// The size of the header of an envelope: the magic bytes and the ephemeral public key.
+const HEADER_SIZE: usize = ENVELOPE_MAGIC.len() + KEY_SIZE;
+
+/// Whether `data` is an encrypted artifact.
+#[must_use]
+pub fn is_encrypted(data: &[u8]) -> bool {
+    data.starts_with(&ENVELOPE_MAGIC)
+}
+
+/// Parse a hex-encoded 32 byte key, with or without a `0x` prefix.
+fn parse_key(hex_key: &str) -> Result<[u8; KEY_SIZE]> {
+    let bytes = hex::decode(hex_key.trim_start_matches("0x")).context("Invalid hex key")?;
+    bytes.try_into().map_err(|bytes: Vec<u8>| {
+        anyhow!("Invalid key length: expected {KEY_SIZE} bytes, got {}", bytes.len())
+    })
+}
+
+/// The AEAD cipher of an envelope, keyed by the X25519 agreement between its ephemeral key and the
+/// recipient key.
+///
+/// Every envelope has a fresh ephemeral key, so every key is only used once and the nonce can be
+/// fixed.
+fn cipher(
+    shared_secret: &[u8; KEY_SIZE],
+    header: &[u8],
+    recipient: &PublicKey,
+) -> ChaCha20Poly1305 {
+    let salt = [header, recipient.as_bytes()].concat();
+    let mut key = Key::default();
+    Hkdf::<Sha256>::new(Some(&salt), shared_secret)
+        .expand(KDF_INFO, &mut key)
+        .expect("the key is shorter than the maximum HKDF output");
+    ChaCha20Poly1305::new(&key)
+}
+
+/// The public key artifacts are encrypted to, such as the key of a prover.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ArtifactPublicKey(PublicKey);
+
+impl ArtifactPublicKey {
+    /// The key with the raw bytes `bytes`.
+    #[must_use]
+    pub fn from_bytes(bytes: [u8; KEY_SIZE]) -> Self {
+        Self(PublicKey::from(bytes))
+    }
+
+    /// The key with the hex-encoded bytes `hex_key`.
+    pub fn from_hex(hex_key: &str) -> Result<Self> {
+        parse_key(hex_key).map(Self::from_bytes)
+    }
+
+    /// The raw bytes of the key.
+    #[must_use]
+    pub fn to_bytes(&self) -> [u8; KEY_SIZE] {
+        self.0.to_bytes()
+    }
+
+    /// Encrypt `plaintext` so that only the holder of the matching [`ArtifactSecretKey`] can
+    /// decrypt it.
+    ///
+    /// The envelope holds a fresh ephemeral X25519 public key and the plaintext sealed with
+    /// ChaCha20-Poly1305, under a key derived with HKDF-SHA256 from the agreement between the
+    /// ephemeral key and this key.
+    ///
+    /// # Panics
+    ///
+    /// Panics for plaintexts over the 256 GiB limit of ChaCha20-Poly1305.
+    #[must_use]
+    pub fn encrypt(&self, plaintext: &[u8]) -> Bytes {
+        let ephemeral = StaticSecret::random_from_rng(OsRng);
+        let shared_secret = ephemeral.diffie_hellman(&self.0);
+        let mut envelope = BytesMut::with_capacity(HEADER_SIZE + plaintext.len() + 16);
+        envelope.put_slice(&ENVELOPE_MAGIC);
+        envelope.put_slice(PublicKey::from(&ephemeral).as_bytes());
+
+        let cipher = cipher(shared_secret.as_bytes(), &envelope, &self.0);
+        let payload = Payload { msg: plaintext, aad: &envelope };
+        let ciphertext = cipher
+            .encrypt(&Nonce::default(), payload)
+            .expect("encryption only fails for oversized plaintexts");
+        envelope.put_slice(&ciphertext);
+        envelope.freeze()
+    }
+}
+
+impl fmt::Display for ArtifactPublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "0x{}", hex::encode(self.0.as_bytes()))
+    }
+}
+
+impl fmt::Debug for ArtifactPublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ArtifactPublicKey({self})")
+    }
+}
+
+/// The secret key encrypted artifacts are decrypted with, such as the key of a prover.
+#[derive(Clone)]
+pub struct ArtifactSecretKey(StaticSecret);
+
+impl ArtifactSecretKey {
+    /// Generate a random key.
+    #[must_use]
+    pub fn generate() -> Self {
+        Self(StaticSecret::random_from_rng(OsRng))
+    }
+
+    /// The key with the raw bytes `bytes`.
+    #[must_use]
+    pub fn from_bytes(bytes: [u8; KEY_SIZE]) -> Self {
+        Self(StaticSecret::from(bytes))
+    }
+
+    /// The key with the hex-encoded bytes `hex_key`.
+    pub fn from_hex(hex_key: &str) -> Result<Self> {
+        parse_key(hex_key).map(Self::from_bytes)
+    }
+
+    /// The public key that artifacts are encrypted to for this key.
+    #[must_use]
+    pub fn public_key(&self) -> ArtifactPublicKey {
+        ArtifactPublicKey(PublicKey::from(&self.0))
+    }
+
+    /// Decrypt an envelope created by [`ArtifactPublicKey::encrypt`].
+    ///
+    /// Fails with [`ArtifactErrorKind::Corrupt`] for malformed envelopes and for envelopes that
+    /// fail authentication, because they were encrypted to another key or tampered with. Neither
+    /// is fixed by trying again.
+    pub fn decrypt(&self, envelope: &[u8]) -> Result<Bytes> {
+        if !is_encrypted(envelope) || envelope.len() < HEADER_SIZE {
+            let message = "Malformed encrypted artifact";
+            return Err(BackendError::new(ArtifactErrorKind::Corrupt, message).into());
+        }
+        let (header, ciphertext) = envelope.split_at(HEADER_SIZE);
+        let ephemeral: [u8; KEY_SIZE] = header[ENVELOPE_MAGIC.len()..].try_into()?;
+        let shared_secret = self.0.diffie_hellman(&PublicKey::from(ephemeral));
+
+        let cipher = cipher(shared_secret.as_bytes(), header, &PublicKey::from(&self.0));
+        let payload = Payload { msg: ciphertext, aad: header };
+        let plaintext = cipher.decrypt(&Nonce::default(), payload).map_err(|_| {
+            let message = format!(
+                "Failed to decrypt artifact: it is not encrypted to {} or was tampered with",
+                self.public_key()
+            );
+            BackendError::new(ArtifactErrorKind::Corrupt, message)
+        })?;
+        Ok(Bytes::from(plaintext))
+    }
+}
+
+impl fmt::Debug for ArtifactSecretKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // The secret itself is never printed.
+        f.debug_tuple("ArtifactSecretKey").field(&self.public_key()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_envelope_round_trip() {
+        let key = ArtifactSecretKey::generate();
+        let envelope = key.public_key().encrypt(b"private stdin");
+        assert!(is_encrypted(&envelope));
+        assert_eq!(key.decrypt(&envelope).unwrap(), Bytes::from_static(b"private stdin"));
+
+        // Envelopes are randomized, so equal plaintexts are not linkable.
+        assert_ne!(envelope, key.public_key().encrypt(b"private stdin"));
+
+        let parsed = ArtifactSecretKey::from_hex(&hex::encode(key.0.to_bytes())).unwrap();
+        assert_eq!(parsed.public_key(), key.public_key());
+        let public = ArtifactPublicKey::from_hex(&key.public_key().to_string()).unwrap();
+        assert_eq!(public, key.public_key());
+    }
+
+    #[test]
+    fn test_rejects_other_keys_and_tampering() {
+        let key = ArtifactSecretKey::generate();
+        let envelope = key.public_key().encrypt(b"private stdin");
+
+        let other = ArtifactSecretKey::generate().decrypt(&envelope).unwrap_err();
+        assert_eq!(ArtifactErrorKind::of(&other), ArtifactErrorKind::Corrupt);
+
+        let mut tampered = envelope.to_vec();
+        *tampered.last_mut().unwrap() ^= 1;
+        let tampered = key.decrypt(&tampered).unwrap_err();
+        assert_eq!(ArtifactErrorKind::of(&tampered), ArtifactErrorKind::Corrupt);
+
+        let truncated = key.decrypt(&envelope[..HEADER_SIZE - 1]).unwrap_err();
+        assert_eq!(ArtifactErrorKind::of(&truncated), ArtifactErrorKind::Corrupt);
+    }
+}
@@ -0,0 +1,237 @@
+use std::fmt::Display;
+
+use spn_artifact_types::ArtifactType;
+use thiserror::Error;
+
+use crate::ExpiredError;
+
+/// The category of an artifact failure, which decides whether retrying may fix it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ArtifactErrorKind {
+    /// The artifact does not exist, or its URI is invalid.
+    NotFound,
+    /// The store refused access to the artifact.
+    AccessDenied,
+    /// The artifact does not match its digest, or cannot be decoded.
+    Corrupt,
+    /// The artifact is larger than the limit of its type.
+    TooLarge,
+    /// The artifact is past its expiry.
+    Expired,
+    /// The transfer failed in a way that retrying may fix, such as a network error.
+    Transient,
+}
+
+impl ArtifactErrorKind {
+    /// Whether retrying may fix failures of this kind.
+    #[must_use]
+    pub fn is_transient(self) -> bool {
+        self == Self::Transient
+    }
+
+    /// The kind of the first categorized error in the chain of `error`.
+    ///
+    /// Errors without a [`BackendError`], [`ArtifactError`] or [`ExpiredError`] in their chain,
+    /// such as I/O and network errors, are considered transient.
+    #[must_use]
+    pub fn of(error: &anyhow::Error) -> Self {
+        error
+            .chain()
+            .find_map(|cause| {
+                if let Some(error) = cause.downcast_ref::<BackendError>() {
+                    Some(error.kind)
+                } else if let Some(error) = cause.downcast_ref::<ArtifactError>() {
+                    Some(error.kind())
+                } else {
+                    cause.is::<ExpiredError>().then_some(Self::Expired)
+                }
+            })
+            .unwrap_or(Self::Transient)
+    }
+}
+
+/// A failure of an [`crate::ArtifactBackend`], in one of the [`ArtifactErrorKind`] categories.
+///
+/// Backends return it as an [`anyhow::Error`], and [`ArtifactError::new`] reads the category from
+/// the chain of that error.
+#[derive(Debug, Error)]
+#[error("{message}")]
+pub struct BackendError {
+    /// The category of the failure.
+    pub kind: ArtifactErrorKind,
+    /// What failed.
+    pub message: String,
+}
+
+impl BackendError {
+    /// Create an error of `kind`.
+    pub fn new(kind: ArtifactErrorKind, message: impl Into<String>) -> Self {
+        Self { kind, message: message.into() }
+    }
+
+    /// The error for an artifact that does not exist at `uri`.
+    pub fn not_found(uri: impl Display) -> Self {
+        Self::new(ArtifactErrorKind::NotFound, format!("Artifact not found: {uri}"))
+    }
+}
+
+/// A failure to download or upload an artifact, returned by [`crate::Artifact`] and
+/// [`crate::download`].
+#[derive(Debug, Error)]
+pub enum ArtifactError {
+    /// The artifact does not exist, or its URI is invalid.
+    #[error("{artifact_type:?} artifact {id} was not found")]
+    NotFound {
+        /// The ID of the artifact.
+        id: String,
+        /// The type of the artifact.
+        artifact_type: ArtifactType,
+        /// The cause of the failure.
+        source: anyhow::Error,
+    },
+    /// The store refused access to the artifact.
+    #[error("Access to {artifact_type:?} artifact {id} was denied")]
+    AccessDenied {
+        /// The ID of the artifact.
+        id: String,
+        /// The type of the artifact.
+        artifact_type: ArtifactType,
+        /// The cause of the failure.
+        source: anyhow::Error,
+    },
+    /// The artifact does not match its digest, or cannot be decoded.
+    #[error("{artifact_type:?} artifact {id} is corrupt")]
+    Corrupt {
+        /// The ID of the artifact.
+        id: String,
+        /// The type of the artifact.
+        artifact_type: ArtifactType,
+        /// The cause of the failure.
+        source: anyhow::Error,
+    },
+    /// The artifact is larger than the limit of its type.
+    #[error("{artifact_type:?} artifact {id} is over the size limit")]
+    TooLarge {
+        /// The ID of the artifact.
+        id: String,
+        /// The type of the artifact.
+        artifact_type: ArtifactType,
+        /// The cause of the failure.
+        source: anyhow::Error,
+    },
+    /// The artifact is past its expiry.
+    #[error("{artifact_type:?} artifact {id} expired at {expires_at}")]
+    Expired {
+        /// The ID of the artifact.
+        id: String,
+        /// The type of the artifact.
+        artifact_type: ArtifactType,
+        /// When the artifact expired, as a Unix timestamp.
+        expires_at: u64,
+    },
+    /// The transfer failed in a way that retrying may fix, such as a network error.
+    #[error("Failed to transfer {artifact_type:?} artifact {id}")]
+    Transient {
+        /// The ID of the artifact.
+        id: String,
+        /// The type of the artifact.
+        artifact_type: ArtifactType,
+        /// The cause of the failure.
+        source: anyhow::Error,
+    },
+}
+
+impl ArtifactError {
+    /// Categorize the failure `error` of the artifact `id` of `artifact_type`, with
+    /// [`ArtifactErrorKind::of`].
+    #[must_use]
+    pub fn new(id: &str, artifact_type: ArtifactType, error: anyhow::Error) -> Self {
+        let id = id.to_string();
+        let source = error;
+        match ArtifactErrorKind::of(&source) {
+            ArtifactErrorKind::NotFound => Self::NotFound { id, artifact_type, source },
+            ArtifactErrorKind::AccessDenied => Self::AccessDenied { id, artifact_type, source },
+            ArtifactErrorKind::Corrupt => Self::Corrupt { id, artifact_type, source },
+            ArtifactErrorKind::TooLarge => Self::TooLarge { id, artifact_type, source },
+            ArtifactErrorKind::Expired => {
+                let expires_at = source
+                    .chain()
+                    .find_map(|cause| cause.downcast_ref::<ExpiredError>())
+                    .map_or(0, |expired| expired.expires_at);
+                Self::Expired { id, artifact_type, expires_at }
+            }
+            ArtifactErrorKind::Transient => Self::Transient { id, artifact_type, source },
+        }
+    }
+
+    /// The category of the failure.
+    #[must_use]
+    pub fn kind(&self) -> ArtifactErrorKind {
+        match self {
+            Self::NotFound { .. } => ArtifactErrorKind::NotFound,
+            Self::AccessDenied { .. } => ArtifactErrorKind::AccessDenied,
+            Self::Corrupt { .. } => ArtifactErrorKind::Corrupt,
+            Self::TooLarge { .. } => ArtifactErrorKind::TooLarge,
+            Self::Expired { .. } => ArtifactErrorKind::Expired,
+            Self::Transient { .. } => ArtifactErrorKind::Transient,
+        }
+    }
+
+    /// The ID of the artifact.
+    #[must_use]
+    pub fn id(&self) -> &str {
+        match self {
+            Self::NotFound { id, .. }
+            | Self::AccessDenied { id, .. }
+            | Self::Corrupt { id, .. }
+            | Self::TooLarge { id, .. }
+            | Self::Expired { id, .. }
+            | Self::Transient { id, .. } => id,
+        }
+    }
+
+    /// The type of the artifact.
+    #[must_use]
+    pub fn artifact_type(&self) -> ArtifactType {
+        match self {
+            Self::NotFound { artifact_type, .. }
+            | Self::AccessDenied { artifact_type, .. }
+            | Self::Corrupt { artifact_type, .. }
+            | Self::TooLarge { artifact_type, .. }
+            | Self::Expired { artifact_type, .. }
+            | Self::Transient { artifact_type, .. } => *artifact_type,
+        }
+    }
+
+    /// Whether retrying may fix the failure.
+    #[must_use]
+    pub fn is_transient(&self) -> bool {
+        self.kind().is_transient()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::{anyhow, Context};
+
+    use super::*;
+
+    #[test]
+    fn test_categorizes_error_chains() {
+        let not_found = Err::<(), _>(BackendError::not_found("s3://bucket/stdins/a"))
+            .context("Failed to download")
+            .unwrap_err();
+        let error = ArtifactError::new("a", ArtifactType::Stdin, not_found);
+        assert_eq!(error.kind(), ArtifactErrorKind::NotFound);
+        assert_eq!((error.id(), error.artifact_type()), ("a", ArtifactType::Stdin));
+        assert_eq!(error.to_string(), "Stdin artifact a was not found");
+
+        let expired = anyhow::Error::new(ExpiredError { id: "b".to_string(), expires_at: 7 });
+        let error = ArtifactError::new("b", ArtifactType::Program, expired);
+        assert!(matches!(error, ArtifactError::Expired { expires_at: 7, .. }));
+        assert!(!error.is_transient());
+
+        let error = ArtifactError::new("c", ArtifactType::Proof, anyhow!("connection reset"));
+        assert!(error.is_transient());
+    }
+}
@@ -0,0 +1,257 @@
+use std::{
+    path::Path,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{anyhow, Context, Result};
+use spn_artifact_types::ArtifactType;
+use thiserror::Error;
+use tracing::{debug, info};
+use url::Url;
+
+use crate::{get_s3_prefix, ArtifactBackend, ArtifactMetadata, FileBackend};
+
+/// The error returned when an artifact is used after it expired.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("Artifact {id} expired at {expires_at}")]
+pub struct ExpiredError {
+    /// The ID or URI of the artifact.
+    pub id: String,
+    /// When the artifact expired, as a Unix timestamp.
+    pub expires_at: u64,
+}
+
+/// `time` as a Unix timestamp.
+fn unix_time(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).map_or(0, |since| since.as_secs())
+}
+
+/// Fail with an [`ExpiredError`] if the artifact `id`, which expires at `expires_at`, is expired
+/// at `now`.
+pub fn check_expiry(
+    id: &str,
+    expires_at: Option<u64>,
+    now: SystemTime,
+) -> Result<(), ExpiredError> {
+    match expires_at {
+        Some(expires_at) if expires_at <= unix_time(now) => {
+            Err(ExpiredError { id: id.to_string(), expires_at })
+        }
+        _ => Ok(()),
+    }
+}
+
+impl ArtifactMetadata {
+    /// Whether the artifact is expired at `now`, because its recorded expiry passed or because it
+    /// was last written more than `max_age` ago.
+    #[must_use]
+    pub fn is_expired(&self, now: SystemTime, max_age: Option<Duration>) -> bool {
+        let past_expiry = self.expires_at.is_some_and(|expires_at| expires_at <= unix_time(now));
+        let too_old = max_age.zip(self.last_modified).is_some_and(|(max_age, last_modified)| {
+            now.duration_since(last_modified).is_ok_and(|age| age >= max_age)
+        });
+        past_expiry || too_old
+    }
+}
+
+/// Which artifacts [`cleanup_expired`] deletes.
+///
+/// Artifacts are always deleted once their recorded expiry passes. Backends that do not record
+/// expiries, such as the local filesystem, rely on `max_age` instead.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CleanupPolicy {
+    /// The age after which artifacts are deleted, even without a recorded expiry.
+    pub max_age: Option<Duration>,
+    /// Whether expired artifacts are only reported, and not deleted.
+    pub dry_run: bool,
+}
+
+impl CleanupPolicy {
+    /// Delete artifacts last written more than `max_age` ago.
+    #[must_use]
+    pub fn with_max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// Only report expired artifacts, without deleting them.
+    #[must_use]
+    pub fn with_dry_run(mut self) -> Self {
+        self.dry_run = true;
+        self
+    }
+}
+
+/// The outcome of [`cleanup_expired`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CleanupReport {
+    /// The number of artifacts found under the prefix.
+    pub scanned: usize,
+    /// The expired artifacts, which were deleted unless the cleanup was a dry run.
+    pub expired: Vec<Url>,
+}
+
+/// Delete the expired artifacts under `prefix` in `backend`, such as every stdin under
+/// `s3://bucket/stdins/`.
+pub async fn cleanup_expired(
+    backend: &dyn ArtifactBackend,
+    prefix: &Url,
+    policy: &CleanupPolicy,
+) -> Result<CleanupReport> {
+    let now = SystemTime::now();
+    let uris = backend.list(prefix).await?;
+    let mut report = CleanupReport { scanned: uris.len(), expired: Vec::new() };
+    for uri in uris {
+        // Artifacts deleted since they were listed are skipped.
+        let Some(metadata) = backend.head(&uri).await? else {
+            continue;
+        };
+        if !metadata.is_expired(now, policy.max_age) {
+            continue;
+        }
+        if !policy.dry_run {
+            backend.delete(&uri).await?;
+            debug!(%uri, "deleted expired artifact");
+        }
+        report.expired.push(uri);
+    }
+    info!(
+        %prefix,
+        scanned = report.scanned,
+        expired = report.expired.len(),
+        dry_run = policy.dry_run,
+        "cleaned up expired artifacts"
+    );
+    Ok(report)
+}
+
+/// Delete the expired artifacts of `artifact_type` in the download cache at `cache_dir`.
+pub async fn cleanup_cache(
+    cache_dir: &Path,
+    artifact_type: ArtifactType,
+    policy: &CleanupPolicy,
+) -> Result<CleanupReport> {
+    let dir = std::path::absolute(cache_dir.join(get_s3_prefix(artifact_type)))
+        .context("Invalid artifact cache directory")?;
+    let prefix = Url::from_directory_path(&dir)
+        .map_err(|()| anyhow!("Invalid artifact cache directory {}", dir.display()))?;
+    cleanup_expired(&FileBackend, &prefix, policy).await
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use super::*;
+    use crate::{
+        download,
+        download::{cache_metadata_path, cache_path, write_cache_metadata},
+        sha256_hex, ArtifactError, DownloadConfig, MemoryBackend,
+    };
+
+    fn metadata(expires_at: Option<u64>) -> ArtifactMetadata {
+        ArtifactMetadata { expires_at, ..Default::default() }
+    }
+
+    #[tokio::test]
+    async fn test_cleans_up_expired_artifacts() {
+        let backend = MemoryBackend::default();
+        let now = unix_time(SystemTime::now());
+        let artifacts = [
+            ("memory://bucket/stdins/expired", Some(now - 10)),
+            ("memory://bucket/stdins/fresh", Some(now + 3600)),
+            ("memory://bucket/stdins/forever", None),
+            ("memory://bucket/proofs/expired", Some(now - 10)),
+        ];
+        for (uri, expires_at) in artifacts {
+            let uri = Url::parse(uri).unwrap();
+            backend.put_with_metadata(&uri, Bytes::new(), &metadata(expires_at)).await.unwrap();
+        }
+
+        let prefix = Url::parse("memory://bucket/stdins/").unwrap();
+        let policy = CleanupPolicy::default().with_dry_run();
+        let report = cleanup_expired(&backend, &prefix, &policy).await.unwrap();
+        assert_eq!(report.scanned, 3);
+        assert_eq!(report.expired, vec![Url::parse("memory://bucket/stdins/expired").unwrap()]);
+        assert_eq!(backend.uris().len(), 4);
+
+        cleanup_expired(&backend, &prefix, &CleanupPolicy::default()).await.unwrap();
+        let mut uris = backend.uris();
+        uris.sort();
+        assert_eq!(
+            uris,
+            vec![
+                "memory://bucket/proofs/expired",
+                "memory://bucket/stdins/forever",
+                "memory://bucket/stdins/fresh"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cleans_up_cache_by_recorded_expiry() {
+        let dir =
+            std::env::temp_dir().join(format!("spn-artifacts-expiry-meta-{}", std::process::id()));
+        let now = unix_time(SystemTime::now());
+        let cached = |name: &str| {
+            let uri = Url::parse(&format!("s3://bucket/stdins/{name}")).unwrap();
+            cache_path(&dir, ArtifactType::Stdin, &uri)
+        };
+        for (name, expires_at) in [("expired", Some(now - 10)), ("fresh", Some(now + 3600))] {
+            let path = cached(name);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(&path, name).unwrap();
+            let metadata = ArtifactMetadata {
+                sha256: Some(sha256_hex(name.as_bytes())),
+                ..metadata(expires_at)
+            };
+            write_cache_metadata(&path, &metadata).await.unwrap();
+        }
+
+        // The sidecars are not artifacts, and are deleted with their artifact.
+        let report = cleanup_cache(&dir, ArtifactType::Stdin, &CleanupPolicy::default()).await;
+        let report = report.unwrap();
+        assert_eq!(report.scanned, 2);
+        assert_eq!(report.expired, vec![Url::from_file_path(cached("expired")).unwrap()]);
+        assert!(!cached("expired").exists());
+        assert!(!cache_metadata_path(&cached("expired")).exists());
+        assert!(cached("fresh").exists());
+        assert!(cache_metadata_path(&cached("fresh")).exists());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_cleans_up_cache_by_age() {
+        let dir = std::env::temp_dir().join(format!("spn-artifacts-expiry-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("programs/nested")).unwrap();
+        std::fs::write(dir.join("programs/a"), b"a").unwrap();
+        std::fs::write(dir.join("programs/nested/b"), b"b").unwrap();
+        std::fs::write(dir.join("programs/c.1.2.part"), b"c").unwrap();
+
+        let policy = CleanupPolicy::default().with_max_age(Duration::from_secs(3600));
+        let report = cleanup_cache(&dir, ArtifactType::Program, &policy).await.unwrap();
+        assert_eq!((report.scanned, report.expired.len()), (2, 0));
+
+        let policy = CleanupPolicy::default().with_max_age(Duration::ZERO);
+        let report = cleanup_cache(&dir, ArtifactType::Program, &policy).await.unwrap();
+        assert_eq!((report.scanned, report.expired.len()), (2, 2));
+        assert!(!dir.join("programs/a").exists());
+        assert!(dir.join("programs/c.1.2.part").exists());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_rejects_expired_downloads() {
+        let backend = MemoryBackend::default();
+        let uri = Url::parse("memory://bucket/stdins/old").unwrap();
+        let expires_at = unix_time(SystemTime::now()) - 1;
+        backend.put_with_metadata(&uri, Bytes::new(), &metadata(Some(expires_at))).await.unwrap();
+
+        let config = DownloadConfig::default();
+        let error =
+            download(&backend, &uri, ArtifactType::Stdin, "old", &config).await.unwrap_err();
+        assert!(matches!(error, ArtifactError::Expired { expires_at: e, .. } if e == expires_at));
+    }
+}
@@ -0,0 +1,182 @@
+use std::{
+    io::ErrorKind,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use bytes::Bytes;
+use url::Url;
+
+use crate::{
+    download::{cache_metadata_path, parse_cache_metadata},
+    register_backend, ArtifactBackend, ArtifactErrorKind, ArtifactMetadata, BackendError,
+};
+
+/// A counter that keeps the names of concurrent temporary files apart.
+static TEMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// An artifact store on the local filesystem, for `file:///path` URIs.
+///
+/// Writes go to a temporary file next to the destination that is then renamed into place, so a
+/// reader never sees a partially written artifact. The expiry of artifacts cached by
+/// [`crate::download`] is read from the `.meta` file beside them, which is deleted along with
+/// them.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FileBackend;
+
+impl FileBackend {
+    /// Handle `file://` URIs with a [`FileBackend`], for devnets and tests.
+    ///
+    /// Only do this when every artifact URI is trusted, since it lets them read any file the node
+    /// can read.
+    pub fn register() {
+        register_backend("file", Arc::new(Self));
+    }
+}
+
+/// The path of a `file://` URI.
+fn path(uri: &Url) -> Result<PathBuf> {
+    uri.to_file_path().map_err(|()| {
+        BackendError::new(ArtifactErrorKind::NotFound, format!("Invalid file URI: {uri}")).into()
+    })
+}
+
+#[async_trait]
+impl ArtifactBackend for FileBackend {
+    async fn get(&self, uri: &Url) -> Result<Bytes> {
+        let path = path(uri)?;
+        match tokio::fs::read(&path).await {
+            Ok(data) => Ok(Bytes::from(data)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Err(BackendError::not_found(uri).into()),
+            Err(e) => {
+                Err(e).with_context(|| format!("Failed to read artifact file {}", path.display()))
+            }
+        }
+    }
+
+    async fn put(&self, uri: &Url, data: Bytes) -> Result<()> {
+        let path = path(uri)?;
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await.with_context(|| {
+                format!("Failed to create artifact directory {}", parent.display())
+            })?;
+        }
+        let mut temp = path.clone().into_os_string();
+        temp.push(format!(
+            ".{}.{}.tmp",
+            std::process::id(),
+            TEMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        tokio::fs::write(&temp, &data)
+            .await
+            .with_context(|| format!("Failed to write artifact file {}", path.display()))?;
+        tokio::fs::rename(&temp, &path)
+            .await
+            .with_context(|| format!("Failed to move artifact file to {}", path.display()))
+    }
+
+    async fn head(&self, uri: &Url) -> Result<Option<ArtifactMetadata>> {
+        let path = path(uri)?;
+        match tokio::fs::metadata(&path).await {
+            Ok(metadata) => Ok(Some(ArtifactMetadata {
+                size: Some(metadata.len()),
+                last_modified: metadata.modified().ok(),
+                expires_at: tokio::fs::read_to_string(cache_metadata_path(&path))
+                    .await
+                    .ok()
+                    .and_then(|contents| parse_cache_metadata(&contents)?.expires_at),
+                ..Default::default()
+            })),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => {
+                Err(e).with_context(|| format!("Failed to stat artifact file {}", path.display()))
+            }
+        }
+    }
+
+    async fn delete(&self, uri: &Url) -> Result<()> {
+        let path = path(uri)?;
+        for path in [cache_metadata_path(&path), path] {
+            match tokio::fs::remove_file(&path).await {
+                Ok(()) => {}
+                Err(e) if e.kind() == ErrorKind::NotFound => {}
+                Err(e) => {
+                    return Err(e).with_context(|| {
+                        format!("Failed to delete artifact file {}", path.display())
+                    })
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn list(&self, prefix: &Url) -> Result<Vec<Url>> {
+        // A prefix names a directory, whose files are listed recursively.
+        let mut uris = Vec::new();
+        let mut dirs = vec![path(prefix)?];
+        while let Some(dir) = dirs.pop() {
+            let mut entries = match tokio::fs::read_dir(&dir).await {
+                Ok(entries) => entries,
+                Err(e) if e.kind() == ErrorKind::NotFound => continue,
+                Err(e) => {
+                    return Err(e).with_context(|| {
+                        format!("Failed to list artifact directory {}", dir.display())
+                    })
+                }
+            };
+            while let Some(entry) = entries.next_entry().await? {
+                let path = entry.path();
+                if entry.file_type().await?.is_dir() {
+                    dirs.push(path);
+                } else if !matches!(
+                    path.extension().and_then(|e| e.to_str()),
+                    Some("tmp" | "part" | "meta")
+                ) {
+                    // Files being written, here or by a cached download, and the metadata of
+                    // cached downloads are skipped.
+                    uris.push(
+                        Url::from_file_path(&path)
+                            .map_err(|()| anyhow!("Invalid artifact path {}", path.display()))?,
+                    );
+                }
+            }
+        }
+        Ok(uris)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_file_round_trip() {
+        let dir = std::env::temp_dir().join(format!("spn-artifacts-file-{}", std::process::id()));
+        let uri = Url::from_file_path(dir.join("stdins/artifact_1")).unwrap();
+        let copy = Url::from_file_path(dir.join("stdins/artifact_2")).unwrap();
+
+        assert_eq!(FileBackend.head(&uri).await.unwrap(), None);
+        FileBackend.put(&uri, Bytes::from_static(b"hello")).await.unwrap();
+        assert_eq!(FileBackend.get(&uri).await.unwrap(), Bytes::from_static(b"hello"));
+        let metadata = FileBackend.head(&uri).await.unwrap().unwrap();
+        assert_eq!(metadata.size, Some(5));
+        assert!(metadata.last_modified.is_some());
+
+        FileBackend.copy(&uri, &copy).await.unwrap();
+        let prefix = Url::from_directory_path(dir.join("stdins")).unwrap();
+        let mut uris = FileBackend.list(&prefix).await.unwrap();
+        uris.sort();
+        assert_eq!(uris, vec![uri.clone(), copy.clone()]);
+        FileBackend.delete(&uri).await.unwrap();
+        FileBackend.delete(&uri).await.unwrap();
+        assert_eq!(FileBackend.head(&uri).await.unwrap(), None);
+        assert_eq!(FileBackend.get(&copy).await.unwrap(), Bytes::from_static(b"hello"));
+
+        tokio::fs::remove_dir_all(dir).await.unwrap();
+    }
+}
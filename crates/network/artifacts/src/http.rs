@@ -0,0 +1,318 @@
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures::{stream, StreamExt};
+use reqwest::{
+    header::{HeaderMap, CONTENT_LENGTH, ETAG, IF_RANGE, RANGE},
+    StatusCode,
+};
+use url::Url;
+
+use crate::{
+    ArtifactBackend, ArtifactErrorKind, ArtifactMetadata, ArtifactStream, BackendError,
+    ContentEncoding,
+};
+
+/// The header S3 returns the recorded digest of an object in, including through presigned URLs.
+const SHA256_HEADER: &str = "x-amz-meta-sha256";
+
+/// The header S3 returns the recorded encoding of an object in.
+const ENCODING_HEADER: &str = "x-amz-meta-encoding";
+
+/// The header S3 returns the recorded expiry of an object in.
+const EXPIRES_AT_HEADER: &str = "x-amz-meta-expires-at";
+
+/// The category of a failed request with `status`.
+pub(crate) fn status_kind(status: StatusCode) -> ArtifactErrorKind {
+    match status {
+        StatusCode::NOT_FOUND | StatusCode::GONE => ArtifactErrorKind::NotFound,
+        StatusCode::REQUEST_TIMEOUT | StatusCode::TOO_MANY_REQUESTS => ArtifactErrorKind::Transient,
+        // Other client errors, such as an expired presigned URL, are refusals.
+        status if status.is_client_error() => ArtifactErrorKind::AccessDenied,
+        _ => ArtifactErrorKind::Transient,
+    }
+}
+
+/// Fail on an unsuccessful status, categorized with [`status_kind`].
+fn check_status(uri: &Url, status: StatusCode) -> Result<()> {
+    if status.is_success() {
+        return Ok(());
+    }
+    let message = format!("Failed to download from HTTPS URL {uri}: status {status}");
+    match status_kind(status) {
+        ArtifactErrorKind::Transient => Err(anyhow!(message)),
+        kind => Err(BackendError::new(kind, message).into()),
+    }
+}
+
+/// The digest, encoding, expiry and entity tag recorded in the headers of a response.
+fn header_metadata(headers: &HeaderMap) -> ArtifactMetadata {
+    let header = |name| headers.get(name).and_then(|value| value.to_str().ok());
+    ArtifactMetadata {
+        size: None,
+        sha256: header(SHA256_HEADER).map(str::to_string),
+        encoding: header(ENCODING_HEADER).and_then(ContentEncoding::from_name),
+        expires_at: header(EXPIRES_AT_HEADER).and_then(|value| value.parse().ok()),
+        last_modified: None,
+        etag: header(ETAG).map(str::to_string),
+    }
+}
+
+/// The error for writes to the read-only `uri`.
+fn read_only(uri: &Url) -> anyhow::Error {
+    let message = format!("HTTP artifact URLs are read-only: {uri}");
+    BackendError::new(ArtifactErrorKind::AccessDenied, message).into()
+}
+
+/// A read-only artifact store for `http://` and `https://` URLs, such as public or presigned
+/// artifact URLs.
+#[derive(Debug, Clone)]
+pub struct HttpBackend {
+    /// The client used for every request.
+    client: reqwest::Client,
+}
+
+impl Default for HttpBackend {
+    fn default() -> Self {
+        Self { client: reqwest::Client::new() }
+    }
+}
+
+impl HttpBackend {
+    /// Create a backend that sends requests with `client`.
+    #[must_use]
+    pub fn new(client: reqwest::Client) -> Self {
+        Self { client }
+    }
+}
+
+#[async_trait]
+impl ArtifactBackend for HttpBackend {
+    async fn get(&self, uri: &Url) -> Result<Bytes> {
+        let res = self.client.get(uri.clone()).send().await.context("Failed to GET HTTPS URL")?;
+        check_status(uri, res.status())?;
+        let bytes = res.bytes().await.context("Failed to read HTTPS response body")?;
+        Ok(bytes)
+    }
+
+    async fn get_stream(&self, uri: &Url) -> Result<ArtifactStream> {
+        self.get_stream_from(uri, 0, None).await
+    }
+
+    async fn get_stream_from(
+        &self,
+        uri: &Url,
+        offset: u64,
+        etag: Option<&str>,
+    ) -> Result<ArtifactStream> {
+        let mut request = self.client.get(uri.clone());
+        if offset > 0 {
+            request = request.header(RANGE, format!("bytes={offset}-"));
+            // Only resume if the artifact is unchanged, otherwise the server sends all of it.
+            if let Some(etag) = etag {
+                request = request.header(IF_RANGE, etag);
+            }
+        }
+        let res = request.send().await.context("Failed to GET HTTPS URL")?;
+        check_status(uri, res.status())?;
+        let partial = res.status() == StatusCode::PARTIAL_CONTENT;
+        // A full response to a conditional range means that the artifact changed, and its body is
+        // the new artifact, which the download restarts with. Servers that ignore the range
+        // send the whole unchanged artifact again instead.
+        let (start, skip) = match (partial, etag) {
+            (true, _) => (offset, 0),
+            (false, Some(_)) => (0, 0),
+            (false, None) => (0, offset),
+        };
+        let size = res.content_length();
+        let metadata = header_metadata(res.headers());
+        let body = stream::try_unfold(res, |mut res| async move {
+            let chunk = res.chunk().await.context("Failed to read HTTPS response body")?;
+            Ok(chunk.map(|chunk| (chunk, res)))
+        });
+        Ok(ArtifactStream { offset: start, size, metadata, body: body.boxed() }.skip(skip))
+    }
+
+    async fn put(&self, uri: &Url, _data: Bytes) -> Result<()> {
+        Err(read_only(uri))
+    }
+
+    async fn head(&self, uri: &Url) -> Result<Option<ArtifactMetadata>> {
+        let res = self.client.head(uri.clone()).send().await.context("Failed to HEAD HTTPS URL")?;
+        if res.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        check_status(uri, res.status())?;
+        // Servers may leave the length out, e.g. for compressed responses.
+        let size =
+            res.headers().get(CONTENT_LENGTH).and_then(|value| value.to_str().ok()?.parse().ok());
+        Ok(Some(ArtifactMetadata { size, ..header_metadata(res.headers()) }))
+    }
+
+    async fn copy(&self, src: &Url, _dst: &Url) -> Result<()> {
+        Err(read_only(src))
+    }
+
+    async fn delete(&self, uri: &Url) -> Result<()> {
+        Err(read_only(uri))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use spn_artifact_types::ArtifactType;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    use super::*;
+    use crate::{download, DownloadConfig};
+
+    /// Read the head of a request, returning the offset of its range header and the value of its
+    /// `if-range` header, if any.
+    async fn read_range(socket: &mut tokio::net::TcpStream) -> (Option<usize>, Option<String>) {
+        let mut request = Vec::new();
+        let mut buf = [0; 1024];
+        while !request.ends_with(b"\r\n\r\n") {
+            let n = socket.read(&mut buf).await.unwrap();
+            assert!(n > 0, "connection closed before the end of the request");
+            request.extend_from_slice(&buf[..n]);
+        }
+        let request = String::from_utf8(request).unwrap();
+        let header = |wanted: &str| {
+            request.lines().find_map(|line| {
+                let (name, value) = line.split_once(':')?;
+                name.eq_ignore_ascii_case(wanted).then(|| value.trim().to_string())
+            })
+        };
+        let offset = header("range").map(|value| {
+            value.strip_prefix("bytes=").unwrap().strip_suffix('-').unwrap().parse().unwrap()
+        });
+        (offset, header("if-range"))
+    }
+
+    #[tokio::test]
+    async fn test_resumes_interrupted_downloads() {
+        let data: Vec<u8> = (0..64 * 1024).map(|i| (i % 251) as u8).collect();
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = {
+            let data = data.clone();
+            tokio::spawn(async move {
+                // HEAD is not allowed, as with some presigned URLs.
+                let (mut socket, _) = listener.accept().await.unwrap();
+                read_range(&mut socket).await;
+                socket
+                    .write_all(b"HTTP/1.1 403 Forbidden\r\ncontent-length: 0\r\n\r\n")
+                    .await
+                    .unwrap();
+                drop(socket);
+
+                // The first GET is cut off halfway through the body.
+                let (mut socket, _) = listener.accept().await.unwrap();
+                assert_eq!(read_range(&mut socket).await, (None, None));
+                let head = format!(
+                    "HTTP/1.1 200 OK\r\ncontent-length: {}\r\netag: \"v1\"\r\n\r\n",
+                    data.len()
+                );
+                socket.write_all(head.as_bytes()).await.unwrap();
+                socket.write_all(&data[..data.len() / 2]).await.unwrap();
+                drop(socket);
+
+                // The retry asks for the rest only, of the same version of the artifact.
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let (offset, if_range) = read_range(&mut socket).await;
+                let offset = offset.expect("missing range header");
+                assert_eq!(offset, data.len() / 2);
+                assert_eq!(if_range.as_deref(), Some("\"v1\""));
+                let head = format!(
+                    "HTTP/1.1 206 Partial Content\r\ncontent-length: {}\r\n\r\n",
+                    data.len() - offset
+                );
+                socket.write_all(head.as_bytes()).await.unwrap();
+                socket.write_all(&data[offset..]).await.unwrap();
+            })
+        };
+
+        let uri = Url::parse(&format!("http://{addr}/programs/resumed")).unwrap();
+        let backend =
+            HttpBackend::new(reqwest::Client::builder().pool_max_idle_per_host(0).build().unwrap());
+        let downloaded =
+            download(&backend, &uri, ArtifactType::Program, "resumed", &DownloadConfig::default())
+                .await
+                .unwrap();
+        assert_eq!(downloaded, data);
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_restarts_download_of_changed_artifacts() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            read_range(&mut socket).await;
+            socket.write_all(b"HTTP/1.1 403 Forbidden\r\ncontent-length: 0\r\n\r\n").await.unwrap();
+            drop(socket);
+
+            // The first GET is cut off after 10 bytes.
+            let (mut socket, _) = listener.accept().await.unwrap();
+            assert_eq!(read_range(&mut socket).await, (None, None));
+            socket
+                .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 20\r\netag: \"v1\"\r\n\r\n")
+                .await
+                .unwrap();
+            socket.write_all(&[1; 10]).await.unwrap();
+            drop(socket);
+
+            // The artifact changed since, so the range is ignored and all of it is sent.
+            let (mut socket, _) = listener.accept().await.unwrap();
+            assert_eq!(read_range(&mut socket).await, (Some(10), Some("\"v1\"".to_string())));
+            socket
+                .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 30\r\netag: \"v2\"\r\n\r\n")
+                .await
+                .unwrap();
+            socket.write_all(&[2; 30]).await.unwrap();
+        });
+
+        let uri = Url::parse(&format!("http://{addr}/programs/changed")).unwrap();
+        let backend =
+            HttpBackend::new(reqwest::Client::builder().pool_max_idle_per_host(0).build().unwrap());
+        let downloaded =
+            download(&backend, &uri, ArtifactType::Program, "changed", &DownloadConfig::default())
+                .await
+                .unwrap();
+        assert_eq!(downloaded, vec![2; 30]);
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_head_times_out_with_the_download() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            // HEAD never gets a response.
+            let (mut head, _) = listener.accept().await.unwrap();
+            read_range(&mut head).await;
+
+            let (mut socket, _) = listener.accept().await.unwrap();
+            read_range(&mut socket).await;
+            socket.write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 4\r\n\r\n").await.unwrap();
+            socket.write_all(&[3; 4]).await.unwrap();
+            drop(head);
+        });
+
+        let uri = Url::parse(&format!("http://{addr}/programs/slow")).unwrap();
+        let backend =
+            HttpBackend::new(reqwest::Client::builder().pool_max_idle_per_host(0).build().unwrap());
+        let config =
+            DownloadConfig { idle_timeout: Duration::from_millis(200), ..Default::default() };
+        let downloaded =
+            download(&backend, &uri, ArtifactType::Program, "slow", &config).await.unwrap();
+        assert_eq!(downloaded, vec![3; 4]);
+        server.await.unwrap();
+    }
+}
@@ -20,34 +20,47 @@
 #![allow(clippy::struct_excessive_bools)]
 #![warn(missing_docs)]
 
-use std::{
-    collections::HashMap,
-    sync::{Arc, LazyLock},
-    time::Duration,
-};
+mod backend;
+mod codec;
+mod content;
+mod download;
+mod encryption;
+mod error;
+mod expiry;
+mod file;
+mod http;
+mod memory;
+mod presigned;
+mod proof;
+mod retry;
+mod s3;
+
+pub use backend::*;
+pub use codec::*;
+pub use content::*;
+pub use download::*;
+pub use encryption::*;
+pub use error::*;
+pub use expiry::*;
+pub use file::*;
+pub use http::*;
+pub use memory::*;
+pub use presigned::*;
+pub use proof::*;
+pub use retry::*;
+pub use s3::*;
+
+use std::{path::Path, time::SystemTime};
 
 use spn_artifact_types::ArtifactType;
 
-use anyhow::{anyhow, Context, Result};
-use aws_config::{retry::RetryConfig, BehaviorVersion, Region};
-use aws_sdk_s3::{
-    config::{IdentityCache, StalledStreamProtectionConfig},
-    primitives::{ByteStream, SdkBody},
-    Client as S3Client,
-};
-use aws_smithy_async::rt::sleep::default_async_sleep;
+use anyhow::{anyhow, Result};
 use bytes::Bytes;
 use serde::{de::DeserializeOwned, Serialize};
-use tokio::sync::RwLock;
 use tracing::instrument;
 use url::Url;
 
-/// S3 Clients that are cached across the entire application.
-#[allow(clippy::type_complexity)]
-static S3_CLIENTS: LazyLock<Arc<RwLock<HashMap<String, Arc<S3Client>>>>> =
-    LazyLock::new(|| Arc::new(RwLock::new(HashMap::new())));
-
-/// An artifact is a file that is stored in S3.
+/// An artifact is a file that is stored in S3, or in any other [`ArtifactBackend`].
 #[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq, ::prost::Message)]
 pub struct Artifact {
     /// The unique identifier for the artifact, representing its location in the S3
@@ -63,6 +76,17 @@ pub struct Artifact {
 }
 
 impl Artifact {
+    /// When the artifact expires, as a Unix timestamp, if it does.
+    #[must_use]
+    pub fn expires_at(&self) -> Option<u64> {
+        self.expiry.map(|expiry| u64::try_from(expiry).unwrap_or_default())
+    }
+
+    /// Fail with an [`ExpiredError`] if the artifact is expired.
+    pub fn check_expiry(&self) -> Result<(), ExpiredError> {
+        check_expiry(&self.id, self.expires_at(), SystemTime::now())
+    }
+
     /// Uploads a serializable item to S3 as an artifact.
     ///
     /// Serializes the item using bincode and uploads it to the specified S3 bucket
@@ -80,16 +104,17 @@ impl Artifact {
         s3_bucket: &str,
         s3_region: &str,
         artifact_type: ArtifactType,
-    ) -> Result<()> {
-        let s3_client = get_s3_client(s3_region).await;
-        let data = bincode::serialize(&item).context("Failed to serialize data")?;
-        upload_file(&s3_client, s3_bucket, &self.id, artifact_type, Bytes::from(data)).await
+    ) -> Result<(), ArtifactError> {
+        let data = self.serialize(&item, artifact_type)?;
+        self.upload_raw(data, s3_bucket, s3_region, artifact_type).await
     }
 
     /// Downloads raw bytes of an artifact from S3.
     ///
-    /// Retrieves the artifact from the specified S3 bucket and region. Implements
-    /// exponential backoff retry logic with up to 5 attempts.
+    /// Retrieves the artifact from the specified S3 bucket and region. Failed downloads are
+    /// resumed with the shared [`ArtifactRetryPolicy`]. The download is streamed and bounded by the
+    /// [`DownloadConfig`] set with [`set_download_config`]. Failures are categorized into an
+    /// [`ArtifactError`], and expired artifacts are rejected with [`ArtifactError::Expired`].
     ///
     /// # Arguments
     /// * `s3_bucket` - The S3 bucket name
@@ -101,47 +126,27 @@ impl Artifact {
         s3_bucket: &str,
         s3_region: &str,
         artifact_type: ArtifactType,
-    ) -> Result<Bytes> {
-        let s3_client = get_s3_client(s3_region).await;
-
-        // Retry configuration
-        let max_retries = 5;
-        let mut retry_count = 0;
-        let mut delay = Duration::from_secs(1); // Start with 1 second delay
-
-        loop {
-            match download_s3_file(&s3_client, s3_bucket, &self.id, artifact_type).await {
-                Ok(bytes) => return Ok(bytes),
-                Err(e) => {
-                    retry_count += 1;
-                    if retry_count >= max_retries {
-                        return Err(e);
-                    }
-
-                    // Log the retry attempt
-                    tracing::warn!(
-                        "retry attempt {} for downloading artifact {}: {}",
-                        retry_count,
-                        self.id,
-                        e
-                    );
-
-                    // Wait with exponential backoff
-                    tokio::time::sleep(delay).await;
-                    delay *= 2; // Double the delay for next retry
-                }
-            }
+    ) -> Result<Bytes, ArtifactError> {
+        let result: Result<_> = async {
+            self.check_expiry()?;
+            let uri = s3_uri(s3_bucket, artifact_type, &self.id)?;
+            Ok((backend_for(&uri, s3_region)?, uri))
         }
+        .await;
+        let (backend, uri) = self.categorize(artifact_type, result)?;
+        download(&*backend, &uri, artifact_type, &self.id, &download_config()).await
     }
 
     /// Downloads raw bytes of an artifact from a URI.
     ///
-    /// Supports both S3 URIs (s3://bucket/path) and HTTPS URLs. For S3 URIs,
-    /// extracts the bucket name and downloads using the S3 client. For HTTPS URLs,
-    /// performs a standard HTTP GET request.
+    /// Supports S3 URIs (s3://bucket/path), HTTP(S) URLs and any other scheme with an
+    /// [`ArtifactBackend`]. For S3 URIs, extracts the bucket name and downloads the artifact
+    /// under the prefix of its type. Other URIs are downloaded as is. The download is streamed
+    /// and bounded by the [`DownloadConfig`] set with [`set_download_config`], and expired
+    /// artifacts are rejected like in [`Artifact::download_raw`].
     ///
     /// # Arguments
-    /// * `uri` - The URI to download from (s3:// or https://)
+    /// * `uri` - The URI to download from (s3://, http://, https:// or a registered scheme)
     /// * `s3_region` - The AWS region for S3 operations
     /// * `artifact_type` - The type of artifact determining the S3 prefix
     #[instrument(fields(label = self.label, id = self.id), skip_all)]
@@ -150,18 +155,15 @@ impl Artifact {
         uri: &str,
         s3_region: &str,
         artifact_type: ArtifactType,
-    ) -> Result<Bytes> {
-        let parsed_url = Url::parse(uri).context("Failed to parse URI")?;
-        match parsed_url.scheme() {
-            "s3" => {
-                let bucket =
-                    parsed_url.host_str().ok_or_else(|| anyhow!("S3 URI missing bucket: {uri}"))?;
-                let s3_client = get_s3_client(s3_region).await;
-                download_s3_file(&s3_client, bucket, &self.id, artifact_type).await
-            }
-            "https" => download_https_file(uri).await,
-            scheme => Err(anyhow!("Unsupported URI scheme for download_raw_from_uri: {scheme}")),
+    ) -> Result<Bytes, ArtifactError> {
+        let result: Result<_> = async {
+            self.check_expiry()?;
+            let uri = self.resolve_uri(uri, artifact_type)?;
+            Ok((backend_for(&uri, s3_region)?, uri))
         }
+        .await;
+        let (backend, uri) = self.categorize(artifact_type, result)?;
+        download(&*backend, &uri, artifact_type, &self.id, &download_config()).await
     }
 
     /// Downloads and deserializes a program artifact from S3.
@@ -176,9 +178,9 @@ impl Artifact {
         &self,
         s3_bucket: &str,
         s3_region: &str,
-    ) -> Result<T> {
+    ) -> Result<T, ArtifactError> {
         let bytes = self.download_raw(s3_bucket, s3_region, ArtifactType::Program).await?;
-        bincode::deserialize(&bytes).context("Failed to deserialize program")
+        self.deserialize(&bytes, ArtifactType::Program)
     }
 
     /// Downloads and deserializes a program artifact from a URI.
@@ -193,9 +195,9 @@ impl Artifact {
         &self,
         uri: &str,
         s3_region: &str,
-    ) -> Result<T> {
+    ) -> Result<T, ArtifactError> {
         let bytes = self.download_raw_from_uri(uri, s3_region, ArtifactType::Program).await?;
-        bincode::deserialize(&bytes).context("Failed to deserialize program from URI")
+        self.deserialize(&bytes, ArtifactType::Program)
     }
 
     /// Downloads and deserializes a stdin artifact from S3.
@@ -210,9 +212,9 @@ impl Artifact {
         &self,
         s3_bucket: &str,
         s3_region: &str,
-    ) -> Result<T> {
+    ) -> Result<T, ArtifactError> {
         let bytes = self.download_raw(s3_bucket, s3_region, ArtifactType::Stdin).await?;
-        bincode::deserialize(&bytes).context("Failed to deserialize stdin")
+        self.deserialize(&bytes, ArtifactType::Stdin)
     }
 
     /// Downloads and deserializes a stdin artifact from a URI.
@@ -227,9 +229,49 @@ impl Artifact {
         &self,
         uri: &str,
         s3_region: &str,
-    ) -> Result<T> {
+    ) -> Result<T, ArtifactError> {
+        self.download_stdin_from_uri_with_key(uri, s3_region, None).await
+    }
+
+    /// Downloads and deserializes a stdin artifact from a URI, decrypting it if it is encrypted.
+    ///
+    /// Stdins uploaded with [`Artifact::upload_encrypted_stdin_to_uri`] are decrypted with `key`,
+    /// and rejected with [`ArtifactError::AccessDenied`] without one, or with
+    /// [`ArtifactError::Corrupt`] if they were encrypted to another key. Unencrypted stdins are
+    /// read like in [`Artifact::download_stdin_from_uri`].
+    ///
+    /// # Arguments
+    /// * `uri` - The URI to download from (s3:// or https://)
+    /// * `s3_region` - The AWS region for S3 operations
+    /// * `key` - The secret key encrypted stdins are decrypted with
+    pub async fn download_stdin_from_uri_with_key<T: DeserializeOwned + Send + Sync + 'static>(
+        &self,
+        uri: &str,
+        s3_region: &str,
+        key: Option<&ArtifactSecretKey>,
+    ) -> Result<T, ArtifactError> {
+        let bytes = self.download_raw_stdin_from_uri_with_key(uri, s3_region, key).await?;
+        self.deserialize(&bytes, ArtifactType::Stdin)
+    }
+
+    /// Downloads a stdin artifact from a URI, decrypting it if it is encrypted, without
+    /// deserializing it.
+    ///
+    /// This returns the bincode-serialized stdin as the requester uploaded it, for provers that
+    /// leave decoding it to an SP1 version other than their own.
+    ///
+    /// # Arguments
+    /// * `uri` - The URI to download from (s3:// or https://)
+    /// * `s3_region` - The AWS region for S3 operations
+    /// * `key` - The secret key encrypted stdins are decrypted with
+    pub async fn download_raw_stdin_from_uri_with_key(
+        &self,
+        uri: &str,
+        s3_region: &str,
+        key: Option<&ArtifactSecretKey>,
+    ) -> Result<Bytes, ArtifactError> {
         let bytes = self.download_raw_from_uri(uri, s3_region, ArtifactType::Stdin).await?;
-        bincode::deserialize(&bytes).context("Failed to deserialize stdin from URI")
+        self.decrypt(bytes, key, ArtifactType::Stdin)
     }
 
     /// Downloads and deserializes a proof artifact from S3.
@@ -244,9 +286,9 @@ impl Artifact {
         &self,
         s3_bucket: &str,
         s3_region: &str,
-    ) -> Result<T> {
+    ) -> Result<T, ArtifactError> {
         let bytes = self.download_raw(s3_bucket, s3_region, ArtifactType::Proof).await?;
-        bincode::deserialize(&bytes).context("Failed to deserialize proof")
+        self.deserialize(&bytes, ArtifactType::Proof)
     }
 
     /// Downloads and deserializes a proof artifact from a URI.
@@ -261,15 +303,17 @@ impl Artifact {
         &self,
         uri: &str,
         s3_region: &str,
-    ) -> Result<T> {
+    ) -> Result<T, ArtifactError> {
         let bytes = self.download_raw_from_uri(uri, s3_region, ArtifactType::Proof).await?;
-        bincode::deserialize(&bytes).context("Failed to deserialize proof from URI")
+        self.deserialize(&bytes, ArtifactType::Proof)
     }
 
     /// Uploads raw bytes as an artifact to S3.
     ///
     /// Directly uploads the provided bytes to the specified S3 bucket and region
-    /// with the appropriate artifact type prefix.
+    /// with the appropriate artifact type prefix. The bytes are compressed and their digest
+    /// recorded according to the [`UploadConfig`] set with [`set_upload_config`], along with the
+    /// expiry of the artifact.
     ///
     /// # Arguments
     /// * `data` - The raw bytes to upload
@@ -283,16 +327,134 @@ impl Artifact {
         s3_bucket: &str,
         s3_region: &str,
         artifact_type: ArtifactType,
-    ) -> Result<()> {
-        let s3_client = get_s3_client(s3_region).await;
-        upload_file(&s3_client, s3_bucket, &self.id, artifact_type, data).await
+    ) -> Result<(), ArtifactError> {
+        let result: Result<()> = async {
+            let uri = s3_uri(s3_bucket, artifact_type, &self.id)?;
+            let (data, mut metadata) = encode(data, &upload_config())?;
+            metadata.expires_at = self.expires_at();
+            let backend = backend_for(&uri, s3_region)?;
+            with_retry("upload artifact", || {
+                backend.put_with_metadata(&uri, data.clone(), &metadata)
+            })
+            .await
+        }
+        .await;
+        self.categorize(artifact_type, result)
+    }
+
+    /// Uploads a file as an artifact to S3.
+    ///
+    /// The file is streamed from disk, in parallel parts when it is over the multipart threshold
+    /// of the [`UploadConfig`], so it is never fully loaded in memory. It is stored uncompressed,
+    /// with its digest recorded.
+    ///
+    /// # Arguments
+    /// * `path` - The path of the file to upload
+    /// * `s3_bucket` - The S3 bucket name
+    /// * `s3_region` - The AWS region of the S3 bucket
+    /// * `artifact_type` - The type of artifact determining the S3 prefix
+    #[instrument(fields(label = self.label, id = self.id), skip_all)]
+    pub async fn upload_file(
+        &self,
+        path: impl AsRef<Path>,
+        s3_bucket: &str,
+        s3_region: &str,
+        artifact_type: ArtifactType,
+    ) -> Result<(), ArtifactError> {
+        let path = path.as_ref();
+        let result: Result<()> = async {
+            let uri = s3_uri(s3_bucket, artifact_type, &self.id)?;
+            let (size, sha256) = sha256_file(path).await?;
+            let metadata = ArtifactMetadata {
+                size: Some(size),
+                sha256: Some(sha256),
+                encoding: Some(ContentEncoding::Identity),
+                expires_at: self.expires_at(),
+                ..Default::default()
+            };
+            let backend = backend_for(&uri, s3_region)?;
+            with_retry("upload artifact", || backend.put_file(&uri, path, &metadata)).await
+        }
+        .await;
+        self.categorize(artifact_type, result)
+    }
+
+    /// Uploads raw bytes as an artifact to a URI.
+    ///
+    /// For S3 URIs, extracts the bucket name and uploads the artifact under the prefix of its
+    /// type, like [`Artifact::download_raw_from_uri`] expects. Other URIs are written as is.
+    /// The bytes are encoded like in [`Artifact::upload_raw`].
+    ///
+    /// # Arguments
+    /// * `data` - The raw bytes to upload
+    /// * `uri` - The URI to upload to (s3:// or a registered scheme)
+    /// * `s3_region` - The AWS region for S3 operations
+    /// * `artifact_type` - The type of artifact determining the S3 prefix
+    #[instrument(fields(label = self.label, id = self.id), skip_all)]
+    pub async fn upload_raw_to_uri(
+        &self,
+        data: Bytes,
+        uri: &str,
+        s3_region: &str,
+        artifact_type: ArtifactType,
+    ) -> Result<(), ArtifactError> {
+        let result = self.put_to_uri(data, uri, s3_region, artifact_type, &upload_config()).await;
+        self.categorize(artifact_type, result)
+    }
+
+    /// Uploads a serializable item as a stdin artifact to a URI, encrypted to `recipient`.
+    ///
+    /// Serializes the item using bincode, compresses it according to the [`UploadConfig`] set
+    /// with [`set_upload_config`], and encrypts it with [`ArtifactPublicKey::encrypt`], so only
+    /// the prover holding the matching [`ArtifactSecretKey`] can read it. It is then uploaded like
+    /// in [`Artifact::upload_raw_to_uri`].
+    ///
+    /// # Arguments
+    /// * `item` - The stdin to serialize and upload
+    /// * `uri` - The URI to upload to (s3:// or a registered scheme)
+    /// * `s3_region` - The AWS region for S3 operations
+    /// * `recipient` - The public key of the prover that may read the stdin
+    #[instrument(fields(label = self.label, id = self.id), skip_all)]
+    pub async fn upload_encrypted_stdin_to_uri<T: Serialize>(
+        &self,
+        item: T,
+        uri: &str,
+        s3_region: &str,
+        recipient: &ArtifactPublicKey,
+    ) -> Result<(), ArtifactError> {
+        let data = self.serialize(&item, ArtifactType::Stdin)?;
+        let result: Result<()> = async {
+            // Ciphertexts do not compress, so the payload is compressed before it is encrypted.
+            let config = upload_config();
+            let (compressed, _) = encode(data, &config)?;
+            let envelope = recipient.encrypt(&compressed);
+            let config = UploadConfig { compression_threshold: None, ..config };
+            self.put_to_uri(envelope, uri, s3_region, ArtifactType::Stdin, &config).await
+        }
+        .await;
+        self.categorize(ArtifactType::Stdin, result)
+    }
+
+    /// Uploads a serializable item as an artifact to a URI.
+    ///
+    /// Serializes the item using bincode and uploads it with [`Artifact::upload_raw_to_uri`].
+    pub async fn upload_to_uri<T: Serialize>(
+        &self,
+        item: T,
+        uri: &str,
+        s3_region: &str,
+        artifact_type: ArtifactType,
+    ) -> Result<(), ArtifactError> {
+        let data = self.serialize(&item, artifact_type)?;
+        self.upload_raw_to_uri(data, uri, s3_region, artifact_type).await
     }
 
     /// Copies an artifact between S3 buckets.
     ///
     /// Copies the artifact from a source bucket to a destination bucket, potentially
     /// across different regions. If the artifact already exists in the destination,
-    /// the operation succeeds without copying.
+    /// the operation succeeds without copying. Within a region, the copy is left to the
+    /// backend.
     ///
     /// # Arguments
     /// * `artifact_type` - The type of artifact determining the S3 prefix
@@ -307,36 +469,123 @@ impl Artifact {
         src_region: &str,
         dst_bucket: &str,
         dst_region: &str,
+    ) -> Result<(), ArtifactError> {
+        let result: Result<()> = async {
+            let src = s3_uri(src_bucket, artifact_type, &self.id)?;
+            let dst = s3_uri(dst_bucket, artifact_type, &self.id)?;
+            let src_backend = backend_for(&src, src_region)?;
+            let dst_backend = backend_for(&dst, dst_region)?;
+
+            // Check if destination exists
+            if dst_backend.head(&dst).await?.is_some() {
+                return Ok(());
+            }
+
+            let (src, dst) = (&src, &dst);
+            let (src_backend, dst_backend) = (&src_backend, &dst_backend);
+            with_retry("copy artifact", || async move {
+                if src_region == dst_region {
+                    return src_backend.copy(src, dst).await;
+                }
+                let metadata = src_backend.head(src).await?.unwrap_or_default();
+                let data = src_backend.get(src).await?;
+                dst_backend.put_with_metadata(dst, data, &metadata).await
+            })
+            .await
+        }
+        .await;
+        self.categorize(artifact_type, result)
+    }
+
+    /// Encode `data` with `config` and upload it to `uri`.
+    async fn put_to_uri(
+        &self,
+        data: Bytes,
+        uri: &str,
+        s3_region: &str,
+        artifact_type: ArtifactType,
+        config: &UploadConfig,
     ) -> Result<()> {
-        let key = get_s3_key(artifact_type, &self.id);
+        let uri = self.resolve_uri(uri, artifact_type)?;
+        let (data, mut metadata) = encode(data, config)?;
+        metadata.expires_at = self.expires_at();
+        let backend = backend_for(&uri, s3_region)?;
+        with_retry("upload artifact", || backend.put_with_metadata(&uri, data.clone(), &metadata))
+            .await
+    }
 
-        let src_client = get_s3_client(src_region).await;
-        let dst_client = get_s3_client(dst_region).await;
+    /// Decrypt `data` with `key` if it is encrypted, and decompress the payload.
+    fn decrypt(
+        &self,
+        data: Bytes,
+        key: Option<&ArtifactSecretKey>,
+        artifact_type: ArtifactType,
+    ) -> Result<Bytes, ArtifactError> {
+        if !is_encrypted(&data) {
+            return Ok(data);
+        }
+        let result = match key {
+            Some(key) => key.decrypt(&data).and_then(|payload| {
+                let max_size = download_config().max_size(artifact_type);
+                decode(payload, &ArtifactMetadata::default(), max_size)
+            }),
+            None => {
+                let message = "Artifact is encrypted, but no decryption key is configured";
+                Err(BackendError::new(ArtifactErrorKind::AccessDenied, message).into())
+            }
+        };
+        self.categorize(artifact_type, result)
+    }
 
-        // Check if destination exists
-        let dst_res = dst_client.head_object().bucket(dst_bucket).key(&key).send().await;
-        if dst_res.is_ok() {
-            return Ok(());
+    /// The URI `uri` resolves to. S3 URIs resolve to the artifact under the prefix of its type
+    /// in their bucket, and other URIs to themselves.
+    fn resolve_uri(&self, uri: &str, artifact_type: ArtifactType) -> Result<Url> {
+        let invalid = |message| BackendError::new(ArtifactErrorKind::NotFound, message);
+        let parsed_url =
+            Url::parse(uri).map_err(|e| invalid(format!("Failed to parse URI {uri}: {e}")))?;
+        if parsed_url.scheme() != "s3" {
+            return Ok(parsed_url);
         }
+        let bucket = parsed_url
+            .host_str()
+            .ok_or_else(|| invalid(format!("S3 URI missing bucket: {uri}")))?;
+        s3_uri(bucket, artifact_type, &self.id)
+    }
 
-        let src_res = src_client
-            .get_object()
-            .bucket(src_bucket)
-            .key(&key)
-            .send()
-            .await
-            .context("Failed to get object from S3")?;
-
-        dst_client
-            .put_object()
-            .bucket(dst_bucket)
-            .key(&key)
-            .body(src_res.body)
-            .send()
-            .await
-            .context("Failed to upload object to S3")?;
+    /// Categorize the failure of `result`, an operation on the artifact as `artifact_type`.
+    fn categorize<T>(
+        &self,
+        artifact_type: ArtifactType,
+        result: Result<T>,
+    ) -> Result<T, ArtifactError> {
+        result.map_err(|e| ArtifactError::new(&self.id, artifact_type, e))
+    }
+
+    /// Serialize `item` with bincode.
+    fn serialize<T: Serialize>(
+        &self,
+        item: &T,
+        artifact_type: ArtifactType,
+    ) -> Result<Bytes, ArtifactError> {
+        bincode::serialize(item).map(Bytes::from).map_err(|e| ArtifactError::Corrupt {
+            id: self.id.clone(),
+            artifact_type,
+            source: anyhow::Error::new(e).context("Failed to serialize data"),
+        })
+    }
 
-        Ok(())
+    /// Deserialize `bytes` with bincode.
+    fn deserialize<T: DeserializeOwned>(
+        &self,
+        bytes: &[u8],
+        artifact_type: ArtifactType,
+    ) -> Result<T, ArtifactError> {
+        bincode::deserialize(bytes).map_err(|e| ArtifactError::Corrupt {
+            id: self.id.clone(),
+            artifact_type,
+            source: anyhow::Error::new(e)
+                .context(format!("Failed to deserialize {artifact_type:?} artifact")),
+        })
     }
 }
 
@@ -352,7 +601,8 @@ pub fn extract_artifact_name(s3_url: &str) -> Result<String> {
 /// Different artifact types have different S3 prefixes.
 ///
 /// This is so that different types are artifacts can have different expiration times. In S3, each
-/// prefix can have a different expiration time.
+/// prefix can have a different expiration time. Elsewhere, expired artifacts can be deleted per
+/// prefix with [`cleanup_expired`].
 #[must_use]
 pub fn get_s3_prefix(artifact_type: ArtifactType) -> &'static str {
     match artifact_type {
@@ -370,101 +620,109 @@ pub fn get_s3_key(artifact_type: ArtifactType, id: &str) -> String {
     format!("{}/{id}", get_s3_prefix(artifact_type))
 }
 
-/// Get an S3 client for a given region.
-///
-/// This is a global cache of S3 clients, so that we don't need to create a new client for each
-/// request.
-async fn get_s3_client(s3_region: &str) -> Arc<S3Client> {
-    let client = {
-        let lock = S3_CLIENTS.read().await;
-        lock.get(s3_region).cloned()
-    };
-    if let Some(client) = client {
-        client
-    } else {
-        let client = {
-            let mut base = aws_config::load_defaults(BehaviorVersion::latest()).await.to_builder();
-            base.set_retry_config(Some(
-                RetryConfig::standard()
-                    .with_max_attempts(7)
-                    .with_max_backoff(Duration::from_secs(30)),
-            ))
-            .set_sleep_impl(default_async_sleep())
-            .set_region(Some(Region::new(s3_region.to_string())));
-            base.set_stalled_stream_protection(Some(StalledStreamProtectionConfig::disabled()));
-            // Refresh identity slightly more frequently than the default to avoid ExpiredToken
-            // errors
-            base.set_identity_cache(Some(
-                IdentityCache::lazy()
-                    .load_timeout(Duration::from_secs(10))
-                    .buffer_time(Duration::from_secs(300))
-                    .build(),
-            ));
-            let config = base.build();
-            S3Client::new(&config)
-        };
-        let client = Arc::new(client);
-        S3_CLIENTS.write().await.insert(s3_region.into(), client.clone());
-        client
-    }
+/// The URI of the artifact with the given type and ID in an S3 bucket.
+fn s3_uri(bucket: &str, artifact_type: ArtifactType, id: &str) -> Result<Url> {
+    Url::parse(&format!("s3://{bucket}/{}", get_s3_key(artifact_type, id))).map_err(|e| {
+        let message = format!("Invalid S3 bucket name {bucket}: {e}");
+        BackendError::new(ArtifactErrorKind::NotFound, message).into()
+    })
 }
 
-async fn download_s3_file(
-    client: &S3Client,
-    bucket: &str,
-    id: &str,
-    artifact_type: ArtifactType,
-) -> Result<Bytes> {
-    let key = get_s3_key(artifact_type, id);
-
-    let res = client
-        .get_object()
-        .bucket(bucket)
-        .key(key)
-        .send()
-        .await
-        .context("Failed to get object from S3")?;
-
-    let data = res.body.collect().await.context("Failed to read S3 object body")?;
-    let bytes = data.into_bytes();
-
-    Ok(bytes)
-}
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
 
-async fn download_https_file(uri: &str) -> Result<Bytes> {
-    let client = reqwest::Client::new();
-    let res = client
-        .get(uri)
-        .timeout(Duration::from_secs(60))
-        .send()
-        .await
-        .context("Failed to GET HTTPS URL")?;
-    if !res.status().is_success() {
-        return Err(anyhow!("Failed to download from HTTPS URL {uri}: status {}", res.status()));
+    use super::*;
+
+    fn artifact(id: &str) -> Artifact {
+        Artifact { id: id.to_string(), label: "test".to_string(), expiry: None }
+    }
+
+    #[tokio::test]
+    async fn test_dispatches_by_scheme() {
+        MemoryBackend::register();
+        let uri = "memory://artifacts/stdins/artifact_dispatch";
+        let stdin = vec![1u64, 2, 3];
+        artifact("artifact_dispatch")
+            .upload_to_uri(&stdin, uri, "", ArtifactType::Stdin)
+            .await
+            .unwrap();
+        let downloaded: Vec<u64> =
+            artifact("artifact_dispatch").download_stdin_from_uri(uri, "").await.unwrap();
+        assert_eq!(downloaded, stdin);
+
+        let unsupported = artifact("artifact_dispatch").download_raw_from_uri(
+            "ftp://artifacts/artifact_dispatch",
+            "",
+            ArtifactType::Stdin,
+        );
+        assert_eq!(unsupported.await.unwrap_err().kind(), ArtifactErrorKind::NotFound);
+
+        // Local files are not read unless registered, since requesters choose the URIs.
+        let local =
+            artifact("passwd").download_raw_from_uri("file:///etc/passwd", "", ArtifactType::Stdin);
+        assert_eq!(local.await.unwrap_err().kind(), ArtifactErrorKind::NotFound);
+
+        let missing = artifact("artifact_missing")
+            .download_stdin_from_uri::<Vec<u64>>("memory://artifacts/stdins/artifact_missing", "");
+        let error = missing.await.unwrap_err();
+        assert!(matches!(error, ArtifactError::NotFound { .. }));
+        assert_eq!((error.id(), error.artifact_type()), ("artifact_missing", ArtifactType::Stdin));
+    }
+
+    #[tokio::test]
+    async fn test_uses_registered_backend() {
+        // A scheme of its own, so that other tests keep their backends.
+        let backend = MemoryBackend::default();
+        register_backend("registered-test", Arc::new(backend.clone()));
+
+        let artifact = artifact("artifact_registered");
+        let uri = "registered-test://bucket/proofs/artifact_registered";
+        artifact.upload_to_uri(&7u32, uri, "", ArtifactType::Proof).await.unwrap();
+        assert_eq!(artifact.download_proof_from_uri::<u32>(uri, "").await.unwrap(), 7);
+        assert_eq!(backend.uris(), vec![uri.to_string()]);
+    }
+
+    #[test]
+    fn test_s3_uris_resolve_to_type_prefix() {
+        let artifact = artifact("artifact_bucket");
+        let resolved =
+            artifact.resolve_uri("s3://src/anything/artifact_bucket", ArtifactType::Proof).unwrap();
+        assert_eq!(resolved.as_str(), "s3://src/proofs/artifact_bucket");
+        let uri = s3_uri("dst", ArtifactType::Proof, &artifact.id).unwrap();
+        assert_eq!(uri.as_str(), "s3://dst/proofs/artifact_bucket");
+
+        // Other URIs resolve to themselves.
+        let memory = "memory://bucket/anything/artifact_bucket";
+        assert_eq!(artifact.resolve_uri(memory, ArtifactType::Proof).unwrap().as_str(), memory);
     }
-    let bytes = res.bytes().await.context("Failed to read HTTPS response body")?;
-    Ok(bytes)
-}
 
-async fn upload_file(
-    client: &S3Client,
-    bucket: &str,
-    id: &str,
-    artifact_type: ArtifactType,
-    data: Bytes,
-) -> Result<()> {
-    let key = get_s3_key(artifact_type, id);
-
-    let body = ByteStream::new(SdkBody::from(data));
-
-    client
-        .put_object()
-        .bucket(bucket)
-        .key(key)
-        .body(body)
-        .send()
-        .await
-        .context("Failed to upload object to S3")?;
-
-    Ok(())
+    #[tokio::test]
+    async fn test_encrypted_stdin_round_trip() {
+        MemoryBackend::register();
+        let uri = "memory://artifacts/stdins/artifact_private";
+        let stdin = vec![42u64; 1024];
+        let prover_key = ArtifactSecretKey::generate();
+        let artifact = artifact("artifact_private");
+        artifact
+            .upload_encrypted_stdin_to_uri(&stdin, uri, "", &prover_key.public_key())
+            .await
+            .unwrap();
+
+        let stored = MemoryBackend::shared().get(&Url::parse(uri).unwrap()).await.unwrap();
+        assert!(is_encrypted(&stored));
+        let downloaded: Vec<u64> =
+            artifact.download_stdin_from_uri_with_key(uri, "", Some(&prover_key)).await.unwrap();
+        assert_eq!(downloaded, stdin);
+
+        // Without the prover key, the stdin cannot be read.
+        let error = artifact.download_stdin_from_uri::<Vec<u64>>(uri, "").await.unwrap_err();
+        assert_eq!(error.kind(), ArtifactErrorKind::AccessDenied);
+        let other_key = ArtifactSecretKey::generate();
+        let error = artifact
+            .download_stdin_from_uri_with_key::<Vec<u64>>(uri, "", Some(&other_key))
+            .await
+            .unwrap_err();
+        assert_eq!(error.kind(), ArtifactErrorKind::Corrupt);
+    }
 }
@@ -0,0 +1,129 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, LazyLock, Mutex},
+    time::SystemTime,
+};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures::{stream, StreamExt};
+use url::Url;
+
+use crate::{register_backend, ArtifactBackend, ArtifactMetadata, ArtifactStream, BackendError};
+
+/// The store behind [`MemoryBackend::shared`].
+static SHARED: LazyLock<MemoryBackend> = LazyLock::new(MemoryBackend::default);
+
+/// An in-process artifact store, keyed by URI.
+///
+/// Clones share the same store.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryBackend {
+    /// The stored artifacts and their metadata, keyed by URI.
+    artifacts: Arc<Mutex<HashMap<String, (Bytes, ArtifactMetadata)>>>,
+}
+
+impl MemoryBackend {
+    /// The store used for `memory://` URIs once [`MemoryBackend::register`] is called.
+    #[must_use]
+    pub fn shared() -> Self {
+        SHARED.clone()
+    }
+
+    /// Handle `memory://` URIs with the [`MemoryBackend::shared`] store, for devnets and tests.
+    pub fn register() {
+        register_backend("memory", Arc::new(Self::shared()));
+    }
+
+    /// The URIs of the stored artifacts.
+    #[must_use]
+    pub fn uris(&self) -> Vec<String> {
+        self.artifacts.lock().unwrap().keys().cloned().collect()
+    }
+}
+
+#[async_trait]
+impl ArtifactBackend for MemoryBackend {
+    async fn get(&self, uri: &Url) -> Result<Bytes> {
+        self.artifacts
+            .lock()
+            .unwrap()
+            .get(uri.as_str())
+            .map(|(data, _)| data.clone())
+            .ok_or_else(|| BackendError::not_found(uri).into())
+    }
+
+    async fn get_stream(&self, uri: &Url) -> Result<ArtifactStream> {
+        let (data, metadata) = self
+            .artifacts
+            .lock()
+            .unwrap()
+            .get(uri.as_str())
+            .cloned()
+            .ok_or_else(|| BackendError::not_found(uri))?;
+        Ok(ArtifactStream {
+            offset: 0,
+            size: Some(data.len() as u64),
+            metadata,
+            body: stream::once(async move { Ok(data) }).boxed(),
+        })
+    }
+
+    async fn put(&self, uri: &Url, data: Bytes) -> Result<()> {
+        self.put_with_metadata(uri, data, &ArtifactMetadata::default()).await
+    }
+
+    async fn put_with_metadata(
+        &self,
+        uri: &Url,
+        data: Bytes,
+        metadata: &ArtifactMetadata,
+    ) -> Result<()> {
+        // Stamped once here, so `head` reports when the artifact was written.
+        let metadata = ArtifactMetadata {
+            size: Some(data.len() as u64),
+            last_modified: Some(SystemTime::now()),
+            ..metadata.clone()
+        };
+        self.artifacts.lock().unwrap().insert(uri.to_string(), (data, metadata));
+        Ok(())
+    }
+
+    async fn head(&self, uri: &Url) -> Result<Option<ArtifactMetadata>> {
+        Ok(self.artifacts.lock().unwrap().get(uri.as_str()).map(|(_, metadata)| metadata.clone()))
+    }
+
+    async fn delete(&self, uri: &Url) -> Result<()> {
+        self.artifacts.lock().unwrap().remove(uri.as_str());
+        Ok(())
+    }
+
+    async fn list(&self, prefix: &Url) -> Result<Vec<Url>> {
+        let artifacts = self.artifacts.lock().unwrap();
+        let uris = artifacts.keys().filter(|uri| uri.starts_with(prefix.as_str()));
+        uris.map(|uri| Ok(Url::parse(uri)?)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_head_reports_put_time() {
+        let backend = MemoryBackend::default();
+        let uri = Url::parse("memory://test/programs/p").unwrap();
+        let before = SystemTime::now();
+        backend.put(&uri, Bytes::from_static(b"program")).await.unwrap();
+        let after = SystemTime::now();
+
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        let metadata = backend.head(&uri).await.unwrap().unwrap();
+        let last_modified = metadata.last_modified.unwrap();
+        assert!(before <= last_modified && last_modified <= after);
+        assert_eq!(metadata.size, Some(7));
+    }
+}
@@ -0,0 +1,358 @@
+use std::path::Path;
+
+use alloy_signer::SignerSync;
+use alloy_signer_local::PrivateKeySigner;
+use anyhow::{anyhow, Context, Result};
+use bytes::Bytes;
+use futures::{stream, StreamExt};
+use reqwest::header::CONTENT_LENGTH;
+use serde::Serialize;
+use spn_artifact_types::{
+    artifact_store_client::ArtifactStoreClient, ArtifactType, CreateArtifactRequest,
+    CreateArtifactResponse,
+};
+use tokio::io::AsyncReadExt;
+use tonic::{
+    body::BoxBody,
+    client::GrpcService,
+    codegen::{Body, StdError},
+    transport::Channel,
+    Code, Status,
+};
+use tracing::{debug, instrument};
+
+use crate::{http::status_kind, with_retry, ArtifactErrorKind, BackendError};
+
+/// The message signed to authenticate `CreateArtifact` requests.
+pub const CREATE_ARTIFACT_MESSAGE: &[u8] = b"create_artifact";
+
+/// The default size of the chunks that payloads are streamed to presigned URLs in.
+pub const DEFAULT_UPLOAD_CHUNK_SIZE: usize = 8 * 1024 * 1024;
+
+/// The error for a failed `CreateArtifact` call, categorized by the code of `status` like the
+/// network client classifies its calls, so that only transient failures are retried.
+fn create_error(status: Status) -> anyhow::Error {
+    let kind = match status.code() {
+        Code::Unavailable
+        | Code::DeadlineExceeded
+        | Code::Internal
+        | Code::Aborted
+        | Code::ResourceExhausted
+        | Code::Unknown => ArtifactErrorKind::Transient,
+        Code::NotFound => ArtifactErrorKind::NotFound,
+        _ => ArtifactErrorKind::AccessDenied,
+    };
+    match kind {
+        ArtifactErrorKind::Transient => {
+            anyhow::Error::new(status).context("Failed to create artifact")
+        }
+        kind => BackendError::new(kind, format!("Failed to create artifact: {status}")).into(),
+    }
+}
+
+/// Uploads artifacts through the `ArtifactStore` service, without any storage credentials.
+///
+/// Every upload creates an artifact with `CreateArtifact`, authenticated by a signature of
+/// [`CREATE_ARTIFACT_MESSAGE`], and then sends the payload to the returned presigned URL. The
+/// payload is stored as is, without the compression of [`crate::Artifact`] uploads, so that any
+/// client can read it back.
+#[derive(Debug, Clone)]
+pub struct PresignedUploader<T = Channel> {
+    /// The client of the artifact store.
+    store: ArtifactStoreClient<T>,
+    /// The signer that authenticates the requests.
+    signer: PrivateKeySigner,
+    /// The client used for the uploads.
+    http: reqwest::Client,
+    /// The size of the chunks that payloads are streamed in.
+    chunk_size: usize,
+}
+
+impl<T> PresignedUploader<T>
+where
+    T: GrpcService<BoxBody> + Clone,
+    T::Error: Into<StdError>,
+    T::ResponseBody: Body<Data = Bytes> + Send + 'static,
+    <T::ResponseBody as Body>::Error: Into<StdError> + Send,
+{
+    /// Create an uploader that authenticates with `signer`.
+    #[must_use]
+    pub fn new(store: ArtifactStoreClient<T>, signer: PrivateKeySigner) -> Self {
+        Self { store, signer, http: reqwest::Client::new(), chunk_size: DEFAULT_UPLOAD_CHUNK_SIZE }
+    }
+
+    /// Send the uploads with `client`.
+    #[must_use]
+    pub fn with_http_client(mut self, client: reqwest::Client) -> Self {
+        self.http = client;
+        self
+    }
+
+    /// Stream payloads in chunks of `chunk_size` bytes.
+    #[must_use]
+    pub fn with_chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size.max(1);
+        self
+    }
+
+    /// Create an artifact of `artifact_type`, returning its URI and presigned upload URL.
+    ///
+    /// Transient failures of the call, such as an unavailable store, are retried with the shared
+    /// [`crate::ArtifactRetryPolicy`].
+    pub async fn create(&self, artifact_type: ArtifactType) -> Result<CreateArtifactResponse> {
+        let signature = self
+            .signer
+            .sign_message_sync(CREATE_ARTIFACT_MESSAGE)
+            .context("Failed to sign artifact request")?;
+        let request = CreateArtifactRequest {
+            signature: signature.as_bytes().to_vec(),
+            artifact_type: artifact_type.into(),
+        };
+        let response =
+            with_retry("create artifact", || {
+                let mut store = self.store.clone();
+                let request = request.clone();
+                async move {
+                    Ok(store.create_artifact(request).await.map_err(create_error)?.into_inner())
+                }
+            })
+            .await?;
+        debug!(uri = response.artifact_uri, "created artifact");
+        Ok(response)
+    }
+
+    /// Upload a serializable item as an artifact of `artifact_type`, returning its URI.
+    ///
+    /// The item is serialized with bincode.
+    pub async fn upload<S: Serialize>(
+        &self,
+        artifact_type: ArtifactType,
+        item: &S,
+    ) -> Result<String> {
+        let data = bincode::serialize(item).context("Failed to serialize artifact")?;
+        self.upload_raw(artifact_type, Bytes::from(data)).await
+    }
+
+    /// Upload raw bytes as an artifact of `artifact_type`, returning its URI.
+    ///
+    /// The URI can be used as the `stdin_uri` of a proof request, or to create a program.
+    #[instrument(skip(self, data), fields(size = data.len()))]
+    pub async fn upload_raw(&self, artifact_type: ArtifactType, data: Bytes) -> Result<String> {
+        let response = self.create(artifact_type).await?;
+        let size = data.len() as u64;
+        with_retry("upload presigned artifact", || {
+            let chunks = chunks(data.clone(), self.chunk_size).map(Ok::<_, std::io::Error>);
+            self.put(&response.artifact_presigned_url, size, reqwest::Body::wrap_stream(chunks))
+        })
+        .await?;
+        Ok(response.artifact_uri)
+    }
+
+    /// Upload the file at `path` as an artifact of `artifact_type`, returning its URI.
+    ///
+    /// The file is streamed in chunks, so it is never fully loaded in memory.
+    #[instrument(skip(self, path), fields(path = %path.as_ref().display()))]
+    pub async fn upload_file(
+        &self,
+        artifact_type: ArtifactType,
+        path: impl AsRef<Path>,
+    ) -> Result<String> {
+        let path = path.as_ref();
+        let response = self.create(artifact_type).await?;
+        let url = &response.artifact_presigned_url;
+        with_retry("upload presigned artifact", || async move {
+            let (size, body) = file_body(path, self.chunk_size).await?;
+            self.put(url, size, body).await
+        })
+        .await?;
+        Ok(response.artifact_uri)
+    }
+
+    /// Send `body` of `size` bytes to the presigned `url`.
+    async fn put(&self, url: &str, size: u64, body: reqwest::Body) -> Result<()> {
+        let res = self
+            .http
+            .put(url)
+            .header(CONTENT_LENGTH, size)
+            .body(body)
+            .send()
+            .await
+            .context("Failed to upload artifact to presigned URL")?;
+        let status = res.status();
+        if status.is_success() {
+            return Ok(());
+        }
+        let message = format!("Failed to upload artifact to presigned URL: status {status}");
+        match status_kind(status) {
+            ArtifactErrorKind::Transient => Err(anyhow!(message)),
+            // The presigned URL is rejected, for example because it expired.
+            kind => Err(BackendError::new(kind, message).into()),
+        }
+    }
+}
+
+/// Split `data` into chunks of at most `chunk_size` bytes, without copying.
+fn chunks(data: Bytes, chunk_size: usize) -> impl futures::Stream<Item = Bytes> {
+    stream::unfold(data, move |mut data| async move {
+        (!data.is_empty()).then(|| {
+            let chunk = data.split_to(chunk_size.min(data.len()));
+            (chunk, data)
+        })
+    })
+}
+
+/// A body that streams the file at `path` in chunks of `chunk_size` bytes, and its size.
+async fn file_body(path: &Path, chunk_size: usize) -> Result<(u64, reqwest::Body)> {
+    let file = tokio::fs::File::open(path)
+        .await
+        .with_context(|| format!("Failed to open artifact file {}", path.display()))?;
+    let size = file.metadata().await?.len();
+    let chunks = stream::try_unfold(file, move |mut file| async move {
+        let mut chunk = Vec::with_capacity(chunk_size);
+        (&mut file).take(chunk_size as u64).read_to_end(&mut chunk).await?;
+        Ok::<_, std::io::Error>((!chunk.is_empty()).then(|| (Bytes::from(chunk), file)))
+    });
+    Ok((size, reqwest::Body::wrap_stream(chunks)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc, Mutex,
+    };
+
+    use alloy_primitives::{Address, Signature};
+    use spn_artifact_types::artifact_store_server::{ArtifactStore, ArtifactStoreServer};
+    use tokio::{
+        io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+        net::TcpListener,
+    };
+    use tonic::{async_trait, transport::Server, Request, Response, Status};
+
+    use super::*;
+
+    /// An artifact store that hands out `upload_url` to the expected signer, after being
+    /// unavailable for the first `outages` calls.
+    #[derive(Default)]
+    struct Store {
+        signer: Address,
+        upload_url: String,
+        outages: u32,
+        calls: Arc<AtomicU32>,
+    }
+
+    #[async_trait]
+    impl ArtifactStore for Store {
+        async fn create_artifact(
+            &self,
+            request: Request<CreateArtifactRequest>,
+        ) -> Result<Response<CreateArtifactResponse>, Status> {
+            if self.calls.fetch_add(1, Ordering::SeqCst) < self.outages {
+                return Err(Status::unavailable("restarting"));
+            }
+            let request = request.into_inner();
+            let signature = Signature::try_from(&request.signature[..])
+                .map_err(|_| Status::invalid_argument("invalid signature"))?;
+            let signer = signature
+                .recover_address_from_msg(CREATE_ARTIFACT_MESSAGE)
+                .map_err(|_| Status::invalid_argument("invalid signature"))?;
+            if signer != self.signer {
+                return Err(Status::permission_denied("unknown signer"));
+            }
+            assert_eq!(request.artifact_type, ArtifactType::Stdin as i32);
+            Ok(Response::new(CreateArtifactResponse {
+                artifact_uri: "s3://artifacts/stdins/test".to_string(),
+                artifact_presigned_url: self.upload_url.clone(),
+            }))
+        }
+    }
+
+    /// Accept one PUT request and return its body.
+    async fn receive_upload(listener: TcpListener) -> Vec<u8> {
+        let (socket, _) = listener.accept().await.unwrap();
+        let mut socket = BufReader::new(socket);
+        let mut size = 0;
+        loop {
+            let mut line = String::new();
+            socket.read_line(&mut line).await.unwrap();
+            if line == "\r\n" {
+                break;
+            }
+            if let Some((name, value)) = line.split_once(':') {
+                if name.eq_ignore_ascii_case("content-length") {
+                    size = value.trim().parse().unwrap();
+                }
+            }
+        }
+        let mut body = vec![0; size];
+        socket.read_exact(&mut body).await.unwrap();
+        socket.write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n").await.unwrap();
+        body
+    }
+
+    /// Serve `store` and return an uploader for it that authenticates with `signer`.
+    async fn serve(store: Store, signer: PrivateKeySigner) -> PresignedUploader {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let incoming =
+            tonic::transport::server::TcpIncoming::from_listener(listener, true, None).unwrap();
+        let router = Server::builder().add_service(ArtifactStoreServer::new(store));
+        tokio::spawn(router.serve_with_incoming(incoming));
+
+        let channel =
+            Channel::from_shared(format!("http://{addr}")).unwrap().connect().await.unwrap();
+        PresignedUploader::new(ArtifactStoreClient::new(channel), signer)
+    }
+
+    #[tokio::test]
+    async fn test_does_not_retry_refused_requests() {
+        let calls = Arc::new(AtomicU32::new(0));
+        let store =
+            Store { signer: Address::repeat_byte(1), calls: calls.clone(), ..Store::default() };
+        let uploader = serve(store, PrivateKeySigner::random()).await;
+
+        let err = uploader.create(ArtifactType::Stdin).await.unwrap_err();
+        assert_eq!(ArtifactErrorKind::of(&err), ArtifactErrorKind::AccessDenied);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_uploads_to_presigned_url() {
+        let signer = PrivateKeySigner::random();
+        let upload_listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let calls = Arc::new(AtomicU32::new(0));
+        let store = Store {
+            signer: signer.address(),
+            upload_url: format!("http://{}/upload", upload_listener.local_addr().unwrap()),
+            outages: 1,
+            calls: calls.clone(),
+        };
+        let received = Arc::new(Mutex::new(None));
+        let upload = {
+            let received = received.clone();
+            tokio::spawn(async move {
+                *received.lock().unwrap() = Some(receive_upload(upload_listener).await);
+            })
+        };
+
+        let uploader = serve(store, signer).await.with_chunk_size(1000);
+        let stdin = vec![9u8; 10_500];
+        let uri = uploader.upload(ArtifactType::Stdin, &stdin).await.unwrap();
+        assert_eq!(uri, "s3://artifacts/stdins/test");
+        // The store was unavailable at first, so creating the artifact was retried.
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        upload.await.unwrap();
+        let body = received.lock().unwrap().take().unwrap();
+        assert_eq!(bincode::deserialize::<Vec<u8>>(&body).unwrap(), stdin);
+    }
+
+    #[tokio::test]
+    async fn test_chunks_cover_payload() {
+        let data = Bytes::from((0..2500u32).map(|i| i as u8).collect::<Vec<_>>());
+        let chunks: Vec<_> = chunks(data.clone(), 1000).collect().await;
+        assert_eq!(chunks.iter().map(Bytes::len).collect::<Vec<_>>(), vec![1000, 1000, 500]);
+        assert_eq!(chunks.concat(), data);
+    }
+}
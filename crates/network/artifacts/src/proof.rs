@@ -0,0 +1,232 @@
+use bytes::Bytes;
+use serde::de::DeserializeOwned;
+use spn_artifact_types::ArtifactType;
+use tracing::{info, warn};
+
+use crate::{extract_artifact_name, get_s3_key, Artifact, ArtifactError};
+
+/// One mebibyte.
+const MIB: usize = 1024 * 1024;
+
+/// The default size above which proofs are uploaded as artifacts.
+pub const DEFAULT_INLINE_PROOF_LIMIT: usize = MIB;
+
+/// The default size up to which proofs are sent inline when their upload fails, just under the
+/// 4 MiB default message limit of gRPC.
+pub const DEFAULT_INLINE_FALLBACK_LIMIT: usize = 4 * MIB - 64 * 1024;
+
+/// How proofs are attached to their fulfillment by [`attach_proof`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProofUploadPolicy {
+    /// Where proofs are uploaded, such as `s3://bucket`, or `None` to always send them inline.
+    ///
+    /// Proofs are uploaded under the proof prefix of the destination, like other artifacts.
+    pub destination: Option<String>,
+    /// The AWS region of S3 destinations.
+    pub s3_region: String,
+    /// The size above which proofs are uploaded instead of sent inline.
+    pub inline_limit: usize,
+    /// The size up to which proofs are still sent inline when their upload fails.
+    pub fallback_limit: usize,
+}
+
+impl Default for ProofUploadPolicy {
+    fn default() -> Self {
+        Self {
+            destination: None,
+            s3_region: String::new(),
+            inline_limit: DEFAULT_INLINE_PROOF_LIMIT,
+            fallback_limit: DEFAULT_INLINE_FALLBACK_LIMIT,
+        }
+    }
+}
+
+impl ProofUploadPolicy {
+    /// Upload large proofs to `destination`, in `s3_region` for S3 destinations.
+    #[must_use]
+    pub fn with_destination(mut self, destination: impl Into<String>, s3_region: &str) -> Self {
+        self.destination = Some(destination.into());
+        self.s3_region = s3_region.to_string();
+        self
+    }
+
+    /// Upload proofs larger than `inline_limit` bytes.
+    #[must_use]
+    pub fn with_inline_limit(mut self, inline_limit: usize) -> Self {
+        self.inline_limit = inline_limit;
+        self
+    }
+
+    /// Send proofs up to `fallback_limit` bytes inline when their upload fails.
+    #[must_use]
+    pub fn with_fallback_limit(mut self, fallback_limit: usize) -> Self {
+        self.fallback_limit = fallback_limit;
+        self
+    }
+}
+
+/// A proof attached to a fulfillment, either inline or as a proof artifact.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProofPayload {
+    /// The bincode-serialized proof.
+    Inline(Vec<u8>),
+    /// The URI of the proof artifact.
+    Uri(String),
+}
+
+impl ProofPayload {
+    /// The payload of a fulfillment with the `proof` and `proof_uri` fields. The URI takes
+    /// precedence when it is set.
+    #[must_use]
+    pub fn from_fields(proof: Vec<u8>, proof_uri: Option<String>) -> Self {
+        match proof_uri.filter(|uri| !uri.is_empty()) {
+            Some(uri) => Self::Uri(uri),
+            None => Self::Inline(proof),
+        }
+    }
+
+    /// The `proof` and `proof_uri` fields of a fulfillment with this payload.
+    #[must_use]
+    pub fn into_fields(self) -> (Vec<u8>, Option<String>) {
+        match self {
+            Self::Inline(proof) => (proof, None),
+            Self::Uri(uri) => (Vec::new(), Some(uri)),
+        }
+    }
+
+    /// The bincode-serialized proof, downloaded from the proof artifact if it is not inline.
+    pub async fn fetch(self, s3_region: &str) -> Result<Bytes, ArtifactError> {
+        match self {
+            Self::Inline(proof) => Ok(Bytes::from(proof)),
+            Self::Uri(uri) => {
+                let artifact = proof_artifact(&uri)?;
+                artifact.download_raw_from_uri(&uri, s3_region, ArtifactType::Proof).await
+            }
+        }
+    }
+
+    /// The proof, downloaded from the proof artifact if it is not inline, and deserialized with
+    /// bincode.
+    pub async fn fetch_proof<T: DeserializeOwned>(
+        self,
+        s3_region: &str,
+    ) -> Result<T, ArtifactError> {
+        let id = match &self {
+            Self::Inline(_) => "inline".to_string(),
+            Self::Uri(uri) => proof_artifact(uri)?.id,
+        };
+        let bytes = self.fetch(s3_region).await?;
+        bincode::deserialize(&bytes).map_err(|e| ArtifactError::Corrupt {
+            id,
+            artifact_type: ArtifactType::Proof,
+            source: anyhow::Error::new(e).context("Failed to deserialize proof"),
+        })
+    }
+}
+
+/// The artifact of the proof at `uri`.
+fn proof_artifact(uri: &str) -> Result<Artifact, ArtifactError> {
+    let id =
+        extract_artifact_name(uri).map_err(|e| ArtifactError::new(uri, ArtifactType::Proof, e))?;
+    Ok(Artifact { id, label: "proof".to_string(), expiry: None })
+}
+
+/// The ID of the proof artifact of the request `request_id`, so retried uploads overwrite each
+/// other.
+#[must_use]
+pub fn proof_artifact_id(request_id: &[u8]) -> String {
+    format!("proof_{}", hex::encode(request_id))
+}
+
+/// Attach the bincode-serialized `proof` of the request `request_id` to its fulfillment.
+///
+/// Proofs up to the inline limit of `policy`, or all proofs without a destination, are sent
+/// inline. Larger proofs are uploaded as proof artifacts and referenced by URI. When the upload
+/// fails, proofs that still fit the fallback limit are sent inline instead.
+pub async fn attach_proof(
+    request_id: &[u8],
+    proof: Vec<u8>,
+    policy: &ProofUploadPolicy,
+) -> Result<ProofPayload, ArtifactError> {
+    let Some(destination) = policy.destination.as_deref() else {
+        return Ok(ProofPayload::Inline(proof));
+    };
+    if proof.len() <= policy.inline_limit {
+        return Ok(ProofPayload::Inline(proof));
+    }
+
+    let artifact =
+        Artifact { id: proof_artifact_id(request_id), label: "proof".to_string(), expiry: None };
+    let uri = format!(
+        "{}/{}",
+        destination.trim_end_matches('/'),
+        get_s3_key(ArtifactType::Proof, &artifact.id)
+    );
+    let size = proof.len();
+    let data = Bytes::from(proof);
+    match artifact
+        .upload_raw_to_uri(data.clone(), &uri, &policy.s3_region, ArtifactType::Proof)
+        .await
+    {
+        Ok(()) => {
+            info!(%uri, size, "uploaded proof artifact");
+            Ok(ProofPayload::Uri(uri))
+        }
+        Err(e) if size <= policy.fallback_limit => {
+            warn!(%uri, size, "failed to upload proof, sending it inline: {e:?}");
+            Ok(ProofPayload::Inline(data.into()))
+        }
+        Err(e) => Err(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ArtifactErrorKind, MemoryBackend};
+
+    #[tokio::test]
+    async fn test_uploads_large_proofs() {
+        MemoryBackend::register();
+        let policy = ProofUploadPolicy::default()
+            .with_destination("memory://proofs-bucket", "")
+            .with_inline_limit(16);
+
+        let small = attach_proof(&[1; 32], vec![1; 16], &policy).await.unwrap();
+        assert_eq!(small, ProofPayload::Inline(vec![1; 16]));
+
+        let proof = bincode::serialize(&vec![7u64; 32]).unwrap();
+        let large = attach_proof(&[2; 32], proof.clone(), &policy).await.unwrap();
+        let uri = format!("memory://proofs-bucket/proofs/{}", proof_artifact_id(&[2; 32]));
+        assert_eq!(large, ProofPayload::Uri(uri));
+
+        // Consumers resolve both kinds of payload the same way.
+        let (bytes, proof_uri) = large.into_fields();
+        assert!(bytes.is_empty());
+        let payload = ProofPayload::from_fields(bytes, proof_uri);
+        assert_eq!(payload.clone().fetch("").await.unwrap(), proof);
+        assert_eq!(payload.fetch_proof::<Vec<u64>>("").await.unwrap(), vec![7; 32]);
+        assert_eq!(
+            small.fetch_proof::<Vec<u8>>("").await.unwrap_err().kind(),
+            ArtifactErrorKind::Corrupt
+        );
+
+        let missing = ProofPayload::Uri("memory://proofs-bucket/proofs/missing".to_string());
+        assert_eq!(missing.fetch("").await.unwrap_err().kind(), ArtifactErrorKind::NotFound);
+    }
+
+    #[tokio::test]
+    async fn test_falls_back_to_inline_proofs() {
+        // Writes to HTTP URLs are refused, so every upload fails.
+        let policy = ProofUploadPolicy::default()
+            .with_destination("https://example.com", "")
+            .with_inline_limit(16)
+            .with_fallback_limit(64);
+
+        let fallback = attach_proof(&[3; 32], vec![3; 64], &policy).await.unwrap();
+        assert_eq!(fallback, ProofPayload::Inline(vec![3; 64]));
+
+        let error = attach_proof(&[4; 32], vec![4; 65], &policy).await.unwrap_err();
+        assert_eq!(error.kind(), ArtifactErrorKind::AccessDenied);
+    }
+}
@@ -0,0 +1,132 @@
+use std::{
+    future::Future,
+    sync::{LazyLock, RwLock},
+    time::Duration,
+};
+
+use anyhow::Result;
+use tracing::warn;
+
+use crate::ArtifactErrorKind;
+
+/// The retry policy shared by every artifact backend.
+static ARTIFACT_RETRY_POLICY: LazyLock<RwLock<ArtifactRetryPolicy>> =
+    LazyLock::new(|| RwLock::new(ArtifactRetryPolicy::default()));
+
+/// How failed artifact operations are retried.
+///
+/// Delays grow exponentially from `initial_interval` by `multiplier` up to `max_interval`, and
+/// retries stop once `max_attempts` attempts were made. This is the only layer that retries
+/// artifact operations: the S3 client does not retry on its own, so attempts do not compound.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ArtifactRetryPolicy {
+    /// The delay before the first retry.
+    pub initial_interval: Duration,
+    /// The maximum delay between retries.
+    pub max_interval: Duration,
+    /// The factor the delay grows by after each retry.
+    pub multiplier: f64,
+    /// The maximum number of attempts, including the first one.
+    pub max_attempts: u32,
+}
+
+impl Default for ArtifactRetryPolicy {
+    fn default() -> Self {
+        Self {
+            initial_interval: Duration::from_secs(1),
+            max_interval: Duration::from_secs(30),
+            multiplier: 2.0,
+            max_attempts: 5,
+        }
+    }
+}
+
+impl ArtifactRetryPolicy {
+    /// The delay before retrying after the failed attempt number `attempt`, starting from 1.
+    #[must_use]
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let exponent = i32::try_from(attempt.saturating_sub(1)).unwrap_or(i32::MAX);
+        let delay = self.initial_interval.as_secs_f64() * self.multiplier.powi(exponent);
+        Duration::from_secs_f64(delay.min(self.max_interval.as_secs_f64()))
+    }
+}
+
+/// Set the retry policy shared by every artifact backend.
+pub fn set_artifact_retry_policy(policy: ArtifactRetryPolicy) {
+    *ARTIFACT_RETRY_POLICY.write().unwrap() = policy;
+}
+
+/// The retry policy shared by every artifact backend.
+#[must_use]
+pub fn artifact_retry_policy() -> ArtifactRetryPolicy {
+    *ARTIFACT_RETRY_POLICY.read().unwrap()
+}
+
+/// Whether retrying may fix `error`, i.e. it is categorized as [`ArtifactErrorKind::Transient`].
+#[must_use]
+pub fn is_transient(error: &anyhow::Error) -> bool {
+    ArtifactErrorKind::of(error).is_transient()
+}
+
+/// Run `operation` with the shared [`ArtifactRetryPolicy`], retrying transient failures.
+pub async fn with_retry<T, F, Fut>(operation_name: &str, operation: F) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    with_retry_policy(operation_name, &artifact_retry_policy(), operation).await
+}
+
+/// Run `operation` with `policy`, retrying transient failures.
+pub async fn with_retry_policy<T, F, Fut>(
+    operation_name: &str,
+    policy: &ArtifactRetryPolicy,
+    mut operation: F,
+) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    let mut attempt = 1;
+    loop {
+        match operation().await {
+            Ok(value) => return Ok(value),
+            Err(e) if attempt < policy.max_attempts && is_transient(&e) => {
+                let delay = policy.delay(attempt);
+                warn!(
+                    operation = operation_name,
+                    attempt,
+                    ?delay,
+                    "retrying artifact operation: {e:#}"
+                );
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::{anyhow, Context};
+
+    use super::*;
+    use crate::BackendError;
+
+    #[test]
+    fn test_delays_grow_up_to_the_maximum() {
+        let policy = ArtifactRetryPolicy::default();
+        let delays: Vec<_> = (1..=7).map(|attempt| policy.delay(attempt).as_secs()).collect();
+        assert_eq!(delays, vec![1, 2, 4, 8, 16, 30, 30]);
+    }
+
+    #[test]
+    fn test_permanent_errors_are_found_in_the_chain() {
+        let permanent = Err::<(), _>(BackendError::not_found("s3://bucket/stdins/a"))
+            .context("failed to download")
+            .unwrap_err();
+        assert!(!is_transient(&permanent));
+        assert!(is_transient(&anyhow!("connection reset")));
+    }
+}
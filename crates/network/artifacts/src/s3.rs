@@ -0,0 +1,895 @@
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    future::Future,
+    io::SeekFrom,
+    ops::Range,
+    path::Path,
+    sync::{Arc, LazyLock},
+    time::{Duration, SystemTime},
+};
+
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use aws_config::{retry::RetryConfig, BehaviorVersion, Region};
+use aws_sdk_s3::{
+    config::{http::HttpResponse, IdentityCache, StalledStreamProtectionConfig},
+    error::{DisplayErrorContext, SdkError},
+    primitives::{ByteStream, SdkBody},
+    types::{CompletedMultipartUpload, CompletedPart},
+    Client as S3Client,
+};
+use aws_smithy_async::rt::sleep::default_async_sleep;
+use bytes::Bytes;
+use futures::{stream, StreamExt, TryStreamExt};
+use reqwest::StatusCode;
+use tokio::{
+    io::{AsyncReadExt, AsyncSeekExt},
+    sync::RwLock,
+};
+use tracing::{debug, warn};
+use url::Url;
+
+use crate::{
+    http::status_kind, upload_config, ArtifactBackend, ArtifactErrorKind, ArtifactMetadata,
+    ArtifactStream, BackendError, ContentEncoding,
+};
+
+/// S3 Clients that are cached across the entire application.
+#[allow(clippy::type_complexity)]
+static S3_CLIENTS: LazyLock<Arc<RwLock<HashMap<String, Arc<S3Client>>>>> =
+    LazyLock::new(|| Arc::new(RwLock::new(HashMap::new())));
+
+/// The user metadata key the digest of an object is stored under.
+const SHA256_KEY: &str = "sha256";
+
+/// The user metadata key the encoding of an object is stored under.
+const ENCODING_KEY: &str = "encoding";
+
+/// The user metadata key the expiry of an object is stored under, as a Unix timestamp.
+const EXPIRES_AT_KEY: &str = "expires-at";
+
+/// The smallest part size S3 accepts, for every part but the last.
+pub const MIN_PART_SIZE: u64 = 5 * 1024 * 1024;
+
+/// How large objects are uploaded and copied in parts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MultipartConfig {
+    /// The size above which objects are uploaded and copied in parts.
+    pub threshold: u64,
+    /// The size of every part but the last. Sizes below [`MIN_PART_SIZE`] are raised to it.
+    pub part_size: u64,
+    /// The number of parts transferred at once.
+    pub concurrency: usize,
+}
+
+impl Default for MultipartConfig {
+    fn default() -> Self {
+        Self { threshold: 64 * 1024 * 1024, part_size: 16 * 1024 * 1024, concurrency: 4 }
+    }
+}
+
+/// An artifact store in S3, for `s3://bucket/key` URIs.
+///
+/// Objects over the [`MultipartConfig`] threshold are uploaded in parallel parts, and copies
+/// within the region are made server-side.
+#[derive(Debug, Clone)]
+pub struct S3Backend {
+    /// The AWS region of the buckets.
+    region: String,
+    /// The client to use instead of the shared client of the region.
+    client: Option<Arc<S3Client>>,
+    /// How large objects are transferred in parts.
+    multipart: MultipartConfig,
+}
+
+impl S3Backend {
+    /// Create a backend for the buckets in `region`, with the multipart configuration of the
+    /// [`crate::UploadConfig`].
+    #[must_use]
+    pub fn new(region: &str) -> Self {
+        Self { region: region.to_string(), client: None, multipart: upload_config().multipart }
+    }
+
+    /// Create a backend that sends requests with `client`, such as one for an S3-compatible
+    /// endpoint.
+    #[must_use]
+    pub fn with_client(region: &str, client: S3Client) -> Self {
+        Self { client: Some(Arc::new(client)), ..Self::new(region) }
+    }
+
+    /// Transfer large objects in parts as configured by `config`.
+    #[must_use]
+    pub fn with_multipart(mut self, config: MultipartConfig) -> Self {
+        self.multipart = config;
+        self
+    }
+
+    /// The AWS region of the buckets.
+    #[must_use]
+    pub fn region(&self) -> &str {
+        &self.region
+    }
+
+    /// The client for the region of the backend.
+    async fn client(&self) -> Arc<S3Client> {
+        match &self.client {
+            Some(client) => client.clone(),
+            None => get_s3_client(&self.region).await,
+        }
+    }
+
+    /// Create the object `key` in `bucket` from parts of a `size` byte payload.
+    ///
+    /// `upload_part` is called with the number, byte range and upload ID of every part, up to
+    /// [`MultipartConfig::concurrency`] at a time. If any part fails, the upload is aborted so
+    /// that the parts already stored are deleted.
+    async fn upload_multipart<F, Fut>(
+        &self,
+        bucket: &str,
+        key: &str,
+        size: u64,
+        metadata: &ArtifactMetadata,
+        upload_part: F,
+    ) -> Result<()>
+    where
+        F: Fn(i32, Range<u64>, String) -> Fut,
+        Fut: Future<Output = Result<CompletedPart>>,
+    {
+        let client = self.client().await;
+        let target = format!("s3://{bucket}/{key}");
+        let upload_id = client
+            .create_multipart_upload()
+            .bucket(bucket)
+            .key(key)
+            .set_metadata(user_metadata(metadata))
+            .send()
+            .await
+            .map_err(|e| s3_error(e, &target, "Failed to create multipart upload in S3"))?
+            .upload_id
+            .ok_or_else(|| anyhow!("S3 returned no multipart upload ID"))?;
+        let ranges = part_ranges(size, self.multipart.part_size.max(MIN_PART_SIZE));
+        debug!(bucket, key, size, parts = ranges.len(), "starting multipart upload");
+
+        let parts = stream::iter(ranges.into_iter().zip(1..))
+            .map(|(range, part_number)| upload_part(part_number, range, upload_id.clone()))
+            .buffer_unordered(self.multipart.concurrency.max(1))
+            .try_collect::<Vec<_>>()
+            .await;
+        let result = match parts {
+            Ok(mut parts) => {
+                parts.sort_by_key(|part| part.part_number);
+                client
+                    .complete_multipart_upload()
+                    .bucket(bucket)
+                    .key(key)
+                    .upload_id(&upload_id)
+                    .multipart_upload(
+                        CompletedMultipartUpload::builder().set_parts(Some(parts)).build(),
+                    )
+                    .send()
+                    .await
+                    .map_err(|e| s3_error(e, &target, "Failed to complete multipart upload in S3"))
+                    .map(|_| ())
+            }
+            Err(e) => Err(e),
+        };
+        if result.is_err() {
+            let abort =
+                client.abort_multipart_upload().bucket(bucket).key(key).upload_id(&upload_id);
+            if let Err(e) = abort.send().await {
+                warn!(bucket, key, %upload_id, "failed to abort multipart upload: {e}");
+            }
+        }
+        result
+    }
+
+    /// Upload one part of a multipart upload.
+    async fn upload_part(
+        &self,
+        bucket: &str,
+        key: &str,
+        upload_id: &str,
+        part_number: i32,
+        data: Bytes,
+    ) -> Result<CompletedPart> {
+        let res = self
+            .client()
+            .await
+            .upload_part()
+            .bucket(bucket)
+            .key(key)
+            .upload_id(upload_id)
+            .part_number(part_number)
+            .body(ByteStream::new(SdkBody::from(data)))
+            .send()
+            .await
+            .map_err(|e| {
+                let context = format!("Failed to upload part {part_number} to S3");
+                s3_error(e, format!("s3://{bucket}/{key}"), &context)
+            })?;
+        Ok(CompletedPart::builder().part_number(part_number).set_e_tag(res.e_tag).build())
+    }
+}
+
+/// The bucket and key of an `s3://bucket/key` URI.
+pub(crate) fn bucket_and_key(uri: &Url) -> Result<(&str, &str)> {
+    let invalid = |message| BackendError::new(ArtifactErrorKind::NotFound, message);
+    let bucket = uri.host_str().ok_or_else(|| invalid(format!("S3 URI missing bucket: {uri}")))?;
+    let key = uri.path().trim_start_matches('/');
+    if key.is_empty() {
+        return Err(invalid(format!("S3 URI missing key: {uri}")).into());
+    }
+    Ok((bucket, key))
+}
+
+/// The byte ranges of the parts of a `size` byte payload.
+fn part_ranges(size: u64, part_size: u64) -> Vec<Range<u64>> {
+    let part_size = part_size.max(1);
+    (0..size.div_ceil(part_size)).map(|i| i * part_size..((i + 1) * part_size).min(size)).collect()
+}
+
+/// Read the bytes in `range` of the file at `path`.
+async fn read_range(path: &Path, range: Range<u64>) -> Result<Bytes> {
+    let mut file = tokio::fs::File::open(path)
+        .await
+        .with_context(|| format!("Failed to open artifact file {}", path.display()))?;
+    file.seek(SeekFrom::Start(range.start)).await?;
+    let mut data = Vec::with_capacity((range.end - range.start) as usize);
+    file.take(range.end - range.start).read_to_end(&mut data).await?;
+    if (data.len() as u64) < range.end - range.start {
+        return Err(anyhow!("Artifact file {} changed during upload", path.display()));
+    }
+    Ok(Bytes::from(data))
+}
+
+/// The user metadata that records the digest, encoding and expiry of an object.
+fn user_metadata(metadata: &ArtifactMetadata) -> Option<HashMap<String, String>> {
+    let mut user_metadata = HashMap::new();
+    if let Some(sha256) = &metadata.sha256 {
+        user_metadata.insert(SHA256_KEY.to_string(), sha256.clone());
+    }
+    if let Some(encoding) = metadata.encoding {
+        user_metadata.insert(ENCODING_KEY.to_string(), encoding.as_str().to_string());
+    }
+    if let Some(expires_at) = metadata.expires_at {
+        user_metadata.insert(EXPIRES_AT_KEY.to_string(), expires_at.to_string());
+    }
+    (!user_metadata.is_empty()).then_some(user_metadata)
+}
+
+/// The digest, encoding and expiry recorded in the user metadata of an object.
+fn object_metadata(metadata: Option<&HashMap<String, String>>) -> ArtifactMetadata {
+    let value = |key| metadata.and_then(|metadata| metadata.get(key));
+    ArtifactMetadata {
+        size: None,
+        sha256: value(SHA256_KEY).cloned(),
+        encoding: value(ENCODING_KEY).and_then(|name| ContentEncoding::from_name(name)),
+        expires_at: value(EXPIRES_AT_KEY).and_then(|value| value.parse().ok()),
+        last_modified: None,
+        etag: None,
+    }
+}
+
+/// The error for a failed request on `uri`, categorized by the status of the response like
+/// HTTP downloads are with [`status_kind`]. Requests without a response, such as timeouts, are
+/// transient.
+fn s3_error<E>(error: SdkError<E, HttpResponse>, uri: impl Display, context: &str) -> anyhow::Error
+where
+    E: std::error::Error + Send + Sync + 'static,
+{
+    let status = error.raw_response().map(|res| res.status().as_u16());
+    let kind = match status.and_then(|status| StatusCode::from_u16(status).ok()) {
+        Some(status) => status_kind(status),
+        None => ArtifactErrorKind::Transient,
+    };
+    let message = format!("{context} {uri}: {}", DisplayErrorContext(&error));
+    match kind {
+        ArtifactErrorKind::Transient => anyhow::Error::new(error).context(message),
+        kind => BackendError::new(kind, message).into(),
+    }
+}
+
+#[async_trait]
+impl ArtifactBackend for S3Backend {
+    async fn get(&self, uri: &Url) -> Result<Bytes> {
+        let (bucket, key) = bucket_and_key(uri)?;
+        let res = self
+            .client()
+            .await
+            .get_object()
+            .bucket(bucket)
+            .key(key)
+            .send()
+            .await
+            .map_err(|e| s3_error(e, uri, "Failed to get object from S3"))?;
+
+        let data = res.body.collect().await.context("Failed to read S3 object body")?;
+        Ok(data.into_bytes())
+    }
+
+    async fn get_stream(&self, uri: &Url) -> Result<ArtifactStream> {
+        self.get_stream_from(uri, 0, None).await
+    }
+
+    async fn get_stream_from(
+        &self,
+        uri: &Url,
+        offset: u64,
+        etag: Option<&str>,
+    ) -> Result<ArtifactStream> {
+        let (bucket, key) = bucket_and_key(uri)?;
+        let client = self.client().await;
+        let get = |offset: u64, etag: Option<&str>| {
+            client
+                .get_object()
+                .bucket(bucket)
+                .key(key)
+                .set_range((offset > 0).then(|| format!("bytes={offset}-")))
+                .set_if_match(etag.filter(|_| offset > 0).map(str::to_string))
+                .send()
+        };
+        let (offset, result) = match get(offset, etag).await {
+            // The artifact changed since the first bytes were received, so the download
+            // restarts with the new artifact.
+            Err(e) if e.raw_response().is_some_and(|res| res.status().as_u16() == 412) => {
+                debug!(%uri, "artifact changed since the download started, restarting it");
+                (0, get(0, None).await)
+            }
+            result => (offset, result),
+        };
+        let res = match result {
+            Ok(res) => res,
+            Err(e) if e.as_service_error().is_some_and(|e| e.is_no_such_key()) => {
+                return Err(BackendError::not_found(uri).into());
+            }
+            Err(e) => return Err(s3_error(e, uri, "Failed to get object from S3")),
+        };
+
+        let size = res.content_length().and_then(|size| size.try_into().ok());
+        let metadata = ArtifactMetadata {
+            etag: res.e_tag().map(str::to_string),
+            ..object_metadata(res.metadata())
+        };
+        let body = stream::try_unfold(res.body, |mut body| async move {
+            let chunk = body.next().await.transpose().context("Failed to read S3 object body")?;
+            Ok(chunk.map(|chunk| (chunk, body)))
+        });
+        Ok(ArtifactStream { offset, size, metadata, body: body.boxed() })
+    }
+
+    async fn put(&self, uri: &Url, data: Bytes) -> Result<()> {
+        self.put_with_metadata(uri, data, &ArtifactMetadata::default()).await
+    }
+
+    async fn put_with_metadata(
+        &self,
+        uri: &Url,
+        data: Bytes,
+        metadata: &ArtifactMetadata,
+    ) -> Result<()> {
+        let (bucket, key) = bucket_and_key(uri)?;
+        let size = data.len() as u64;
+        if size > self.multipart.threshold {
+            return self
+                .upload_multipart(bucket, key, size, metadata, |part_number, range, upload_id| {
+                    let part = data.slice(range.start as usize..range.end as usize);
+                    async move { self.upload_part(bucket, key, &upload_id, part_number, part).await }
+                })
+                .await;
+        }
+
+        self.client()
+            .await
+            .put_object()
+            .bucket(bucket)
+            .key(key)
+            .body(ByteStream::new(SdkBody::from(data)))
+            .set_metadata(user_metadata(metadata))
+            .send()
+            .await
+            .map_err(|e| s3_error(e, uri, "Failed to upload object to S3"))?;
+        Ok(())
+    }
+
+    async fn put_file(&self, uri: &Url, path: &Path, metadata: &ArtifactMetadata) -> Result<()> {
+        let (bucket, key) = bucket_and_key(uri)?;
+        let size = tokio::fs::metadata(path)
+            .await
+            .with_context(|| format!("Failed to read artifact file {}", path.display()))?
+            .len();
+        if size <= self.multipart.threshold {
+            let data = read_range(path, 0..size).await?;
+            return self.put_with_metadata(uri, data, metadata).await;
+        }
+
+        // Only the parts being uploaded are held in memory.
+        self.upload_multipart(
+            bucket,
+            key,
+            size,
+            metadata,
+            |part_number, range, upload_id| async move {
+                let part = read_range(path, range).await?;
+                self.upload_part(bucket, key, &upload_id, part_number, part).await
+            },
+        )
+        .await
+    }
+
+    async fn head(&self, uri: &Url) -> Result<Option<ArtifactMetadata>> {
+        let (bucket, key) = bucket_and_key(uri)?;
+        match self.client().await.head_object().bucket(bucket).key(key).send().await {
+            Ok(res) => Ok(Some(ArtifactMetadata {
+                size: res.content_length().and_then(|size| size.try_into().ok()),
+                last_modified: res
+                    .last_modified()
+                    .and_then(|time| SystemTime::try_from(*time).ok()),
+                etag: res.e_tag().map(str::to_string),
+                ..object_metadata(res.metadata())
+            })),
+            Err(e) if e.as_service_error().is_some_and(|e| e.is_not_found()) => Ok(None),
+            Err(e) => Err(s3_error(e, uri, "Failed to get object metadata from S3")),
+        }
+    }
+
+    async fn copy(&self, src: &Url, dst: &Url) -> Result<()> {
+        let (src_bucket, src_key) = bucket_and_key(src)?;
+        let (dst_bucket, dst_key) = bucket_and_key(dst)?;
+        // The key is taken from the path of the URI, so it is already percent-encoded.
+        let source = format!("{src_bucket}/{src_key}");
+        let metadata = self.head(src).await?.ok_or_else(|| BackendError::not_found(src))?;
+        let size = metadata.size.ok_or_else(|| anyhow!("S3 did not report the size of {src}"))?;
+
+        // Objects are copied server-side, and the user metadata is copied along.
+        if size <= self.multipart.threshold {
+            self.client()
+                .await
+                .copy_object()
+                .bucket(dst_bucket)
+                .key(dst_key)
+                .copy_source(&source)
+                .send()
+                .await
+                .map_err(|e| s3_error(e, dst, "Failed to copy object in S3"))?;
+            return Ok(());
+        }
+
+        let source = &source;
+        self.upload_multipart(
+            dst_bucket,
+            dst_key,
+            size,
+            &metadata,
+            |part_number, range, upload_id| async move {
+                let res = self
+                    .client()
+                    .await
+                    .upload_part_copy()
+                    .bucket(dst_bucket)
+                    .key(dst_key)
+                    .upload_id(upload_id)
+                    .part_number(part_number)
+                    .copy_source(source)
+                    .copy_source_range(format!("bytes={}-{}", range.start, range.end - 1))
+                    .send()
+                    .await
+                    .map_err(|e| {
+                        let context = format!("Failed to copy part {part_number} in S3");
+                        s3_error(e, dst, &context)
+                    })?;
+                let e_tag = res.copy_part_result.and_then(|result| result.e_tag);
+                Ok::<_, anyhow::Error>(
+                    CompletedPart::builder().part_number(part_number).set_e_tag(e_tag).build(),
+                )
+            },
+        )
+        .await
+    }
+
+    async fn delete(&self, uri: &Url) -> Result<()> {
+        let (bucket, key) = bucket_and_key(uri)?;
+        self.client()
+            .await
+            .delete_object()
+            .bucket(bucket)
+            .key(key)
+            .send()
+            .await
+            .map_err(|e| s3_error(e, uri, "Failed to delete object from S3"))?;
+        Ok(())
+    }
+
+    async fn list(&self, prefix: &Url) -> Result<Vec<Url>> {
+        let bucket = prefix.host_str().ok_or_else(|| anyhow!("S3 URI missing bucket: {prefix}"))?;
+        let pages = self
+            .client()
+            .await
+            .list_objects_v2()
+            .bucket(bucket)
+            .prefix(prefix.path().trim_start_matches('/'))
+            .into_paginator()
+            .send()
+            .try_collect()
+            .await
+            .map_err(|e| s3_error(e, prefix, "Failed to list objects in S3"))?;
+        pages
+            .iter()
+            .flat_map(|page| page.contents())
+            .filter_map(|object| object.key())
+            .map(|key| {
+                Url::parse(&format!("s3://{bucket}/{key}"))
+                    .with_context(|| format!("Invalid S3 key: {key}"))
+            })
+            .collect()
+    }
+}
+
+/// Get an S3 client for a given region.
+///
+/// This is a global cache of S3 clients, so that we don't need to create a new client for each
+/// request.
+async fn get_s3_client(s3_region: &str) -> Arc<S3Client> {
+    let client = {
+        let lock = S3_CLIENTS.read().await;
+        lock.get(s3_region).cloned()
+    };
+    if let Some(client) = client {
+        client
+    } else {
+        let client = {
+            let mut base = aws_config::load_defaults(BehaviorVersion::latest()).await.to_builder();
+            // Failed requests are retried by the shared `ArtifactRetryPolicy` of every backend, so
+            // the client does not retry them again.
+            base.set_retry_config(Some(RetryConfig::disabled()))
+                .set_sleep_impl(default_async_sleep())
+                .set_region(Some(Region::new(s3_region.to_string())));
+            base.set_stalled_stream_protection(Some(StalledStreamProtectionConfig::disabled()));
+            // Refresh identity slightly more frequently than the default to avoid ExpiredToken
+            // errors
+            base.set_identity_cache(Some(
+                IdentityCache::lazy()
+                    .load_timeout(Duration::from_secs(10))
+                    .buffer_time(Duration::from_secs(300))
+                    .build(),
+            ));
+            let config = base.build();
+            S3Client::new(&config)
+        };
+        let client = Arc::new(client);
+        S3_CLIENTS.write().await.insert(s3_region.into(), client.clone());
+        client
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::BTreeMap,
+        sync::{
+            atomic::{AtomicU64, Ordering},
+            Mutex,
+        },
+    };
+
+    use aws_sdk_s3::config::Credentials;
+    use axum::{
+        body::Bytes as Body,
+        extract::{DefaultBodyLimit, State},
+        http::{HeaderMap, Method, StatusCode, Uri},
+        response::{IntoResponse, Response},
+        Router,
+    };
+
+    use super::*;
+    use crate::{encode, sha256_hex, UploadConfig};
+
+    /// An object stored by the [`StandIn`].
+    #[derive(Debug, Clone, Default)]
+    struct Object {
+        data: Vec<u8>,
+        metadata: Vec<(String, String)>,
+    }
+
+    /// A multipart upload in progress in the [`StandIn`].
+    #[derive(Debug, Default)]
+    struct Upload {
+        key: String,
+        metadata: Vec<(String, String)>,
+        parts: BTreeMap<i32, Vec<u8>>,
+    }
+
+    /// A local stand-in for the S3 operations used by [`S3Backend`], with path-style addressing.
+    #[derive(Debug, Default)]
+    struct StandIn {
+        objects: Mutex<HashMap<String, Object>>,
+        uploads: Mutex<HashMap<String, Upload>>,
+        /// The number of `GetObject` requests.
+        gets: AtomicU64,
+        /// The number of stored and copied parts.
+        parts: AtomicU64,
+        /// The part number that fails with access denied, if any.
+        failing_part: Option<i32>,
+    }
+
+    /// The `x-amz-meta-*` headers of a request.
+    fn amz_metadata(headers: &HeaderMap) -> Vec<(String, String)> {
+        headers
+            .iter()
+            .filter_map(|(name, value)| {
+                let name = name.as_str().strip_prefix("x-amz-meta-")?;
+                Some((name.to_string(), value.to_str().ok()?.to_string()))
+            })
+            .collect()
+    }
+
+    /// Decode an `aws-chunked` body, as sent by SDKs that add trailing checksums.
+    fn decode_chunked(mut body: &[u8]) -> Vec<u8> {
+        let mut data = Vec::new();
+        loop {
+            let line_end = body.windows(2).position(|w| w == b"\r\n").unwrap();
+            let line = std::str::from_utf8(&body[..line_end]).unwrap();
+            let size = usize::from_str_radix(line.split(';').next().unwrap(), 16).unwrap();
+            if size == 0 {
+                return data;
+            }
+            let chunk = &body[line_end + 2..];
+            data.extend_from_slice(&chunk[..size]);
+            body = &chunk[size + 2..];
+        }
+    }
+
+    /// The object named by an `x-amz-copy-source` header, and the range of it to copy.
+    fn copy_source(state: &StandIn, headers: &HeaderMap) -> Option<Vec<u8>> {
+        let source = headers.get("x-amz-copy-source")?.to_str().unwrap().trim_start_matches('/');
+        let data = state.objects.lock().unwrap().get(source).cloned().unwrap_or_default().data;
+        let Some(range) = headers.get("x-amz-copy-source-range") else {
+            return Some(data);
+        };
+        let (start, end) =
+            range.to_str().unwrap().strip_prefix("bytes=").unwrap().split_once('-').unwrap();
+        Some(data[start.parse().unwrap()..=end.parse().unwrap()].to_vec())
+    }
+
+    /// Handle a request to the stand-in.
+    async fn handle(
+        State(state): State<Arc<StandIn>>,
+        method: Method,
+        uri: Uri,
+        headers: HeaderMap,
+        body: Body,
+    ) -> Response {
+        let key = uri.path().trim_start_matches('/').to_string();
+        let query: HashMap<String, String> =
+            url::form_urlencoded::parse(uri.query().unwrap_or_default().as_bytes())
+                .into_owned()
+                .collect();
+        let body = if headers.get("content-encoding").is_some_and(|v| v == "aws-chunked") {
+            decode_chunked(&body)
+        } else {
+            body.to_vec()
+        };
+        let upload_id = query.get("uploadId").cloned();
+
+        match (method, upload_id) {
+            (Method::POST, None) if query.contains_key("uploads") => {
+                let upload_id = format!("upload-{}", state.uploads.lock().unwrap().len());
+                let upload = Upload { key, metadata: amz_metadata(&headers), ..Default::default() };
+                state.uploads.lock().unwrap().insert(upload_id.clone(), upload);
+                format!(
+                    "<InitiateMultipartUploadResult><UploadId>{upload_id}</UploadId>\
+                     </InitiateMultipartUploadResult>"
+                )
+                .into_response()
+            }
+            (Method::PUT, Some(upload_id)) => {
+                let part_number: i32 = query["partNumber"].parse().unwrap();
+                if state.failing_part == Some(part_number) {
+                    return (StatusCode::FORBIDDEN, "<Error><Code>AccessDenied</Code></Error>")
+                        .into_response();
+                }
+                let (data, copied) = match copy_source(&state, &headers) {
+                    Some(data) => (data, true),
+                    None => (body, false),
+                };
+                let mut uploads = state.uploads.lock().unwrap();
+                let Some(upload) = uploads.get_mut(&upload_id) else {
+                    return StatusCode::NOT_FOUND.into_response();
+                };
+                upload.parts.insert(part_number, data);
+                state.parts.fetch_add(1, Ordering::SeqCst);
+                let e_tag = format!("\"part-{part_number}\"");
+                if copied {
+                    format!("<CopyPartResult><ETag>{e_tag}</ETag></CopyPartResult>").into_response()
+                } else {
+                    ([("etag", e_tag)], "").into_response()
+                }
+            }
+            (Method::POST, Some(upload_id)) => {
+                let upload = state.uploads.lock().unwrap().remove(&upload_id).unwrap();
+                let data = upload.parts.into_values().flatten().collect();
+                let object = Object { data, metadata: upload.metadata };
+                state.objects.lock().unwrap().insert(upload.key, object);
+                "<CompleteMultipartUploadResult><ETag>\"done\"</ETag>\
+                 </CompleteMultipartUploadResult>"
+                    .into_response()
+            }
+            (Method::DELETE, Some(upload_id)) => {
+                state.uploads.lock().unwrap().remove(&upload_id);
+                StatusCode::NO_CONTENT.into_response()
+            }
+            (Method::PUT, None) => {
+                let mut objects = state.objects.lock().unwrap();
+                match headers.get("x-amz-copy-source") {
+                    Some(source) => {
+                        let source = source.to_str().unwrap().trim_start_matches('/');
+                        let object = objects[source].clone();
+                        objects.insert(key, object);
+                        "<CopyObjectResult><ETag>\"copy\"</ETag></CopyObjectResult>".into_response()
+                    }
+                    None => {
+                        objects
+                            .insert(key, Object { data: body, metadata: amz_metadata(&headers) });
+                        ([("etag", "\"object\"")], "").into_response()
+                    }
+                }
+            }
+            (method @ (Method::GET | Method::HEAD), None) => {
+                let Some(object) = state.objects.lock().unwrap().get(&key).cloned() else {
+                    return StatusCode::NOT_FOUND.into_response();
+                };
+                // Objects are tagged with the digest of their contents.
+                let e_tag = format!("\"{}\"", sha256_hex(&object.data));
+                if headers.get("if-match").is_some_and(|value| value != e_tag.as_str()) {
+                    return StatusCode::PRECONDITION_FAILED.into_response();
+                }
+                let offset = headers.get("range").map(|value| {
+                    let value = value.to_str().unwrap().strip_prefix("bytes=").unwrap();
+                    value.strip_suffix('-').unwrap().parse::<usize>().unwrap()
+                });
+                let mut headers = HeaderMap::new();
+                headers.insert("etag", e_tag.parse().unwrap());
+                for (name, value) in object.metadata {
+                    headers.insert(
+                        axum::http::HeaderName::try_from(format!("x-amz-meta-{name}")).unwrap(),
+                        value.parse().unwrap(),
+                    );
+                }
+                if method == Method::GET {
+                    state.gets.fetch_add(1, Ordering::SeqCst);
+                }
+                // The body of HEAD responses is dropped, but it still sets their content length.
+                match offset {
+                    Some(offset) => {
+                        (StatusCode::PARTIAL_CONTENT, headers, object.data[offset..].to_vec())
+                            .into_response()
+                    }
+                    None => (headers, object.data).into_response(),
+                }
+            }
+            (Method::DELETE, None) => {
+                state.objects.lock().unwrap().remove(&key);
+                StatusCode::NO_CONTENT.into_response()
+            }
+            _ => StatusCode::NOT_IMPLEMENTED.into_response(),
+        }
+    }
+
+    /// The size of the payloads uploaded in three parts by the backends of [`serve`].
+    const PAYLOAD_SIZE: u64 = 2 * MIN_PART_SIZE + 1000;
+
+    /// Serve `state` and return a backend that uploads in parts of [`MIN_PART_SIZE`] above 2000
+    /// bytes.
+    async fn serve(state: Arc<StandIn>) -> S3Backend {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let app =
+            Router::new().fallback(handle).layer(DefaultBodyLimit::disable()).with_state(state);
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let config = aws_sdk_s3::Config::builder()
+            .behavior_version(BehaviorVersion::latest())
+            .region(Region::new("us-east-1"))
+            .endpoint_url(format!("http://{addr}"))
+            .force_path_style(true)
+            .credentials_provider(Credentials::new("test", "test", None, None, "test"))
+            .build();
+        S3Backend::with_client("us-east-1", S3Client::from_conf(config)).with_multipart(
+            MultipartConfig { threshold: 2000, part_size: MIN_PART_SIZE, concurrency: 3 },
+        )
+    }
+
+    #[tokio::test]
+    async fn test_multipart_upload_and_server_side_copy() {
+        let state = Arc::new(StandIn::default());
+        let backend = serve(state.clone()).await;
+        let payload: Vec<u8> = (0..PAYLOAD_SIZE).map(|i| (i % 251) as u8).collect();
+        let config = UploadConfig { compression_threshold: None, ..Default::default() };
+        let (data, metadata) = encode(Bytes::from(payload.clone()), &config).unwrap();
+
+        // Large objects are uploaded in parts, with their metadata.
+        let src = Url::parse("s3://bucket/proofs/large").unwrap();
+        backend.put_with_metadata(&src, data, &metadata).await.unwrap();
+        assert_eq!(state.parts.load(Ordering::SeqCst), 3);
+        let head = backend.head(&src).await.unwrap().unwrap();
+        assert_eq!(head, metadata);
+        assert_eq!(backend.get(&src).await.unwrap(), payload);
+
+        // Files are uploaded in parts too.
+        let path = std::env::temp_dir().join(format!("spn-artifacts-s3-{}", std::process::id()));
+        std::fs::write(&path, &payload).unwrap();
+        let from_file = Url::parse("s3://bucket/proofs/file").unwrap();
+        backend.put_file(&from_file, &path, &metadata).await.unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(state.parts.load(Ordering::SeqCst), 6);
+        assert_eq!(backend.get(&from_file).await.unwrap(), payload);
+
+        // Copies are made server-side, in parts above the threshold.
+        let gets = state.gets.load(Ordering::SeqCst);
+        let dst = Url::parse("s3://other/proofs/large").unwrap();
+        backend.copy(&src, &dst).await.unwrap();
+        assert_eq!(state.parts.load(Ordering::SeqCst), 9);
+        assert_eq!(backend.head(&dst).await.unwrap(), Some(head));
+
+        let small = Url::parse("s3://bucket/stdins/small").unwrap();
+        backend.put(&small, Bytes::from_static(b"small")).await.unwrap();
+        let small_copy = Url::parse("s3://other/stdins/small").unwrap();
+        backend.copy(&small, &small_copy).await.unwrap();
+        assert_eq!(state.parts.load(Ordering::SeqCst), 9);
+        assert_eq!(state.gets.load(Ordering::SeqCst), gets);
+        assert_eq!(backend.get(&dst).await.unwrap(), payload);
+        assert_eq!(backend.get(&small_copy).await.unwrap(), "small");
+    }
+
+    #[tokio::test]
+    async fn test_aborts_failed_multipart_upload() {
+        let state = Arc::new(StandIn { failing_part: Some(3), ..Default::default() });
+        let backend = serve(state.clone()).await;
+
+        let uri = Url::parse("s3://bucket/proofs/failed").unwrap();
+        let result =
+            backend.put(&uri, Bytes::from(vec![1; usize::try_from(PAYLOAD_SIZE).unwrap()])).await;
+        // The refusal of the part is categorized, so it is not retried as a network error.
+        assert_eq!(ArtifactErrorKind::of(&result.unwrap_err()), ArtifactErrorKind::AccessDenied);
+        assert!(state.uploads.lock().unwrap().is_empty());
+        assert_eq!(backend.head(&uri).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_raises_part_size_to_s3_minimum() {
+        let state = Arc::new(StandIn::default());
+        let backend = serve(state.clone()).await.with_multipart(MultipartConfig {
+            threshold: 2000,
+            part_size: 1000,
+            concurrency: 3,
+        });
+
+        // Parts of 1000 bytes would be rejected by S3, so the payload fits in a single part.
+        let uri = Url::parse("s3://bucket/proofs/small-parts").unwrap();
+        backend.put(&uri, Bytes::from(vec![1; 4500])).await.unwrap();
+        assert_eq!(state.parts.load(Ordering::SeqCst), 1);
+        assert_eq!(backend.get(&uri).await.unwrap().len(), 4500);
+    }
+
+    #[tokio::test]
+    async fn test_resumes_unchanged_and_restarts_changed_objects() {
+        let state = Arc::new(StandIn::default());
+        let backend = serve(state.clone()).await;
+        let uri = Url::parse("s3://bucket/programs/resumed").unwrap();
+        backend.put(&uri, Bytes::from(vec![1; 100])).await.unwrap();
+        let etag = backend.head(&uri).await.unwrap().unwrap().etag.unwrap();
+        let read = |stream: ArtifactStream| async move {
+            let body: Vec<Bytes> = stream.body.try_collect().await.unwrap();
+            (stream.offset, body.concat())
+        };
+
+        // Unchanged objects are resumed at the offset.
+        let stream = backend.get_stream_from(&uri, 40, Some(&etag)).await.unwrap();
+        assert_eq!(read(stream).await, (40, vec![1; 60]));
+
+        // Changed objects are sent from their start.
+        backend.put(&uri, Bytes::from(vec![2; 80])).await.unwrap();
+        let stream = backend.get_stream_from(&uri, 40, Some(&etag)).await.unwrap();
+        assert_eq!(read(stream).await, (0, vec![2; 80]));
+    }
+}
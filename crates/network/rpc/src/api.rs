@@ -0,0 +1,377 @@
+use futures::{stream::BoxStream, StreamExt};
+use spn_network_types::{
+    prover_network_client::ProverNetworkClient, BidRequest, BidResponse, FailFulfillmentRequest,
+    FailFulfillmentResponse, FulfillProofRequest, FulfillProofResponse, GetBalanceRequest,
+    GetBalanceResponse, GetDelegationParamsRequest, GetDelegationParamsResponse,
+    GetFilteredBalanceLogsRequest, GetFilteredBalanceLogsResponse, GetFilteredBidHistoryRequest,
+    GetFilteredBidHistoryResponse, GetFilteredProofRequestsRequest,
+    GetFilteredProofRequestsResponse, GetFilteredProverStakeBalanceLogsRequest,
+    GetFilteredProverStakeBalanceLogsResponse, GetFilteredWithdrawalReceiptsRequest,
+    GetFilteredWithdrawalReceiptsResponse, GetNonceRequest, GetNonceResponse, GetOwnerRequest,
+    GetOwnerResponse, GetProofRequestDetailsRequest, GetProofRequestDetailsResponse,
+    GetProverStakeBalanceRequest, GetProverStakeBalanceResponse, GetTransferParamsRequest,
+    GetTransferParamsResponse, GetWithdrawParamsRequest, GetWithdrawParamsResponse, ProofRequest,
+    RequestProofRequest, RequestProofResponse, SetAccountNameRequest, SetAccountNameResponse,
+    SetDelegationRequest, SetDelegationResponse, TransferRequest, TransferResponse,
+    WithdrawRequest, WithdrawResponse,
+};
+use tonic::{
+    async_trait,
+    body::BoxBody,
+    client::GrpcService,
+    codegen::{Body, Bytes, StdError},
+    Status,
+};
+
+use crate::middleware::{log_request, log_response};
+
+/// The RPCs of the prover network that are used by a node and by [`crate::NetworkClient`].
+///
+/// This is implemented for the generated [`ProverNetworkClient`], and can be implemented by
+/// in-memory fakes so that bidders and provers can be tested without a gRPC server.
+#[async_trait]
+pub trait ProverNetworkApi: Clone + Send + Sync + 'static {
+    /// Get the proof requests that match the filter.
+    async fn get_filtered_proof_requests(
+        &self,
+        request: GetFilteredProofRequestsRequest,
+    ) -> Result<GetFilteredProofRequestsResponse, Status>;
+
+    /// Subscribe to the proof requests that match the filter.
+    async fn subscribe_proof_requests(
+        &self,
+        request: GetFilteredProofRequestsRequest,
+    ) -> Result<BoxStream<'static, Result<ProofRequest, Status>>, Status>;
+
+    /// Get the details of a proof request.
+    async fn get_proof_request_details(
+        &self,
+        request: GetProofRequestDetailsRequest,
+    ) -> Result<GetProofRequestDetailsResponse, Status>;
+
+    /// Get the nonce of an account.
+    async fn get_nonce(&self, request: GetNonceRequest) -> Result<GetNonceResponse, Status>;
+
+    /// Get the owner of an account.
+    async fn get_owner(&self, request: GetOwnerRequest) -> Result<GetOwnerResponse, Status>;
+
+    /// Get the balance of an account.
+    async fn get_balance(&self, request: GetBalanceRequest) -> Result<GetBalanceResponse, Status>;
+
+    /// Get the stake balance of a prover.
+    async fn get_prover_stake_balance(
+        &self,
+        request: GetProverStakeBalanceRequest,
+    ) -> Result<GetProverStakeBalanceResponse, Status>;
+
+    /// Get the balance logs that match the filter.
+    async fn get_filtered_balance_logs(
+        &self,
+        request: GetFilteredBalanceLogsRequest,
+    ) -> Result<GetFilteredBalanceLogsResponse, Status>;
+
+    /// Get the bid history that matches the filter.
+    async fn get_filtered_bid_history(
+        &self,
+        request: GetFilteredBidHistoryRequest,
+    ) -> Result<GetFilteredBidHistoryResponse, Status>;
+
+    /// Get the prover stake balance logs that match the filter.
+    async fn get_filtered_prover_stake_balance_logs(
+        &self,
+        request: GetFilteredProverStakeBalanceLogsRequest,
+    ) -> Result<GetFilteredProverStakeBalanceLogsResponse, Status>;
+
+    /// Get the withdrawal receipts that match the filter.
+    async fn get_filtered_withdrawal_receipts(
+        &self,
+        request: GetFilteredWithdrawalReceiptsRequest,
+    ) -> Result<GetFilteredWithdrawalReceiptsResponse, Status>;
+
+    /// Bid on a proof request.
+    async fn bid(&self, request: BidRequest) -> Result<BidResponse, Status>;
+
+    /// Fulfill a proof request.
+    async fn fulfill_proof(
+        &self,
+        request: FulfillProofRequest,
+    ) -> Result<FulfillProofResponse, Status>;
+
+    /// Fail the fulfillment of a proof request.
+    async fn fail_fulfillment(
+        &self,
+        request: FailFulfillmentRequest,
+    ) -> Result<FailFulfillmentResponse, Status>;
+
+    /// Request a proof.
+    async fn request_proof(
+        &self,
+        request: RequestProofRequest,
+    ) -> Result<RequestProofResponse, Status>;
+
+    /// Get the auctioneer and fee of transfers.
+    async fn get_transfer_params(
+        &self,
+        request: GetTransferParamsRequest,
+    ) -> Result<GetTransferParamsResponse, Status>;
+
+    /// Transfer credits to another account.
+    async fn transfer(&self, request: TransferRequest) -> Result<TransferResponse, Status>;
+
+    /// Get the auctioneer and fee of withdrawals.
+    async fn get_withdraw_params(
+        &self,
+        request: GetWithdrawParamsRequest,
+    ) -> Result<GetWithdrawParamsResponse, Status>;
+
+    /// Withdraw credits from an account.
+    async fn withdraw(&self, request: WithdrawRequest) -> Result<WithdrawResponse, Status>;
+
+    /// Get the auctioneer and fee of delegations.
+    async fn get_delegation_params(
+        &self,
+        request: GetDelegationParamsRequest,
+    ) -> Result<GetDelegationParamsResponse, Status>;
+
+    /// Set the delegate of a prover.
+    async fn set_delegation(
+        &self,
+        request: SetDelegationRequest,
+    ) -> Result<SetDelegationResponse, Status>;
+
+    /// Set the name of an account.
+    async fn set_account_name(
+        &self,
+        request: SetAccountNameRequest,
+    ) -> Result<SetAccountNameResponse, Status>;
+}
+
+#[async_trait]
+impl<T> ProverNetworkApi for ProverNetworkClient<T>
+where
+    T: GrpcService<BoxBody> + Clone + Send + Sync + 'static,
+    T::Error: Into<StdError>,
+    T::ResponseBody: Body<Data = Bytes> + Send + 'static,
+    <T::ResponseBody as Body>::Error: Into<StdError> + Send,
+    T::Future: Send,
+{
+    async fn get_filtered_proof_requests(
+        &self,
+        request: GetFilteredProofRequestsRequest,
+    ) -> Result<GetFilteredProofRequestsResponse, Status> {
+        let mut client = self.clone();
+        log_request("GetFilteredProofRequests", &request);
+        log_response(
+            "GetFilteredProofRequests",
+            ProverNetworkClient::get_filtered_proof_requests(&mut client, request).await,
+        )
+    }
+
+    async fn subscribe_proof_requests(
+        &self,
+        request: GetFilteredProofRequestsRequest,
+    ) -> Result<BoxStream<'static, Result<ProofRequest, Status>>, Status> {
+        let mut client = self.clone();
+        log_request("SubscribeProofRequests", &request);
+        let stream = ProverNetworkClient::subscribe_proof_requests(&mut client, request).await;
+        Ok(log_response("SubscribeProofRequests", stream)?.boxed())
+    }
+
+    async fn get_proof_request_details(
+        &self,
+        request: GetProofRequestDetailsRequest,
+    ) -> Result<GetProofRequestDetailsResponse, Status> {
+        let mut client = self.clone();
+        log_request("GetProofRequestDetails", &request);
+        log_response(
+            "GetProofRequestDetails",
+            ProverNetworkClient::get_proof_request_details(&mut client, request).await,
+        )
+    }
+
+    async fn get_nonce(&self, request: GetNonceRequest) -> Result<GetNonceResponse, Status> {
+        let mut client = self.clone();
+        log_request("GetNonce", &request);
+        log_response("GetNonce", ProverNetworkClient::get_nonce(&mut client, request).await)
+    }
+
+    async fn get_owner(&self, request: GetOwnerRequest) -> Result<GetOwnerResponse, Status> {
+        let mut client = self.clone();
+        log_request("GetOwner", &request);
+        log_response("GetOwner", ProverNetworkClient::get_owner(&mut client, request).await)
+    }
+
+    async fn get_balance(&self, request: GetBalanceRequest) -> Result<GetBalanceResponse, Status> {
+        let mut client = self.clone();
+        log_request("GetBalance", &request);
+        log_response("GetBalance", ProverNetworkClient::get_balance(&mut client, request).await)
+    }
+
+    async fn get_prover_stake_balance(
+        &self,
+        request: GetProverStakeBalanceRequest,
+    ) -> Result<GetProverStakeBalanceResponse, Status> {
+        let mut client = self.clone();
+        log_request("GetProverStakeBalance", &request);
+        log_response(
+            "GetProverStakeBalance",
+            ProverNetworkClient::get_prover_stake_balance(&mut client, request).await,
+        )
+    }
+
+    async fn get_filtered_balance_logs(
+        &self,
+        request: GetFilteredBalanceLogsRequest,
+    ) -> Result<GetFilteredBalanceLogsResponse, Status> {
+        let mut client = self.clone();
+        log_request("GetFilteredBalanceLogs", &request);
+        log_response(
+            "GetFilteredBalanceLogs",
+            ProverNetworkClient::get_filtered_balance_logs(&mut client, request).await,
+        )
+    }
+
+    async fn get_filtered_bid_history(
+        &self,
+        request: GetFilteredBidHistoryRequest,
+    ) -> Result<GetFilteredBidHistoryResponse, Status> {
+        let mut client = self.clone();
+        log_request("GetFilteredBidHistory", &request);
+        log_response(
+            "GetFilteredBidHistory",
+            ProverNetworkClient::get_filtered_bid_history(&mut client, request).await,
+        )
+    }
+
+    async fn get_filtered_prover_stake_balance_logs(
+        &self,
+        request: GetFilteredProverStakeBalanceLogsRequest,
+    ) -> Result<GetFilteredProverStakeBalanceLogsResponse, Status> {
+        let mut client = self.clone();
+        log_request("GetFilteredProverStakeBalanceLogs", &request);
+        log_response(
+            "GetFilteredProverStakeBalanceLogs",
+            ProverNetworkClient::get_filtered_prover_stake_balance_logs(&mut client, request).await,
+        )
+    }
+
+    async fn get_filtered_withdrawal_receipts(
+        &self,
+        request: GetFilteredWithdrawalReceiptsRequest,
+    ) -> Result<GetFilteredWithdrawalReceiptsResponse, Status> {
+        let mut client = self.clone();
+        log_request("GetFilteredWithdrawalReceipts", &request);
+        log_response(
+            "GetFilteredWithdrawalReceipts",
+            ProverNetworkClient::get_filtered_withdrawal_receipts(&mut client, request).await,
+        )
+    }
+
+    async fn bid(&self, request: BidRequest) -> Result<BidResponse, Status> {
+        let mut client = self.clone();
+        log_request("Bid", &request);
+        log_response("Bid", ProverNetworkClient::bid(&mut client, request).await)
+    }
+
+    async fn fulfill_proof(
+        &self,
+        request: FulfillProofRequest,
+    ) -> Result<FulfillProofResponse, Status> {
+        let mut client = self.clone();
+        log_request("FulfillProof", &request);
+        log_response("FulfillProof", ProverNetworkClient::fulfill_proof(&mut client, request).await)
+    }
+
+    async fn fail_fulfillment(
+        &self,
+        request: FailFulfillmentRequest,
+    ) -> Result<FailFulfillmentResponse, Status> {
+        let mut client = self.clone();
+        log_request("FailFulfillment", &request);
+        log_response(
+            "FailFulfillment",
+            ProverNetworkClient::fail_fulfillment(&mut client, request).await,
+        )
+    }
+
+    async fn request_proof(
+        &self,
+        request: RequestProofRequest,
+    ) -> Result<RequestProofResponse, Status> {
+        let mut client = self.clone();
+        log_request("RequestProof", &request);
+        log_response("RequestProof", ProverNetworkClient::request_proof(&mut client, request).await)
+    }
+
+    async fn get_transfer_params(
+        &self,
+        request: GetTransferParamsRequest,
+    ) -> Result<GetTransferParamsResponse, Status> {
+        let mut client = self.clone();
+        log_request("GetTransferParams", &request);
+        log_response(
+            "GetTransferParams",
+            ProverNetworkClient::get_transfer_params(&mut client, request).await,
+        )
+    }
+
+    async fn transfer(&self, request: TransferRequest) -> Result<TransferResponse, Status> {
+        let mut client = self.clone();
+        log_request("Transfer", &request);
+        log_response("Transfer", ProverNetworkClient::transfer(&mut client, request).await)
+    }
+
+    async fn get_withdraw_params(
+        &self,
+        request: GetWithdrawParamsRequest,
+    ) -> Result<GetWithdrawParamsResponse, Status> {
+        let mut client = self.clone();
+        log_request("GetWithdrawParams", &request);
+        log_response(
+            "GetWithdrawParams",
+            ProverNetworkClient::get_withdraw_params(&mut client, request).await,
+        )
+    }
+
+    async fn withdraw(&self, request: WithdrawRequest) -> Result<WithdrawResponse, Status> {
+        let mut client = self.clone();
+        log_request("Withdraw", &request);
+        log_response("Withdraw", ProverNetworkClient::withdraw(&mut client, request).await)
+    }
+
+    async fn get_delegation_params(
+        &self,
+        request: GetDelegationParamsRequest,
+    ) -> Result<GetDelegationParamsResponse, Status> {
+        let mut client = self.clone();
+        log_request("GetDelegationParams", &request);
+        log_response(
+            "GetDelegationParams",
+            ProverNetworkClient::get_delegation_params(&mut client, request).await,
+        )
+    }
+
+    async fn set_delegation(
+        &self,
+        request: SetDelegationRequest,
+    ) -> Result<SetDelegationResponse, Status> {
+        let mut client = self.clone();
+        log_request("SetDelegation", &request);
+        log_response(
+            "SetDelegation",
+            ProverNetworkClient::set_delegation(&mut client, request).await,
+        )
+    }
+
+    async fn set_account_name(
+        &self,
+        request: SetAccountNameRequest,
+    ) -> Result<SetAccountNameResponse, Status> {
+        let mut client = self.clone();
+        log_request("SetAccountName", &request);
+        log_response(
+            "SetAccountName",
+            ProverNetworkClient::set_account_name(&mut client, request).await,
+        )
+    }
+}
@@ -0,0 +1,211 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, LazyLock, Mutex},
+    time::{Duration, Instant},
+};
+
+use metrics::{counter, gauge};
+use thiserror::Error;
+use tracing::{info, warn};
+
+/// The circuit breakers of all operations, keyed by operation name.
+static CIRCUIT_BREAKERS: LazyLock<Mutex<HashMap<String, Arc<CircuitBreaker>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// The configuration of a [`CircuitBreaker`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CircuitBreakerConfig {
+    /// The number of consecutive transient failures after which the circuit opens.
+    pub failure_threshold: u32,
+    /// How long the circuit stays open before a single trial attempt is let through.
+    pub open_duration: Duration,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self { failure_threshold: 10, open_duration: Duration::from_secs(30) }
+    }
+}
+
+/// The error returned without calling the network while a circuit is open.
+#[derive(Debug, Clone, Error)]
+#[error("circuit breaker for {operation} is open after sustained failures")]
+pub struct CircuitOpenError {
+    /// The operation whose circuit is open.
+    pub operation: String,
+}
+
+/// The state of a [`CircuitBreaker`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BreakerState {
+    /// Attempts go through, counting consecutive failures.
+    Closed { failures: u32 },
+    /// Attempts fail fast until the given time.
+    Open { until: Instant },
+    /// A trial attempt has been let through, and another one is let through after the given time.
+    HalfOpen { until: Instant },
+}
+
+/// A circuit breaker that fails fast after sustained transient failures of an operation.
+///
+/// After [`CircuitBreakerConfig::failure_threshold`] consecutive failures the circuit opens and
+/// every attempt fails with a [`CircuitOpenError`]. Once [`CircuitBreakerConfig::open_duration`]
+/// has passed, a single trial attempt is let through: if it succeeds the circuit closes, otherwise
+/// it opens again.
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    /// The operation guarded by the breaker.
+    operation: String,
+    /// The state of the breaker.
+    state: Mutex<BreakerState>,
+}
+
+impl CircuitBreaker {
+    /// Create a closed circuit breaker for `operation`.
+    #[must_use]
+    pub fn new(operation: &str) -> Self {
+        Self {
+            operation: operation.to_string(),
+            state: Mutex::new(BreakerState::Closed { failures: 0 }),
+        }
+    }
+
+    /// Get the shared circuit breaker of `operation`.
+    #[must_use]
+    pub fn for_operation(operation: &str) -> Arc<Self> {
+        CIRCUIT_BREAKERS
+            .lock()
+            .unwrap()
+            .entry(operation.to_string())
+            .or_insert_with(|| Arc::new(Self::new(operation)))
+            .clone()
+    }
+
+    /// Check whether an attempt may go through.
+    pub fn acquire(
+        &self,
+        config: &CircuitBreakerConfig,
+        now: Instant,
+    ) -> Result<(), CircuitOpenError> {
+        let mut state = self.state.lock().unwrap();
+        match *state {
+            BreakerState::Closed { .. } => Ok(()),
+            BreakerState::Open { until } | BreakerState::HalfOpen { until } if now >= until => {
+                // Let a trial attempt through. A trial that never reports back is replaced by a
+                // new one after another open duration.
+                *state = BreakerState::HalfOpen { until: now + config.open_duration };
+                self.set_gauge(&state);
+                Ok(())
+            }
+            BreakerState::Open { .. } | BreakerState::HalfOpen { .. } => {
+                Err(CircuitOpenError { operation: self.operation.clone() })
+            }
+        }
+    }
+
+    /// Record a successful attempt.
+    pub fn record_success(&self) {
+        let mut state = self.state.lock().unwrap();
+        if !matches!(*state, BreakerState::Closed { .. }) {
+            info!(operation = %self.operation, "circuit breaker closed");
+        }
+        *state = BreakerState::Closed { failures: 0 };
+        self.set_gauge(&state);
+    }
+
+    /// Record a transient failure of an attempt.
+    pub fn record_failure(&self, config: &CircuitBreakerConfig, now: Instant) {
+        let mut state = self.state.lock().unwrap();
+        let open = match *state {
+            BreakerState::Closed { failures } if failures + 1 < config.failure_threshold => {
+                *state = BreakerState::Closed { failures: failures + 1 };
+                false
+            }
+            BreakerState::Closed { .. } | BreakerState::HalfOpen { .. } => true,
+            BreakerState::Open { .. } => false,
+        };
+        if open {
+            warn!(
+                operation = %self.operation,
+                duration = ?config.open_duration,
+                "circuit breaker opened after sustained failures"
+            );
+            counter!("spn_rpc_circuit_opened_total", "operation" => self.operation.clone())
+                .increment(1);
+            *state = BreakerState::Open { until: now + config.open_duration };
+        }
+        self.set_gauge(&state);
+    }
+
+    /// Whether the circuit is currently open.
+    #[must_use]
+    pub fn is_open(&self) -> bool {
+        !matches!(*self.state.lock().unwrap(), BreakerState::Closed { .. })
+    }
+
+    /// Export the state of the breaker: 0 when closed, 1 when open, 2 when half-open.
+    fn set_gauge(&self, state: &BreakerState) {
+        let value = match state {
+            BreakerState::Closed { .. } => 0.0,
+            BreakerState::Open { .. } => 1.0,
+            BreakerState::HalfOpen { .. } => 2.0,
+        };
+        gauge!("spn_rpc_circuit_state", "operation" => self.operation.clone()).set(value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: CircuitBreakerConfig =
+        CircuitBreakerConfig { failure_threshold: 3, open_duration: Duration::from_secs(30) };
+
+    #[test]
+    fn test_opens_after_threshold_and_fails_fast() {
+        let breaker = CircuitBreaker::new("test");
+        let now = Instant::now();
+        for _ in 0..2 {
+            breaker.record_failure(&CONFIG, now);
+            assert!(breaker.acquire(&CONFIG, now).is_ok());
+        }
+        breaker.record_failure(&CONFIG, now);
+        assert!(breaker.is_open());
+        assert!(breaker.acquire(&CONFIG, now + Duration::from_secs(1)).is_err());
+    }
+
+    #[test]
+    fn test_success_resets_failures() {
+        let breaker = CircuitBreaker::new("test");
+        let now = Instant::now();
+        breaker.record_failure(&CONFIG, now);
+        breaker.record_failure(&CONFIG, now);
+        breaker.record_success();
+        breaker.record_failure(&CONFIG, now);
+        breaker.record_failure(&CONFIG, now);
+        assert!(!breaker.is_open());
+    }
+
+    #[test]
+    fn test_half_open_lets_one_trial_through() {
+        let breaker = CircuitBreaker::new("test");
+        let now = Instant::now();
+        for _ in 0..3 {
+            breaker.record_failure(&CONFIG, now);
+        }
+
+        // After the open duration a single trial goes through.
+        let later = now + CONFIG.open_duration;
+        assert!(breaker.acquire(&CONFIG, later).is_ok());
+        assert!(breaker.acquire(&CONFIG, later).is_err());
+
+        // A failed trial opens the circuit again, a successful one closes it.
+        breaker.record_failure(&CONFIG, later);
+        assert!(breaker.acquire(&CONFIG, later).is_err());
+        let later = later + CONFIG.open_duration;
+        assert!(breaker.acquire(&CONFIG, later).is_ok());
+        breaker.record_success();
+        assert!(!breaker.is_open());
+        assert!(breaker.acquire(&CONFIG, later).is_ok());
+    }
+}
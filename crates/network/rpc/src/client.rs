@@ -0,0 +1,563 @@
+use alloy_primitives::{Address, B256, U256};
+use alloy_signer_local::PrivateKeySigner;
+use anyhow::{anyhow, Result};
+use spn_network_types::{
+    prover_network_client::ProverNetworkClient, BidRequest, BidRequestBody, FailFulfillmentRequest,
+    FailFulfillmentRequestBody, FulfillProofRequest, FulfillProofRequestBody,
+    GetDelegationParamsRequest, GetNonceRequest, GetTransferParamsRequest,
+    GetWithdrawParamsRequest, MessageFormat, ProofRequestError, RequestProofRequest,
+    RequestProofRequestBody, SetAccountNameRequest, SetAccountNameRequestBody,
+    SetDelegationRequest, SetDelegationRequestBody, Signable, TransactionVariant, TransferRequest,
+    TransferRequestBody, WithdrawRequest, WithdrawRequestBody,
+};
+use tonic::transport::Channel;
+use tracing::debug;
+
+use crate::{ProverNetworkApi, RetryableRpc};
+
+/// A client that signs and submits transactions to the prover network.
+///
+/// Every transaction is built for the configured domain and the right [`TransactionVariant`],
+/// signed with the account's latest nonce, and submitted with retries. The nonce is fetched again
+/// before every attempt, so a retried transaction never reuses a nonce that was already consumed.
+///
+/// Transfers, withdrawals and delegations are the exception: they are signed once and the same
+/// transaction is resubmitted, since an attempt that timed out may have been applied, and signing
+/// it again with a fresh nonce would apply it twice.
+#[derive(Debug, Clone)]
+pub struct NetworkClient<N = ProverNetworkClient<Channel>> {
+    /// The network the transactions are submitted to.
+    network: N,
+    /// The signer of the transactions.
+    signer: PrivateKeySigner,
+    /// The domain separator of the transactions.
+    domain: B256,
+}
+
+impl<N: ProverNetworkApi> NetworkClient<N> {
+    /// Create a client that signs transactions for `domain` with `signer`.
+    #[must_use]
+    pub fn new(network: N, signer: PrivateKeySigner, domain: B256) -> Self {
+        Self { network, signer, domain }
+    }
+
+    /// The network the transactions are submitted to.
+    #[must_use]
+    pub fn network(&self) -> &N {
+        &self.network
+    }
+
+    /// The signer of the transactions.
+    #[must_use]
+    pub fn signer(&self) -> &PrivateKeySigner {
+        &self.signer
+    }
+
+    /// The address that signs the transactions.
+    #[must_use]
+    pub fn address(&self) -> Address {
+        self.signer.address()
+    }
+
+    /// Get the current nonce of the signer.
+    pub async fn nonce(&self) -> Result<u64> {
+        let address = self.address().to_vec();
+        let nonce = self.network.get_nonce(GetNonceRequest { address }).await?.nonce;
+        debug!(nonce = %nonce, "fetched account nonce");
+        Ok(nonce)
+    }
+
+    /// Bid `amount` on a proof request on behalf of `prover`.
+    pub async fn bid(&self, request_id: &[u8], amount: U256, prover: Address) -> Result<Vec<u8>> {
+        self.network
+            .with_retry(
+                || async {
+                    let body = BidRequestBody {
+                        nonce: self.nonce().await?,
+                        request_id: request_id.to_vec(),
+                        amount: amount.to_string(),
+                        prover: prover.to_vec(),
+                        domain: self.domain.to_vec(),
+                        variant: TransactionVariant::BidVariant.into(),
+                    };
+                    let request = BidRequest {
+                        format: MessageFormat::Binary.into(),
+                        signature: body.sign(&self.signer).into(),
+                        body: Some(body),
+                    };
+                    Ok(self.network.bid(request).await?.tx_hash)
+                },
+                "Bid",
+            )
+            .await
+    }
+
+    /// Fulfill a proof request with `proof`.
+    pub async fn fulfill(&self, request_id: &[u8], proof: &[u8]) -> Result<Vec<u8>> {
+        self.fulfill_with(request_id, proof, None).await
+    }
+
+    /// Fulfill a proof request with the proof artifact at `proof_uri`, for proofs too large to
+    /// send inline.
+    pub async fn fulfill_with_uri(&self, request_id: &[u8], proof_uri: &str) -> Result<Vec<u8>> {
+        self.fulfill_with(request_id, &[], Some(proof_uri)).await
+    }
+
+    /// Fulfill a proof request with `proof` or the proof artifact at `proof_uri`.
+    async fn fulfill_with(
+        &self,
+        request_id: &[u8],
+        proof: &[u8],
+        proof_uri: Option<&str>,
+    ) -> Result<Vec<u8>> {
+        self.network
+            .with_retry(
+                || async {
+                    let body = FulfillProofRequestBody {
+                        nonce: self.nonce().await?,
+                        request_id: request_id.to_vec(),
+                        proof: proof.to_vec(),
+                        reserved_metadata: None,
+                        domain: self.domain.to_vec(),
+                        variant: TransactionVariant::FulfillVariant.into(),
+                        proof_uri: proof_uri.map(String::from),
+                    };
+                    let request = FulfillProofRequest {
+                        format: MessageFormat::Binary.into(),
+                        signature: body.sign(&self.signer).into(),
+                        body: Some(body),
+                    };
+                    Ok(self.network.fulfill_proof(request).await?.tx_hash)
+                },
+                "Fulfill",
+            )
+            .await
+    }
+
+    /// Give up on fulfilling a proof request, optionally with the reason.
+    pub async fn fail(
+        &self,
+        request_id: &[u8],
+        error: Option<ProofRequestError>,
+    ) -> Result<Vec<u8>> {
+        self.network
+            .with_retry(
+                || async {
+                    let body = FailFulfillmentRequestBody {
+                        nonce: self.nonce().await?,
+                        request_id: request_id.to_vec(),
+                        error: error.map(Into::into),
+                    };
+                    let request = FailFulfillmentRequest {
+                        format: MessageFormat::Binary.into(),
+                        signature: body.sign(&self.signer).into(),
+                        body: Some(body),
+                    };
+                    Ok(self.network.fail_fulfillment(request).await?.tx_hash)
+                },
+                "FailFulfillment",
+            )
+            .await
+    }
+
+    /// Transfer `amount` from the signer to `to`, paying the current transfer fee.
+    pub async fn transfer(&self, to: Address, amount: U256) -> Result<Vec<u8>> {
+        let request = self
+            .network
+            .with_retry(
+                || async {
+                    let params =
+                        self.network.get_transfer_params(GetTransferParamsRequest {}).await?;
+                    let body = TransferRequestBody {
+                        nonce: self.nonce().await?,
+                        to: to.to_vec(),
+                        amount: amount.to_string(),
+                        domain: self.domain.to_vec(),
+                        variant: TransactionVariant::TransferVariant.into(),
+                        auctioneer: params.auctioneer,
+                        fee: params.fee,
+                    };
+                    Ok(TransferRequest {
+                        format: MessageFormat::Binary.into(),
+                        signature: body.sign(&self.signer).into(),
+                        body: Some(body),
+                    })
+                },
+                "SignTransfer",
+            )
+            .await?;
+        // The same signed transaction is resubmitted on every attempt.
+        self.network
+            .with_retry(
+                || async { Ok(self.network.transfer(request.clone()).await?.tx_hash) },
+                "Transfer",
+            )
+            .await
+    }
+
+    /// Withdraw `amount` from the balance of the signer, paying the current withdrawal fee.
+    pub async fn withdraw(&self, amount: U256) -> Result<Vec<u8>> {
+        let request = self
+            .network
+            .with_retry(
+                || async {
+                    let params =
+                        self.network.get_withdraw_params(GetWithdrawParamsRequest {}).await?;
+                    let body = WithdrawRequestBody {
+                        nonce: self.nonce().await?,
+                        account: self.address().to_vec(),
+                        amount: amount.to_string(),
+                        domain: self.domain.to_vec(),
+                        variant: TransactionVariant::WithdrawVariant.into(),
+                        auctioneer: params.auctioneer,
+                        fee: params.fee,
+                    };
+                    Ok(WithdrawRequest {
+                        format: MessageFormat::Binary.into(),
+                        signature: body.sign(&self.signer).into(),
+                        body: Some(body),
+                    })
+                },
+                "SignWithdraw",
+            )
+            .await?;
+        // The same signed transaction is resubmitted on every attempt.
+        self.network
+            .with_retry(
+                || async { Ok(self.network.withdraw(request.clone()).await?.tx_hash) },
+                "Withdraw",
+            )
+            .await
+    }
+
+    /// Allow `delegate` to act on behalf of `prover`, paying the current delegation fee.
+    pub async fn set_delegation(&self, prover: Address, delegate: Address) -> Result<Vec<u8>> {
+        let request = self
+            .network
+            .with_retry(
+                || async {
+                    let params =
+                        self.network.get_delegation_params(GetDelegationParamsRequest {}).await?;
+                    let body = SetDelegationRequestBody {
+                        nonce: self.nonce().await?,
+                        delegate: delegate.to_vec(),
+                        prover: prover.to_vec(),
+                        domain: self.domain.to_vec(),
+                        variant: TransactionVariant::DelegateVariant.into(),
+                        auctioneer: params.auctioneer,
+                        fee: params.fee,
+                    };
+                    Ok(SetDelegationRequest {
+                        format: MessageFormat::Binary.into(),
+                        signature: body.sign(&self.signer).into(),
+                        body: Some(body),
+                    })
+                },
+                "SignSetDelegation",
+            )
+            .await?;
+        // The same signed transaction is resubmitted on every attempt.
+        self.network
+            .with_retry(
+                || async { Ok(self.network.set_delegation(request.clone()).await?.tx_hash) },
+                "SetDelegation",
+            )
+            .await
+    }
+
+    /// Set the display name of `address`.
+    pub async fn set_account_name(&self, address: Address, name: &str) -> Result<Vec<u8>> {
+        self.network
+            .with_retry(
+                || async {
+                    let body = SetAccountNameRequestBody {
+                        nonce: self.nonce().await?,
+                        address: address.to_vec(),
+                        name: name.to_string(),
+                    };
+                    let request = SetAccountNameRequest {
+                        format: MessageFormat::Binary.into(),
+                        signature: body.sign(&self.signer).into(),
+                        body: Some(body),
+                    };
+                    Ok(self.network.set_account_name(request).await?.tx_hash)
+                },
+                "SetAccountName",
+            )
+            .await
+    }
+
+    /// Request a proof and return the id of the request.
+    ///
+    /// The nonce, domain and variant of `body` are filled in by the client.
+    pub async fn request_proof(&self, body: RequestProofRequestBody) -> Result<Vec<u8>> {
+        self.network
+            .with_retry(
+                || async {
+                    let body = RequestProofRequestBody {
+                        nonce: self.nonce().await?,
+                        domain: self.domain.to_vec(),
+                        variant: TransactionVariant::RequestVariant.into(),
+                        ..body.clone()
+                    };
+                    let request = RequestProofRequest {
+                        format: MessageFormat::Binary.into(),
+                        signature: body.sign(&self.signer).into(),
+                        body: Some(body),
+                    };
+                    let response = self.network.request_proof(request).await?;
+                    let body = response.body.ok_or_else(|| anyhow!("missing response body"))?;
+                    Ok(body.request_id)
+                },
+                "RequestProof",
+            )
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use futures::stream::BoxStream;
+    use prost::Message;
+    use spn_network_types::{
+        BidResponse, FailFulfillmentResponse, FulfillProofResponse, GetBalanceRequest,
+        GetBalanceResponse, GetDelegationParamsResponse, GetFilteredBalanceLogsRequest,
+        GetFilteredBalanceLogsResponse, GetFilteredBidHistoryRequest,
+        GetFilteredBidHistoryResponse, GetFilteredProofRequestsRequest,
+        GetFilteredProofRequestsResponse, GetFilteredProverStakeBalanceLogsRequest,
+        GetFilteredProverStakeBalanceLogsResponse, GetFilteredWithdrawalReceiptsRequest,
+        GetFilteredWithdrawalReceiptsResponse, GetNonceResponse, GetOwnerRequest, GetOwnerResponse,
+        GetProofRequestDetailsRequest, GetProofRequestDetailsResponse,
+        GetProverStakeBalanceRequest, GetProverStakeBalanceResponse, GetTransferParamsResponse,
+        GetWithdrawParamsResponse, ProofRequest, RequestProofResponse, SetAccountNameResponse,
+        SetDelegationResponse, TransferResponse, WithdrawResponse,
+    };
+    use tonic::{async_trait, Status};
+
+    use super::*;
+
+    const AUCTIONEER: [u8; 20] = [0xaa; 20];
+
+    /// A network that serves fee parameters and records the encoded bodies it receives.
+    #[derive(Debug, Clone, Default)]
+    struct RecordingNetwork {
+        bodies: Arc<Mutex<Vec<Vec<u8>>>>,
+        /// The number of transfers that are applied but time out before they are answered.
+        timeouts: Arc<Mutex<u32>>,
+    }
+
+    impl RecordingNetwork {
+        fn record(&self, body: &impl Message) {
+            self.bodies.lock().unwrap().push(body.encode_to_vec());
+        }
+
+        fn last_body<M: Message + Default>(&self) -> M {
+            M::decode(self.bodies.lock().unwrap().last().unwrap().as_slice()).unwrap()
+        }
+    }
+
+    #[async_trait]
+    impl ProverNetworkApi for RecordingNetwork {
+        async fn get_filtered_proof_requests(
+            &self,
+            _: GetFilteredProofRequestsRequest,
+        ) -> Result<GetFilteredProofRequestsResponse, Status> {
+            Err(Status::unimplemented("get_filtered_proof_requests"))
+        }
+
+        async fn subscribe_proof_requests(
+            &self,
+            _: GetFilteredProofRequestsRequest,
+        ) -> Result<BoxStream<'static, Result<ProofRequest, Status>>, Status> {
+            Err(Status::unimplemented("subscribe_proof_requests"))
+        }
+
+        async fn get_proof_request_details(
+            &self,
+            _: GetProofRequestDetailsRequest,
+        ) -> Result<GetProofRequestDetailsResponse, Status> {
+            Err(Status::unimplemented("get_proof_request_details"))
+        }
+
+        async fn get_nonce(&self, _: GetNonceRequest) -> Result<GetNonceResponse, Status> {
+            // Every received transaction consumes a nonce.
+            Ok(GetNonceResponse { nonce: 3 + self.bodies.lock().unwrap().len() as u64 })
+        }
+
+        async fn get_owner(&self, _: GetOwnerRequest) -> Result<GetOwnerResponse, Status> {
+            Err(Status::unimplemented("get_owner"))
+        }
+
+        async fn get_balance(&self, _: GetBalanceRequest) -> Result<GetBalanceResponse, Status> {
+            Err(Status::unimplemented("get_balance"))
+        }
+
+        async fn get_prover_stake_balance(
+            &self,
+            _: GetProverStakeBalanceRequest,
+        ) -> Result<GetProverStakeBalanceResponse, Status> {
+            Err(Status::unimplemented("get_prover_stake_balance"))
+        }
+
+        async fn get_filtered_balance_logs(
+            &self,
+            _: GetFilteredBalanceLogsRequest,
+        ) -> Result<GetFilteredBalanceLogsResponse, Status> {
+            Err(Status::unimplemented("get_filtered_balance_logs"))
+        }
+
+        async fn get_filtered_bid_history(
+            &self,
+            _: GetFilteredBidHistoryRequest,
+        ) -> Result<GetFilteredBidHistoryResponse, Status> {
+            Err(Status::unimplemented("get_filtered_bid_history"))
+        }
+
+        async fn get_filtered_prover_stake_balance_logs(
+            &self,
+            _: GetFilteredProverStakeBalanceLogsRequest,
+        ) -> Result<GetFilteredProverStakeBalanceLogsResponse, Status> {
+            Err(Status::unimplemented("get_filtered_prover_stake_balance_logs"))
+        }
+
+        async fn get_filtered_withdrawal_receipts(
+            &self,
+            _: GetFilteredWithdrawalReceiptsRequest,
+        ) -> Result<GetFilteredWithdrawalReceiptsResponse, Status> {
+            Err(Status::unimplemented("get_filtered_withdrawal_receipts"))
+        }
+
+        async fn bid(&self, _: BidRequest) -> Result<BidResponse, Status> {
+            Err(Status::unimplemented("bid"))
+        }
+
+        async fn fulfill_proof(
+            &self,
+            _: FulfillProofRequest,
+        ) -> Result<FulfillProofResponse, Status> {
+            Err(Status::unimplemented("fulfill_proof"))
+        }
+
+        async fn fail_fulfillment(
+            &self,
+            _: FailFulfillmentRequest,
+        ) -> Result<FailFulfillmentResponse, Status> {
+            Err(Status::unimplemented("fail_fulfillment"))
+        }
+
+        async fn request_proof(
+            &self,
+            _: RequestProofRequest,
+        ) -> Result<RequestProofResponse, Status> {
+            Err(Status::unimplemented("request_proof"))
+        }
+
+        async fn get_transfer_params(
+            &self,
+            _: GetTransferParamsRequest,
+        ) -> Result<GetTransferParamsResponse, Status> {
+            Ok(GetTransferParamsResponse { auctioneer: AUCTIONEER.to_vec(), fee: "1".to_string() })
+        }
+
+        async fn transfer(&self, request: TransferRequest) -> Result<TransferResponse, Status> {
+            self.record(&request.body.unwrap());
+            let mut timeouts = self.timeouts.lock().unwrap();
+            if *timeouts > 0 {
+                *timeouts -= 1;
+                return Err(Status::deadline_exceeded("transfer"));
+            }
+            Ok(TransferResponse::default())
+        }
+
+        async fn get_withdraw_params(
+            &self,
+            _: GetWithdrawParamsRequest,
+        ) -> Result<GetWithdrawParamsResponse, Status> {
+            Ok(GetWithdrawParamsResponse { auctioneer: AUCTIONEER.to_vec(), fee: "2".to_string() })
+        }
+
+        async fn withdraw(&self, request: WithdrawRequest) -> Result<WithdrawResponse, Status> {
+            self.record(&request.body.unwrap());
+            Ok(WithdrawResponse::default())
+        }
+
+        async fn get_delegation_params(
+            &self,
+            _: GetDelegationParamsRequest,
+        ) -> Result<GetDelegationParamsResponse, Status> {
+            Ok(GetDelegationParamsResponse {
+                auctioneer: AUCTIONEER.to_vec(),
+                fee: "3".to_string(),
+            })
+        }
+
+        async fn set_delegation(
+            &self,
+            request: SetDelegationRequest,
+        ) -> Result<SetDelegationResponse, Status> {
+            self.record(&request.body.unwrap());
+            Ok(SetDelegationResponse::default())
+        }
+
+        async fn set_account_name(
+            &self,
+            _: SetAccountNameRequest,
+        ) -> Result<SetAccountNameResponse, Status> {
+            Err(Status::unimplemented("set_account_name"))
+        }
+    }
+
+    fn client() -> (RecordingNetwork, NetworkClient<RecordingNetwork>) {
+        let network = RecordingNetwork::default();
+        let client = NetworkClient::new(network.clone(), PrivateKeySigner::random(), B256::ZERO);
+        (network, client)
+    }
+
+    #[tokio::test]
+    async fn test_transfer_pays_fee() {
+        let (network, client) = client();
+        client.transfer(Address::repeat_byte(1), U256::from(10)).await.unwrap();
+
+        let body: TransferRequestBody = network.last_body();
+        assert_eq!((body.nonce, body.amount.as_str()), (3, "10"));
+        assert_eq!(body.auctioneer, AUCTIONEER.to_vec());
+        assert_eq!(body.fee, "1");
+    }
+
+    #[tokio::test]
+    async fn test_retried_transfer_reuses_the_signed_transaction() {
+        let (network, client) = client();
+        *network.timeouts.lock().unwrap() = 1;
+        client.transfer(Address::repeat_byte(1), U256::from(10)).await.unwrap();
+
+        // The retry cannot be applied a second time, since it has the nonce of the first attempt.
+        let bodies = network.bodies.lock().unwrap().clone();
+        assert_eq!(bodies.len(), 2);
+        assert_eq!(bodies[0], bodies[1]);
+        assert_eq!(TransferRequestBody::decode(bodies[1].as_slice()).unwrap().nonce, 3);
+    }
+
+    #[tokio::test]
+    async fn test_withdraw_pays_fee() {
+        let (network, client) = client();
+        client.withdraw(U256::from(20)).await.unwrap();
+
+        let body: WithdrawRequestBody = network.last_body();
+        assert_eq!(body.account, client.address().to_vec());
+        assert_eq!(body.auctioneer, AUCTIONEER.to_vec());
+        assert_eq!(body.fee, "2");
+    }
+
+    #[tokio::test]
+    async fn test_set_delegation_pays_fee() {
+        let (network, client) = client();
+        client.set_delegation(Address::repeat_byte(2), Address::repeat_byte(3)).await.unwrap();
+
+        let body: SetDelegationRequestBody = network.last_body();
+        assert_eq!(body.delegate, Address::repeat_byte(3).to_vec());
+        assert_eq!(body.auctioneer, AUCTIONEER.to_vec());
+        assert_eq!(body.fee, "3");
+    }
+}
@@ -0,0 +1,352 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, Result};
+use futures::future::join_all;
+use metrics::{counter, gauge};
+use spn_network_types::{prover_network_client::ProverNetworkClient, GetNonceRequest};
+use tokio::sync::mpsc::Sender;
+use tonic::{
+    transport::{Channel, Endpoint},
+    Code,
+};
+use tower::discover::Change;
+use tracing::{debug, info, warn};
+
+use crate::{AuthInterceptor, EndpointConfig};
+
+/// The default interval between health checks of each endpoint.
+pub const DEFAULT_HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(10);
+
+/// The default time an unhealthy endpoint is kept out of rotation.
+pub const DEFAULT_EJECTION_COOLDOWN: Duration = Duration::from_secs(60);
+
+/// The timeout of a single health check.
+const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The capacity of the channel used to add and remove endpoints from the balanced channel.
+const BALANCE_CHANNEL_CAPACITY: usize = 64;
+
+/// How requests are spread over the healthy endpoints of an [`EndpointPool`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EndpointStrategy {
+    /// Send every request to the first healthy endpoint, in the order they were given.
+    #[default]
+    Failover,
+    /// Balance requests over all healthy endpoints.
+    Balanced,
+}
+
+/// The configuration of an [`EndpointPool`].
+#[derive(Debug, Clone)]
+pub struct EndpointPoolConfig {
+    /// How requests are spread over the healthy endpoints.
+    pub strategy: EndpointStrategy,
+    /// The interval between health checks of each endpoint.
+    pub health_check_interval: Duration,
+    /// The minimum time an unhealthy endpoint is kept out of rotation.
+    pub cooldown: Duration,
+    /// How connections to the endpoints are made.
+    pub endpoint: EndpointConfig,
+}
+
+impl Default for EndpointPoolConfig {
+    fn default() -> Self {
+        Self {
+            strategy: EndpointStrategy::default(),
+            health_check_interval: DEFAULT_HEALTH_CHECK_INTERVAL,
+            cooldown: DEFAULT_EJECTION_COOLDOWN,
+            endpoint: EndpointConfig::default(),
+        }
+    }
+}
+
+/// A set of RPC endpoints behind a single [`Channel`].
+///
+/// Each endpoint is health-checked in the background, until the pool and every clone of its
+/// channel are dropped. An endpoint that fails a health check is ejected from the channel for at
+/// least the configured cooldown, and is only let back in once it passes a health check again. If
+/// every endpoint is unhealthy, the last active endpoints are kept so that requests can still be
+/// retried against them.
+#[derive(Debug, Clone)]
+pub struct EndpointPool {
+    /// The channel that routes requests to the active endpoints.
+    channel: Channel,
+    /// The health of the endpoints.
+    state: Arc<Mutex<PoolState>>,
+}
+
+impl EndpointPool {
+    /// Create a pool over `urls`, in order of preference, and start health-checking them.
+    ///
+    /// Fails if `urls` is empty or the health check interval is zero.
+    ///
+    /// Must be called from within a Tokio runtime.
+    pub async fn connect(urls: &[String], config: EndpointPoolConfig) -> Result<Self> {
+        if urls.is_empty() {
+            return Err(anyhow!("at least one RPC endpoint is required"));
+        }
+        if config.health_check_interval.is_zero() {
+            return Err(anyhow!("the RPC health check interval must be positive"));
+        }
+        let endpoints =
+            urls.iter().map(|url| config.endpoint.endpoint(url)).collect::<Result<Vec<_>, _>>()?;
+        let interceptor = config.endpoint.interceptor()?;
+
+        let (channel, changes) = Channel::balance_channel(BALANCE_CHANNEL_CAPACITY);
+        let state = Arc::new(Mutex::new(PoolState::new(urls.to_vec(), config.strategy)));
+
+        // Start with every endpoint assumed healthy.
+        let active = state.lock().unwrap().active.clone();
+        for &i in &active {
+            changes
+                .send(Change::Insert(i, endpoints[i].clone()))
+                .await
+                .map_err(|_| anyhow!("failed to add endpoint to the channel"))?;
+        }
+        record_active(&state.lock().unwrap(), &[]);
+
+        tokio::spawn(health_check_loop(endpoints, interceptor, changes, state.clone(), config));
+
+        Ok(Self { channel, state })
+    }
+
+    /// The channel that routes requests to the active endpoints.
+    #[must_use]
+    pub fn channel(&self) -> Channel {
+        self.channel.clone()
+    }
+
+    /// The URLs of the endpoints that are currently receiving requests.
+    #[must_use]
+    pub fn active(&self) -> Vec<String> {
+        let state = self.state.lock().unwrap();
+        state.active.iter().map(|&i| state.urls[i].clone()).collect()
+    }
+}
+
+/// The health of the endpoints of an [`EndpointPool`].
+#[derive(Debug)]
+struct PoolState {
+    /// The URLs of the endpoints, in order of preference.
+    urls: Vec<String>,
+    /// For each endpoint, the earliest time it may be let back in if it is ejected.
+    ejected_until: Vec<Option<Instant>>,
+    /// The endpoints that are currently receiving requests.
+    active: Vec<usize>,
+    /// How requests are spread over the healthy endpoints.
+    strategy: EndpointStrategy,
+}
+
+impl PoolState {
+    /// Create the state of a pool where every endpoint is healthy.
+    fn new(urls: Vec<String>, strategy: EndpointStrategy) -> Self {
+        let mut state =
+            Self { ejected_until: vec![None; urls.len()], urls, active: Vec::new(), strategy };
+        state.active = state.select();
+        state
+    }
+
+    /// Record the result of a health check, returning whether the endpoint was just ejected.
+    fn record(&mut self, i: usize, healthy: bool, now: Instant, cooldown: Duration) -> bool {
+        match (healthy, self.ejected_until[i]) {
+            (true, Some(until)) if now >= until => {
+                self.ejected_until[i] = None;
+                false
+            }
+            (true, _) => false,
+            (false, ejected) => {
+                self.ejected_until[i] = Some(now + cooldown);
+                ejected.is_none()
+            }
+        }
+    }
+
+    /// The endpoints that should be receiving requests.
+    fn select(&self) -> Vec<usize> {
+        let mut healthy = (0..self.urls.len()).filter(|&i| self.ejected_until[i].is_none());
+        let selected: Vec<usize> = match self.strategy {
+            EndpointStrategy::Failover => healthy.next().into_iter().collect(),
+            EndpointStrategy::Balanced => healthy.collect(),
+        };
+        if selected.is_empty() {
+            self.active.clone()
+        } else {
+            selected
+        }
+    }
+}
+
+/// Periodically health-check every endpoint and update the endpoints of the balanced channel,
+/// until the channel is dropped.
+async fn health_check_loop(
+    endpoints: Vec<Endpoint>,
+    interceptor: AuthInterceptor,
+    changes: Sender<Change<usize, Endpoint>>,
+    state: Arc<Mutex<PoolState>>,
+    config: EndpointPoolConfig,
+) {
+    const ENDPOINT_POOL_TAG: &str = "\x1b[36m[EndpointPool]\x1b[0m";
+
+    let probes: Vec<Channel> = endpoints.iter().map(Endpoint::connect_lazy).collect();
+    let mut interval = tokio::time::interval(config.health_check_interval);
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {}
+            () = changes.closed() => {
+                debug!("{ENDPOINT_POOL_TAG} Stopped health checks of dropped channel.");
+                return;
+            }
+        }
+        let results = join_all(
+            probes.iter().map(|channel| check_health(channel.clone(), interceptor.clone())),
+        )
+        .await;
+
+        let (removed, inserted) = {
+            let mut state = state.lock().unwrap();
+            let now = Instant::now();
+            for (i, healthy) in results.into_iter().enumerate() {
+                let url = state.urls[i].clone();
+                gauge!("spn_rpc_endpoint_healthy", "endpoint" => url.clone())
+                    .set(f64::from(u8::from(healthy)));
+                if state.record(i, healthy, now, config.cooldown) {
+                    counter!("spn_rpc_endpoint_ejections_total", "endpoint" => url.clone())
+                        .increment(1);
+                    warn!(endpoint = %url, cooldown = ?config.cooldown, "{ENDPOINT_POOL_TAG} Ejected unhealthy RPC endpoint.");
+                }
+            }
+
+            let active = state.select();
+            if active == state.active {
+                continue;
+            }
+            let removed: Vec<usize> =
+                state.active.iter().copied().filter(|i| !active.contains(i)).collect();
+            let inserted: Vec<usize> =
+                active.iter().copied().filter(|i| !state.active.contains(i)).collect();
+            state.active = active;
+
+            let urls: Vec<&str> = state.active.iter().map(|&i| state.urls[i].as_str()).collect();
+            info!(endpoints = ?urls, "{ENDPOINT_POOL_TAG} Switched active RPC endpoints.");
+            record_active(&state, &removed);
+            (removed, inserted)
+        };
+
+        for i in removed {
+            let _ = changes.send(Change::Remove(i)).await;
+        }
+        for i in inserted {
+            let _ = changes.send(Change::Insert(i, endpoints[i].clone())).await;
+        }
+    }
+}
+
+/// Update the active endpoint gauges for the current active endpoints and the given inactive ones.
+fn record_active(state: &PoolState, inactive: &[usize]) {
+    for &i in inactive {
+        gauge!("spn_rpc_endpoint_active", "endpoint" => state.urls[i].clone()).set(0.0);
+    }
+    for &i in &state.active {
+        gauge!("spn_rpc_endpoint_active", "endpoint" => state.urls[i].clone()).set(1.0);
+    }
+}
+
+/// Check whether an endpoint is reachable.
+///
+/// Any response from the server counts as healthy, including errors about the probe itself. Only
+/// timeouts and unavailability count as unhealthy.
+async fn check_health(channel: Channel, interceptor: AuthInterceptor) -> bool {
+    let mut client = ProverNetworkClient::with_interceptor(channel, interceptor);
+    let probe = client.get_nonce(GetNonceRequest { address: vec![0; 20] });
+    match tokio::time::timeout(HEALTH_CHECK_TIMEOUT, probe).await {
+        Ok(Ok(_)) => true,
+        Ok(Err(status)) => !matches!(status.code(), Code::Unavailable | Code::DeadlineExceeded),
+        Err(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COOLDOWN: Duration = Duration::from_secs(60);
+
+    fn pool(strategy: EndpointStrategy) -> PoolState {
+        PoolState::new(vec!["a".into(), "b".into(), "c".into()], strategy)
+    }
+
+    #[test]
+    fn test_failover_prefers_first_healthy_endpoint() {
+        let mut state = pool(EndpointStrategy::Failover);
+        assert_eq!(state.select(), vec![0]);
+
+        let now = Instant::now();
+        assert!(state.record(0, false, now, COOLDOWN));
+        assert_eq!(state.select(), vec![1]);
+
+        // A repeated failure does not count as a new ejection.
+        assert!(!state.record(0, false, now, COOLDOWN));
+    }
+
+    #[test]
+    fn test_balanced_uses_all_healthy_endpoints() {
+        let mut state = pool(EndpointStrategy::Balanced);
+        assert_eq!(state.select(), vec![0, 1, 2]);
+
+        state.record(1, false, Instant::now(), COOLDOWN);
+        assert_eq!(state.select(), vec![0, 2]);
+    }
+
+    #[test]
+    fn test_ejected_endpoint_returns_after_cooldown() {
+        let mut state = pool(EndpointStrategy::Failover);
+        let now = Instant::now();
+        state.record(0, false, now, COOLDOWN);
+
+        // Passing a health check during the cooldown is not enough.
+        state.record(0, true, now + COOLDOWN / 2, COOLDOWN);
+        assert_eq!(state.select(), vec![1]);
+
+        state.record(0, true, now + COOLDOWN, COOLDOWN);
+        assert_eq!(state.select(), vec![0]);
+    }
+
+    #[test]
+    fn test_all_unhealthy_keeps_active_endpoints() {
+        let mut state = pool(EndpointStrategy::Failover);
+        let now = Instant::now();
+        for i in 0..3 {
+            state.record(i, false, now, COOLDOWN);
+        }
+        assert_eq!(state.select(), vec![0]);
+    }
+
+    #[tokio::test]
+    async fn test_rejects_zero_health_check_interval() {
+        let urls = vec!["http://127.0.0.1:1".to_string()];
+        let config =
+            EndpointPoolConfig { health_check_interval: Duration::ZERO, ..Default::default() };
+        let err = EndpointPool::connect(&urls, config).await.unwrap_err();
+        assert!(err.to_string().contains("health check interval"));
+    }
+
+    #[tokio::test]
+    async fn test_health_checks_stop_when_dropped() {
+        let urls = vec!["http://127.0.0.1:1".to_string()];
+        let pool = EndpointPool::connect(&urls, EndpointPoolConfig::default()).await.unwrap();
+        let state = pool.state.clone();
+        assert_eq!(Arc::strong_count(&state), 3);
+
+        // The health check loop releases its state once the channel is gone.
+        drop(pool);
+        let stopped = tokio::time::timeout(Duration::from_secs(5), async {
+            while Arc::strong_count(&state) > 1 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        });
+        assert!(stopped.await.is_ok());
+    }
+}
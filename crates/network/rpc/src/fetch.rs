@@ -2,25 +2,21 @@ use std::str::FromStr;
 
 use alloy_primitives::U256;
 use anyhow::Result;
-use spn_network_types::{
-    prover_network_client::ProverNetworkClient, GetBalanceRequest, GetOwnerRequest,
-};
-use tonic::{transport::Channel, Request};
+use spn_network_types::{GetBalanceRequest, GetOwnerRequest, GetProverStakeBalanceRequest};
 use tracing::debug;
 
-use crate::RetryableRpc;
+use crate::{ProverNetworkApi, RetryableRpc};
 
 /// Fetches the balance of an address on the network.
-pub async fn fetch_balance(network: &ProverNetworkClient<Channel>, address: &[u8]) -> Result<U256> {
+pub async fn fetch_balance<N: ProverNetworkApi>(network: &N, address: &[u8]) -> Result<U256> {
     let address = address.to_vec();
     let response = network
-        .clone()
         .with_retry(
             || async {
                 debug!("fetching balance for {}", hex::encode(&address));
-                let req = Request::new(GetBalanceRequest { address: address.clone() });
-                let response = network.clone().get_balance(req).await?;
-                Ok(response.into_inner().amount)
+                let req = GetBalanceRequest { address: address.clone() };
+                let response = network.get_balance(req).await?;
+                Ok(response.amount)
             },
             "get balance",
         )
@@ -29,13 +25,38 @@ pub async fn fetch_balance(network: &ProverNetworkClient<Channel>, address: &[u8
     Ok(U256::from_str(&response)?)
 }
 
+/// Fetches the stake balance of a prover on the network.
+pub async fn fetch_stake_balance<N: ProverNetworkApi>(network: &N, prover: &[u8]) -> Result<U256> {
+    let prover = prover.to_vec();
+    let response = network
+        .with_retry(
+            || async {
+                debug!("fetching stake balance for {}", hex::encode(&prover));
+                let req = GetProverStakeBalanceRequest { prover: prover.clone() };
+                let response = network.get_prover_stake_balance(req).await?;
+                Ok(response.amount)
+            },
+            "get prover stake balance",
+        )
+        .await?;
+    debug!("fetched stake balance for {} with response: {}", hex::encode(&prover), response);
+    Ok(U256::from_str(&response)?)
+}
+
 /// Fetches the owner of an address/prover on the network.
-pub async fn fetch_owner(
-    network: &ProverNetworkClient<Channel>,
-    address: &[u8],
-) -> Result<Vec<u8>> {
+pub async fn fetch_owner<N: ProverNetworkApi>(network: &N, address: &[u8]) -> Result<Vec<u8>> {
     let address = address.to_vec();
-    let req = Request::new(GetOwnerRequest { address });
-    let resp = network.clone().get_owner(req).await?;
-    Ok(resp.into_inner().owner)
+    let owner = network
+        .with_retry(
+            || async {
+                debug!("fetching owner for {}", hex::encode(&address));
+                let req = GetOwnerRequest { address: address.clone() };
+                let response = network.get_owner(req).await?;
+                Ok(response.owner)
+            },
+            "get owner",
+        )
+        .await?;
+    debug!("fetched owner for {}: {}", hex::encode(&address), hex::encode(&owner));
+    Ok(owner)
 }
@@ -1,5 +1,20 @@
-use std::time::Duration;
-use tonic::transport::{Endpoint, Error};
+use std::{fmt, str::FromStr, time::Duration};
+
+use anyhow::{Context, Result};
+use spn_network_types::prover_network_client::ProverNetworkClient;
+use tonic::{
+    codec::CompressionEncoding,
+    metadata::{AsciiMetadataKey, AsciiMetadataValue},
+    service::{interceptor::InterceptedService, Interceptor},
+    transport::{Certificate, Channel, ClientTlsConfig, Endpoint, Error, Identity},
+    Request, Status,
+};
+
+use crate::{rpc_channel, RateLimitConfig, RpcChannel};
+
+/// A prover network client that sends requests through an [`AuthInterceptor`] and the middleware
+/// of an [`RpcChannel`].
+pub type RpcClient = ProverNetworkClient<InterceptedService<RpcChannel, AuthInterceptor>>;
 
 /// Configure an endpoint with appropriate timeouts and keep-alive settings.
 pub fn configure_endpoint(addr: &str) -> Result<Endpoint, Error> {
@@ -11,3 +26,338 @@ pub fn configure_endpoint(addr: &str) -> Result<Endpoint, Error> {
         .keep_alive_timeout(Duration::from_secs(15))
         .tcp_keepalive(Some(Duration::from_secs(30))))
 }
+
+/// The credentials attached to every request.
+#[derive(Clone, PartialEq, Eq)]
+pub enum RpcAuth {
+    /// A token sent as `authorization: Bearer <token>`.
+    Bearer(String),
+    /// A key sent in the given header.
+    ApiKey {
+        /// The name of the header.
+        header: String,
+        /// The key.
+        key: String,
+    },
+}
+
+impl fmt::Debug for RpcAuth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Bearer(_) => f.write_str("Bearer(<redacted>)"),
+            Self::ApiKey { header, .. } => write!(f, "ApiKey({header}: <redacted>)"),
+        }
+    }
+}
+
+/// The client certificate and private key used for mutual TLS, both PEM-encoded.
+#[derive(Clone, PartialEq, Eq)]
+pub struct ClientIdentity {
+    /// The certificate chain of the client.
+    pub certificate: Vec<u8>,
+    /// The private key of the client.
+    pub key: Vec<u8>,
+}
+
+impl fmt::Debug for ClientIdentity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ClientIdentity").finish_non_exhaustive()
+    }
+}
+
+/// How connections to the RPC endpoints are made and how requests are sent over them.
+///
+/// `https` endpoints use the native root certificates, unless a custom CA is given. Other
+/// endpoints only use TLS when a custom CA or a client identity is given.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EndpointConfig {
+    /// The PEM-encoded CA certificate that the server certificate must chain to.
+    pub ca_certificate: Option<Vec<u8>>,
+    /// The client identity presented for mutual TLS.
+    pub identity: Option<ClientIdentity>,
+    /// The name the server certificate is checked against, instead of the host of the URL.
+    pub domain_name: Option<String>,
+    /// The credentials attached to every request.
+    pub auth: Option<RpcAuth>,
+    /// The user agent sent with every request.
+    pub user_agent: Option<String>,
+    /// Whether requests are gzip-compressed, and gzip-compressed responses are accepted.
+    pub gzip: bool,
+    /// The maximum size of a decoded response, instead of the default of 4 MiB.
+    pub max_decoding_message_size: Option<usize>,
+    /// The maximum size of an encoded request, instead of no limit.
+    pub max_encoding_message_size: Option<usize>,
+    /// The rate limits of the RPC methods.
+    pub rate_limits: RateLimitConfig,
+}
+
+impl EndpointConfig {
+    /// Trust the PEM-encoded CA certificate `pem`.
+    #[must_use]
+    pub fn with_ca_certificate(mut self, pem: impl Into<Vec<u8>>) -> Self {
+        self.ca_certificate = Some(pem.into());
+        self
+    }
+
+    /// Present the PEM-encoded `certificate` and `key` for mutual TLS.
+    #[must_use]
+    pub fn with_identity(
+        mut self,
+        certificate: impl Into<Vec<u8>>,
+        key: impl Into<Vec<u8>>,
+    ) -> Self {
+        self.identity = Some(ClientIdentity { certificate: certificate.into(), key: key.into() });
+        self
+    }
+
+    /// Check the server certificate against `domain_name`.
+    #[must_use]
+    pub fn with_domain_name(mut self, domain_name: &str) -> Self {
+        self.domain_name = Some(domain_name.to_string());
+        self
+    }
+
+    /// Attach `auth` to every request.
+    #[must_use]
+    pub fn with_auth(mut self, auth: RpcAuth) -> Self {
+        self.auth = Some(auth);
+        self
+    }
+
+    /// Send `user_agent` with every request.
+    #[must_use]
+    pub fn with_user_agent(mut self, user_agent: &str) -> Self {
+        self.user_agent = Some(user_agent.to_string());
+        self
+    }
+
+    /// Compress requests and accept compressed responses.
+    #[must_use]
+    pub fn with_gzip(mut self) -> Self {
+        self.gzip = true;
+        self
+    }
+
+    /// Allow requests and responses of up to `size` bytes.
+    #[must_use]
+    pub fn with_max_message_size(mut self, size: usize) -> Self {
+        self.max_decoding_message_size = Some(size);
+        self.max_encoding_message_size = Some(size);
+        self
+    }
+
+    /// Limit the rate of calls to the RPC methods with `rate_limits`.
+    #[must_use]
+    pub fn with_rate_limits(mut self, rate_limits: RateLimitConfig) -> Self {
+        self.rate_limits = rate_limits;
+        self
+    }
+
+    /// Create the endpoint for `addr`, with the timeouts of [`configure_endpoint`].
+    pub fn endpoint(&self, addr: &str) -> Result<Endpoint, Error> {
+        let mut endpoint = configure_endpoint(addr)?;
+        if let Some(user_agent) = &self.user_agent {
+            endpoint = endpoint.user_agent(user_agent.clone())?;
+        }
+
+        let https = endpoint.uri().scheme_str() == Some("https");
+        if !https && self.ca_certificate.is_none() && self.identity.is_none() {
+            return Ok(endpoint);
+        }
+        let mut tls = ClientTlsConfig::new();
+        tls = match &self.ca_certificate {
+            Some(pem) => tls.ca_certificate(Certificate::from_pem(pem)),
+            None => tls.with_native_roots(),
+        };
+        if let Some(identity) = &self.identity {
+            tls = tls.identity(Identity::from_pem(&identity.certificate, &identity.key));
+        }
+        if let Some(domain_name) = &self.domain_name {
+            tls = tls.domain_name(domain_name);
+        }
+        endpoint.tls_config(tls)
+    }
+
+    /// Create the interceptor that attaches the credentials to every request.
+    pub fn interceptor(&self) -> Result<AuthInterceptor> {
+        let metadata = match &self.auth {
+            None => Vec::new(),
+            Some(RpcAuth::Bearer(token)) => {
+                vec![("authorization", format!("Bearer {token}"))]
+            }
+            Some(RpcAuth::ApiKey { header, key }) => vec![(header.as_str(), key.clone())],
+        };
+        let metadata = metadata
+            .into_iter()
+            .map(|(key, value)| {
+                let key = AsciiMetadataKey::from_str(key)
+                    .with_context(|| format!("invalid auth header name {key}"))?;
+                let mut value = AsciiMetadataValue::try_from(value)
+                    .context("auth credentials must be visible ASCII")?;
+                value.set_sensitive(true);
+                Ok((key, value))
+            })
+            .collect::<Result<_>>()?;
+        Ok(AuthInterceptor { metadata })
+    }
+
+    /// Create a prover network client over `channel` with the credentials, compression, message
+    /// size limits and rate limits of this configuration.
+    pub fn client(&self, channel: Channel) -> Result<RpcClient> {
+        let channel = rpc_channel(channel, self.rate_limits.clone());
+        let mut client = ProverNetworkClient::with_interceptor(channel, self.interceptor()?);
+        if self.gzip {
+            client = client
+                .send_compressed(CompressionEncoding::Gzip)
+                .accept_compressed(CompressionEncoding::Gzip);
+        }
+        if let Some(size) = self.max_decoding_message_size {
+            client = client.max_decoding_message_size(size);
+        }
+        if let Some(size) = self.max_encoding_message_size {
+            client = client.max_encoding_message_size(size);
+        }
+        Ok(client)
+    }
+}
+
+/// An interceptor that attaches credentials to every request.
+#[derive(Debug, Clone, Default)]
+pub struct AuthInterceptor {
+    /// The metadata inserted into every request.
+    metadata: Vec<(AsciiMetadataKey, AsciiMetadataValue)>,
+}
+
+impl Interceptor for AuthInterceptor {
+    fn call(&mut self, mut request: Request<()>) -> Result<Request<()>, Status> {
+        for (key, value) in &self.metadata {
+            request.metadata_mut().insert(key.clone(), value.clone());
+        }
+        Ok(request)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rcgen::{
+        BasicConstraints, CertificateParams, CertifiedKey, DistinguishedName, DnType, IsCa, KeyPair,
+    };
+    use spn_artifact_types::{
+        artifact_store_client::ArtifactStoreClient,
+        artifact_store_server::{ArtifactStore, ArtifactStoreServer},
+        CreateArtifactRequest, CreateArtifactResponse,
+    };
+    use tokio::net::TcpListener;
+    use tonic::{
+        async_trait,
+        transport::{server::TcpIncoming, Server, ServerTlsConfig},
+        Response,
+    };
+
+    use super::*;
+
+    /// An artifact store that only answers requests with the expected credentials.
+    struct AuthenticatedStore;
+
+    #[async_trait]
+    impl ArtifactStore for AuthenticatedStore {
+        async fn create_artifact(
+            &self,
+            request: Request<CreateArtifactRequest>,
+        ) -> Result<Response<CreateArtifactResponse>, Status> {
+            let metadata = request.metadata();
+            if metadata.get("authorization").map(|v| v.to_str().unwrap()) != Some("Bearer secret") {
+                return Err(Status::unauthenticated("missing token"));
+            }
+            let user_agent = metadata.get("user-agent").unwrap().to_str().unwrap();
+            Ok(Response::new(CreateArtifactResponse {
+                artifact_uri: user_agent.to_string(),
+                ..Default::default()
+            }))
+        }
+    }
+
+    /// Create a certificate named `name`, signed by `issuer` or self-signed.
+    fn certificate(name: &str, issuer: Option<&CertifiedKey>) -> CertifiedKey {
+        let mut params = CertificateParams::new(vec![name.to_string()]).unwrap();
+        params.distinguished_name = DistinguishedName::new();
+        params.distinguished_name.push(DnType::CommonName, name);
+        if issuer.is_none() {
+            params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        }
+        let key_pair = KeyPair::generate().unwrap();
+        let cert = match issuer {
+            Some(issuer) => params.signed_by(&key_pair, &issuer.cert, &issuer.key_pair).unwrap(),
+            None => params.self_signed(&key_pair).unwrap(),
+        };
+        CertifiedKey { cert, key_pair }
+    }
+
+    /// Serve an [`AuthenticatedStore`] over TLS that requires a client certificate signed by `ca`.
+    async fn serve(ca: &CertifiedKey, server: &CertifiedKey) -> String {
+        let tls = ServerTlsConfig::new()
+            .identity(Identity::from_pem(server.cert.pem(), server.key_pair.serialize_pem()))
+            .client_ca_root(Certificate::from_pem(ca.cert.pem()));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let incoming = TcpIncoming::from_listener(listener, true, None).unwrap();
+        let router = Server::builder()
+            .tls_config(tls)
+            .unwrap()
+            .add_service(ArtifactStoreServer::new(AuthenticatedStore));
+        tokio::spawn(router.serve_with_incoming(incoming));
+        format!("https://{addr}")
+    }
+
+    #[tokio::test]
+    async fn test_mutual_tls_with_auth() {
+        let _ = rustls::crypto::ring::default_provider().install_default();
+        let ca = certificate("spn test ca", None);
+        let server = certificate("localhost", Some(&ca));
+        let client = certificate("spn test client", Some(&ca));
+        let url = serve(&ca, &server).await;
+
+        let config = EndpointConfig::default()
+            .with_ca_certificate(ca.cert.pem())
+            .with_identity(client.cert.pem(), client.key_pair.serialize_pem())
+            .with_domain_name("localhost")
+            .with_user_agent("spn-test")
+            .with_auth(RpcAuth::Bearer("secret".to_string()));
+        let channel = config.endpoint(&url).unwrap().connect().await.unwrap();
+        let mut store =
+            ArtifactStoreClient::with_interceptor(channel, config.interceptor().unwrap());
+        let response = store.create_artifact(CreateArtifactRequest::default()).await.unwrap();
+        assert!(response.into_inner().artifact_uri.starts_with("spn-test"));
+
+        // Without credentials the server rejects the request.
+        let channel = config.endpoint(&url).unwrap().connect().await.unwrap();
+        let err = ArtifactStoreClient::new(channel)
+            .create_artifact(CreateArtifactRequest::default())
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), tonic::Code::Unauthenticated);
+
+        // Without a client certificate the server rejects the connection.
+        let config = EndpointConfig { identity: None, ..config };
+        let channel = config.endpoint(&url).unwrap().connect_lazy();
+        let mut store =
+            ArtifactStoreClient::with_interceptor(channel, config.interceptor().unwrap());
+        assert!(store.create_artifact(CreateArtifactRequest::default()).await.is_err());
+    }
+
+    #[test]
+    fn test_auth_is_redacted() {
+        let config = EndpointConfig::default().with_auth(RpcAuth::ApiKey {
+            header: "x-api-key".to_string(),
+            key: "secret".to_string(),
+        });
+        assert!(!format!("{config:?}").contains("secret"));
+        assert!(config.interceptor().is_ok());
+
+        let config = config.with_auth(RpcAuth::ApiKey {
+            header: "x api key".to_string(),
+            key: "secret".to_string(),
+        });
+        assert!(config.interceptor().is_err());
+    }
+}
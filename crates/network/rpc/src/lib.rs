@@ -20,10 +20,24 @@
 #![allow(clippy::struct_excessive_bools)]
 #![warn(missing_docs)]
 
+mod api;
+mod breaker;
+mod client;
+mod endpoints;
 mod fetch;
 mod grpc;
+mod middleware;
+mod pagination;
 mod retry;
+mod subscription;
 
+pub use api::*;
+pub use breaker::*;
+pub use client::*;
+pub use endpoints::*;
 pub use fetch::*;
 pub use grpc::*;
+pub use middleware::*;
+pub use pagination::*;
 pub use retry::*;
+pub use subscription::*;
@@ -0,0 +1,452 @@
+use std::{
+    collections::HashMap,
+    fmt::{self, Debug},
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+use anyhow::{ensure, Result};
+use futures::future::BoxFuture;
+use metrics::{counter, histogram};
+use tonic::{
+    codegen::http::{Request, Response},
+    transport::Channel,
+    Code, Status,
+};
+use tower::{Layer, Service};
+use tracing::debug;
+
+/// The channel used by an [`crate::RpcClient`]: rate limited, then instrumented.
+pub type RpcChannel = RateLimited<Instrumented<Channel>>;
+
+/// The longest a call is delayed by a rate limit, however low the rate.
+const MAX_RATE_LIMIT_DELAY: Duration = Duration::from_secs(3600);
+
+/// The fields of requests and responses whose bytes are never logged.
+const REDACTED_FIELDS: &[&str] = &["signature", "proof", "vk"];
+
+/// Wrap `channel` with the middleware of every RPC client: rate limiting with `rate_limits`,
+/// followed by metrics and logging.
+#[must_use]
+pub fn rpc_channel(channel: Channel, rate_limits: RateLimitConfig) -> RpcChannel {
+    let channel = InstrumentLayer.layer(channel);
+    RateLimitLayer::new(rate_limits).layer(channel)
+}
+
+/// The name of the gRPC method called at `path`, e.g. `GetNonce` for
+/// `/network.ProverNetwork/GetNonce`.
+#[must_use]
+pub fn method_name(path: &str) -> &str {
+    path.rsplit('/').next().unwrap_or(path)
+}
+
+/// The rate at which calls to a method may be made.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimit {
+    /// The number of calls allowed per second, on average.
+    pub per_second: f64,
+    /// The number of calls that may be made at once after a quiet period.
+    pub burst: u32,
+}
+
+impl RateLimit {
+    /// Allow `per_second` calls per second, with bursts of up to `burst` calls.
+    ///
+    /// Fails unless `per_second` is positive and finite: calls that should not be limited are
+    /// left out of the [`RateLimitConfig`] instead.
+    pub fn new(per_second: f64, burst: u32) -> Result<Self> {
+        ensure!(
+            per_second.is_finite() && per_second > 0.0,
+            "rate limit must be a positive number of calls per second, got {per_second}"
+        );
+        Ok(Self { per_second, burst })
+    }
+}
+
+/// The rate limits of the RPC methods.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RateLimitConfig {
+    /// The limit of the methods without a limit of their own, or `None` for no limit.
+    pub default: Option<RateLimit>,
+    /// The limits of individual methods, keyed by method name, e.g. `GetNonce`.
+    pub methods: HashMap<String, RateLimit>,
+}
+
+impl RateLimitConfig {
+    /// Limit the methods without a limit of their own to `limit`.
+    #[must_use]
+    pub fn with_default(mut self, limit: RateLimit) -> Self {
+        self.default = Some(limit);
+        self
+    }
+
+    /// Limit calls to `method` to `limit`.
+    #[must_use]
+    pub fn with_method(mut self, method: &str, limit: RateLimit) -> Self {
+        self.methods.insert(method.to_string(), limit);
+        self
+    }
+
+    /// The limit of `method`, if any.
+    #[must_use]
+    pub fn limit(&self, method: &str) -> Option<RateLimit> {
+        self.methods.get(method).copied().or(self.default)
+    }
+}
+
+/// A token bucket that hands out reservations, so that callers are served in order.
+#[derive(Debug, Clone, Copy)]
+struct TokenBucket {
+    /// The tokens left, negative when calls are queued.
+    tokens: f64,
+    /// When the tokens were last refilled.
+    refilled_at: Instant,
+}
+
+impl TokenBucket {
+    /// Create a full bucket.
+    fn new(limit: RateLimit, now: Instant) -> Self {
+        Self { tokens: f64::from(limit.burst.max(1)), refilled_at: now }
+    }
+
+    /// Take a token, returning how long the caller must wait before using it, at most
+    /// [`MAX_RATE_LIMIT_DELAY`].
+    fn reserve(&mut self, limit: RateLimit, now: Instant) -> Duration {
+        let elapsed = now.saturating_duration_since(self.refilled_at).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * limit.per_second).min(f64::from(limit.burst.max(1)));
+        self.refilled_at = now;
+        self.tokens -= 1.0;
+        if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            // Rates too low to represent wait as long as possible, rather than not at all.
+            Duration::try_from_secs_f64(-self.tokens / limit.per_second)
+                .map_or(MAX_RATE_LIMIT_DELAY, |delay| delay.min(MAX_RATE_LIMIT_DELAY))
+        }
+    }
+}
+
+/// A [`Layer`] that limits the rate of calls to each gRPC method.
+#[derive(Debug, Clone)]
+pub struct RateLimitLayer {
+    /// The rate limits of the methods.
+    config: Arc<RateLimitConfig>,
+    /// The buckets of the methods, shared by every service created by the layer.
+    buckets: Arc<Mutex<HashMap<String, TokenBucket>>>,
+}
+
+impl RateLimitLayer {
+    /// Create a layer that limits calls according to `config`.
+    #[must_use]
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self { config: Arc::new(config), buckets: Arc::default() }
+    }
+
+    /// Reserve a call to `method`, returning how long the caller must wait before making it.
+    fn reserve(&self, method: &str, now: Instant) -> Duration {
+        let Some(limit) = self.config.limit(method) else {
+            return Duration::ZERO;
+        };
+        let mut buckets = self.buckets.lock().unwrap();
+        buckets
+            .entry(method.to_string())
+            .or_insert_with(|| TokenBucket::new(limit, now))
+            .reserve(limit, now)
+    }
+}
+
+impl<S> Layer<S> for RateLimitLayer {
+    type Service = RateLimited<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RateLimited { inner, limiter: self.clone() }
+    }
+}
+
+/// A service that delays calls to keep each gRPC method under its rate limit.
+///
+/// Calls over the limit are queued rather than rejected.
+#[derive(Debug, Clone)]
+pub struct RateLimited<S> {
+    /// The wrapped service.
+    inner: S,
+    /// The shared limiter.
+    limiter: RateLimitLayer,
+}
+
+impl<S, ReqBody> Service<Request<ReqBody>> for RateLimited<S>
+where
+    S: Service<Request<ReqBody>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    ReqBody: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<S::Response, S::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<ReqBody>) -> Self::Future {
+        let method = method_name(request.uri().path()).to_string();
+        let delay = self.limiter.reserve(&method, Instant::now());
+        if delay.is_zero() {
+            return Box::pin(self.inner.call(request));
+        }
+
+        // Call the service that was polled ready once the delay is over.
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        debug!(method, ?delay, "rate limiting rpc call");
+        counter!("spn_rpc_rate_limited_total", "method" => method.clone()).increment(1);
+        histogram!("spn_rpc_rate_limit_delay_seconds", "method" => method)
+            .record(delay.as_secs_f64());
+        Box::pin(async move {
+            tokio::time::sleep(delay).await;
+            inner.call(request).await
+        })
+    }
+}
+
+/// A [`Layer`] that records the latency and outcome of every gRPC call.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct InstrumentLayer;
+
+impl<S> Layer<S> for InstrumentLayer {
+    type Service = Instrumented<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        Instrumented { inner }
+    }
+}
+
+/// A service that records the latency and outcome of every gRPC call.
+///
+/// Latencies are recorded in `spn_rpc_request_duration_seconds` and outcomes in
+/// `spn_rpc_requests_total`, both labelled with the method. The outcome of a call is the gRPC
+/// status sent in the response headers, `Ok` when the status only follows the body, or
+/// `transport` when no response was received.
+#[derive(Debug, Clone)]
+pub struct Instrumented<S> {
+    /// The wrapped service.
+    inner: S,
+}
+
+impl<S, ReqBody, ResBody> Service<Request<ReqBody>> for Instrumented<S>
+where
+    S: Service<Request<ReqBody>, Response = Response<ResBody>>,
+    S::Future: Send + 'static,
+    S::Error: fmt::Display,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<S::Response, S::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<ReqBody>) -> Self::Future {
+        let method = method_name(request.uri().path()).to_string();
+        let future = self.inner.call(request);
+        Box::pin(async move {
+            let start = Instant::now();
+            let result = future.await;
+            let elapsed = start.elapsed();
+
+            let code = match &result {
+                Ok(response) => {
+                    let code = Status::from_header_map(response.headers())
+                        .map_or(Code::Ok, |status| status.code());
+                    format!("{code:?}")
+                }
+                Err(e) => {
+                    debug!(method, error = %e, "rpc transport error");
+                    "transport".to_string()
+                }
+            };
+            debug!(method, code, ?elapsed, "rpc call completed");
+            histogram!("spn_rpc_request_duration_seconds", "method" => method.clone())
+                .record(elapsed.as_secs_f64());
+            counter!("spn_rpc_requests_total", "method" => method, "code" => code).increment(1);
+            result
+        })
+    }
+}
+
+/// Log a request sent to `method`, with signatures and proof bytes redacted.
+pub(crate) fn log_request<T: Debug>(method: &str, request: &T) {
+    debug!(method, request = %Redacted(request), "sending rpc request");
+}
+
+/// Log the response to a call to `method`, with signatures and proof bytes redacted, and return
+/// its message.
+pub(crate) fn log_response<T: Debug>(
+    method: &str,
+    response: Result<tonic::Response<T>, Status>,
+) -> Result<T, Status> {
+    match response {
+        Ok(response) => {
+            let response = response.into_inner();
+            debug!(method, response = %Redacted(&response), "received rpc response");
+            Ok(response)
+        }
+        Err(status) => {
+            debug!(method, code = ?status.code(), message = status.message(), "rpc call failed");
+            Err(status)
+        }
+    }
+}
+
+/// Formats a message like its [`Debug`] implementation, with the bytes of signatures and proofs
+/// replaced by their length.
+pub struct Redacted<'a, T>(pub &'a T);
+
+impl<T: Debug> fmt::Display for Redacted<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&redact(&format!("{:?}", self.0)))
+    }
+}
+
+/// Replace the byte arrays of the [`REDACTED_FIELDS`] in the compact [`Debug`] output `debug`.
+fn redact(debug: &str) -> String {
+    let mut redacted = String::with_capacity(debug.len());
+    let mut rest = debug;
+    loop {
+        let next = REDACTED_FIELDS
+            .iter()
+            .filter_map(|field| {
+                find_field(rest, field).map(|start| (start, start + field.len() + 2))
+            })
+            .min();
+        let Some((_, value_start)) = next else {
+            redacted.push_str(rest);
+            return redacted;
+        };
+
+        redacted.push_str(&rest[..value_start]);
+        rest = &rest[value_start..];
+        let prefix = if rest.starts_with('[') {
+            ""
+        } else if rest.starts_with("Some([") {
+            "Some("
+        } else {
+            continue;
+        };
+        let array = &rest[prefix.len()..];
+        let Some(end) = array.find(']') else {
+            continue;
+        };
+        let items = &array[1..end];
+        let len = if items.trim().is_empty() { 0 } else { items.matches(',').count() + 1 };
+        redacted.push_str(prefix);
+        redacted.push_str(&format!("<{len} bytes>"));
+        rest = &array[end + 1..];
+    }
+}
+
+/// The position of the first `field: ` in `debug` that is a whole field name.
+fn find_field(debug: &str, field: &str) -> Option<usize> {
+    let pattern = format!("{field}: ");
+    debug.match_indices(&pattern).map(|(start, _)| start).find(|&start| {
+        debug[..start].chars().next_back().map_or(true, |c| !c.is_alphanumeric() && c != '_')
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+
+    use spn_network_types::{
+        BidRequest, BidRequestBody, FulfillProofRequest, FulfillProofRequestBody,
+    };
+    use tower::{service_fn, ServiceExt};
+
+    use super::*;
+
+    #[test]
+    fn test_redacts_signatures_and_proofs() {
+        let bid = BidRequest {
+            format: 1,
+            signature: vec![7; 65],
+            body: Some(BidRequestBody { nonce: 3, request_id: vec![1, 2], ..Default::default() }),
+        };
+        let redacted = Redacted(&bid).to_string();
+        assert!(redacted.contains("signature: <65 bytes>"));
+        assert!(redacted.contains("request_id: [1, 2]"));
+        assert!(redacted.contains("nonce: 3"));
+
+        let fulfill = FulfillProofRequest {
+            format: 1,
+            signature: Vec::new(),
+            body: Some(FulfillProofRequestBody { proof: vec![9; 1000], ..Default::default() }),
+        };
+        let redacted = Redacted(&fulfill).to_string();
+        assert!(redacted.contains("signature: <0 bytes>"));
+        assert!(redacted.contains("proof: <1000 bytes>"));
+        assert!(!redacted.contains('9'));
+    }
+
+    #[test]
+    fn test_token_bucket() {
+        let limit = RateLimit::new(10.0, 2).unwrap();
+        let now = Instant::now();
+        let mut bucket = TokenBucket::new(limit, now);
+
+        // The burst goes through, then calls are spaced by the rate.
+        assert_eq!(bucket.reserve(limit, now), Duration::ZERO);
+        assert_eq!(bucket.reserve(limit, now), Duration::ZERO);
+        assert_eq!(bucket.reserve(limit, now).as_millis(), 100);
+        assert_eq!(bucket.reserve(limit, now).as_millis(), 200);
+
+        // Tokens refill over time, up to the burst.
+        let later = now + Duration::from_secs(10);
+        assert_eq!(bucket.reserve(limit, later), Duration::ZERO);
+        assert_eq!(bucket.reserve(limit, later), Duration::ZERO);
+        assert!(bucket.reserve(limit, later) > Duration::ZERO);
+    }
+
+    #[test]
+    fn test_rejects_invalid_rates() {
+        for rate in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            assert!(RateLimit::new(rate, 1).is_err(), "accepted {rate}");
+        }
+
+        // Tiny rates wait as long as possible instead of overflowing.
+        let limit = RateLimit::new(f64::MIN_POSITIVE, 1).unwrap();
+        let now = Instant::now();
+        let mut bucket = TokenBucket::new(limit, now);
+        assert_eq!(bucket.reserve(limit, now), Duration::ZERO);
+        assert_eq!(bucket.reserve(limit, now), MAX_RATE_LIMIT_DELAY);
+    }
+
+    #[tokio::test]
+    async fn test_rate_limits_each_method() {
+        let config =
+            RateLimitConfig::default().with_method("Bid", RateLimit::new(20.0, 1).unwrap());
+        let service = service_fn(|_: Request<()>| async { Ok::<_, Infallible>(Response::new(())) });
+        let mut service = RateLimitLayer::new(config).layer(service);
+        let call = |path: &str| Request::builder().uri(path).body(()).unwrap();
+
+        // Unlimited methods are not delayed.
+        let start = Instant::now();
+        for _ in 0..10 {
+            service
+                .ready()
+                .await
+                .unwrap()
+                .call(call("/network.ProverNetwork/GetNonce"))
+                .await
+                .unwrap();
+        }
+        assert!(start.elapsed() < Duration::from_millis(40));
+
+        // Limited methods are spaced by the rate.
+        for _ in 0..3 {
+            service.ready().await.unwrap().call(call("/network.ProverNetwork/Bid")).await.unwrap();
+        }
+        assert!(start.elapsed() >= Duration::from_millis(100));
+    }
+}
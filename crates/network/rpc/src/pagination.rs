@@ -0,0 +1,329 @@
+use std::future::Future;
+
+use anyhow::Result;
+use futures::{stream, Stream, TryStreamExt};
+use spn_network_types::{
+    BalanceLog, BidHistory, GetFilteredBalanceLogsRequest, GetFilteredBalanceLogsResponse,
+    GetFilteredBidHistoryRequest, GetFilteredBidHistoryResponse, GetFilteredProofRequestsRequest,
+    GetFilteredProofRequestsResponse, GetFilteredProverStakeBalanceLogsRequest,
+    GetFilteredProverStakeBalanceLogsResponse, GetFilteredWithdrawalReceiptsRequest,
+    GetFilteredWithdrawalReceiptsResponse, ProofRequest, StakeBalanceLog, WithdrawalReceipt,
+};
+use tonic::Status;
+
+use crate::{retry_operation, ProverNetworkApi};
+
+/// The largest page size accepted by the network.
+pub const MAX_PAGE_SIZE: u32 = 100;
+
+/// How a paginated query is walked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PageConfig {
+    /// The number of items fetched per page, at most [`MAX_PAGE_SIZE`].
+    pub page_size: u32,
+    /// The maximum number of items to yield, or `None` to walk every page.
+    pub max_items: Option<usize>,
+}
+
+impl Default for PageConfig {
+    fn default() -> Self {
+        Self { page_size: MAX_PAGE_SIZE, max_items: None }
+    }
+}
+
+/// A query whose results are split into pages.
+///
+/// Pages are numbered from 1.
+pub trait PaginatedRequest: Clone + Send + Sync {
+    /// The response to a single page.
+    type Response;
+    /// The items listed in a response.
+    type Item;
+
+    /// Select the page `page` of `limit` items.
+    fn set_page(&mut self, page: u32, limit: u32);
+
+    /// The items listed in `response`.
+    fn into_items(response: Self::Response) -> Vec<Self::Item>;
+}
+
+impl PaginatedRequest for GetFilteredProofRequestsRequest {
+    type Response = GetFilteredProofRequestsResponse;
+    type Item = ProofRequest;
+
+    fn set_page(&mut self, page: u32, limit: u32) {
+        self.page = Some(page);
+        self.limit = Some(limit);
+    }
+
+    fn into_items(response: Self::Response) -> Vec<Self::Item> {
+        response.requests
+    }
+}
+
+impl PaginatedRequest for GetFilteredBalanceLogsRequest {
+    type Response = GetFilteredBalanceLogsResponse;
+    type Item = BalanceLog;
+
+    fn set_page(&mut self, page: u32, limit: u32) {
+        self.page = Some(page);
+        self.limit = Some(limit);
+    }
+
+    fn into_items(response: Self::Response) -> Vec<Self::Item> {
+        response.logs
+    }
+}
+
+impl PaginatedRequest for GetFilteredBidHistoryRequest {
+    type Response = GetFilteredBidHistoryResponse;
+    type Item = BidHistory;
+
+    fn set_page(&mut self, page: u32, limit: u32) {
+        self.page = Some(page);
+        self.limit = Some(limit);
+    }
+
+    fn into_items(response: Self::Response) -> Vec<Self::Item> {
+        response.bids
+    }
+}
+
+impl PaginatedRequest for GetFilteredProverStakeBalanceLogsRequest {
+    type Response = GetFilteredProverStakeBalanceLogsResponse;
+    type Item = StakeBalanceLog;
+
+    fn set_page(&mut self, page: u32, limit: u32) {
+        self.page = Some(page);
+        self.limit = Some(limit);
+    }
+
+    fn into_items(response: Self::Response) -> Vec<Self::Item> {
+        response.logs
+    }
+}
+
+impl PaginatedRequest for GetFilteredWithdrawalReceiptsRequest {
+    type Response = GetFilteredWithdrawalReceiptsResponse;
+    type Item = WithdrawalReceipt;
+
+    fn set_page(&mut self, page: u32, limit: u32) {
+        self.page = Some(page);
+        self.limit = Some(limit);
+    }
+
+    fn into_items(response: Self::Response) -> Vec<Self::Item> {
+        response.receipts
+    }
+}
+
+/// Stream the items of a paginated query, fetching pages with `fetch` as they are needed.
+///
+/// Each page is fetched with retries. The stream ends after the first page that is not full, once
+/// `config.max_items` items were yielded, or after the first error.
+///
+/// Every page is requested with the same size, since the network offsets pages by their size, and
+/// the items of the last page past `config.max_items` are dropped.
+pub fn paginate<R, F, Fut>(
+    request: R,
+    config: PageConfig,
+    operation_name: &'static str,
+    fetch: F,
+) -> impl Stream<Item = Result<R::Item>>
+where
+    R: PaginatedRequest,
+    F: Fn(R) -> Fut + Send + Sync,
+    Fut: Future<Output = Result<R::Response, Status>> + Send,
+{
+    let page_size = config.page_size.clamp(1, MAX_PAGE_SIZE);
+    let pages = stream::try_unfold(
+        (fetch, request, 1, config.max_items, false),
+        move |(fetch, mut request, page, remaining, done)| async move {
+            if done || remaining == Some(0) {
+                return Ok(None);
+            }
+
+            request.set_page(page, page_size);
+            let response = retry_operation(
+                || async { Ok(fetch(request.clone()).await?) },
+                None,
+                operation_name,
+            )
+            .await?;
+
+            let mut items = R::into_items(response);
+            let done = items.len() < page_size as usize;
+            items.truncate(remaining.unwrap_or(usize::MAX).min(page_size as usize));
+            let remaining = remaining.map(|remaining| remaining - items.len());
+            Ok(Some((items, (fetch, request, page + 1, remaining, done))))
+        },
+    );
+    pages.map_ok(|items| stream::iter(items.into_iter().map(Ok))).try_flatten()
+}
+
+/// Stream the proof requests that match `request`.
+pub fn stream_proof_requests<N: ProverNetworkApi>(
+    network: &N,
+    request: GetFilteredProofRequestsRequest,
+    config: PageConfig,
+) -> impl Stream<Item = Result<ProofRequest>> {
+    let network = network.clone();
+    paginate(request, config, "get filtered proof requests", move |request| {
+        let network = network.clone();
+        async move { network.get_filtered_proof_requests(request).await }
+    })
+}
+
+/// Stream the balance logs that match `request`.
+pub fn stream_balance_logs<N: ProverNetworkApi>(
+    network: &N,
+    request: GetFilteredBalanceLogsRequest,
+    config: PageConfig,
+) -> impl Stream<Item = Result<BalanceLog>> {
+    let network = network.clone();
+    paginate(request, config, "get filtered balance logs", move |request| {
+        let network = network.clone();
+        async move { network.get_filtered_balance_logs(request).await }
+    })
+}
+
+/// Stream the bid history that matches `request`.
+pub fn stream_bid_history<N: ProverNetworkApi>(
+    network: &N,
+    request: GetFilteredBidHistoryRequest,
+    config: PageConfig,
+) -> impl Stream<Item = Result<BidHistory>> {
+    let network = network.clone();
+    paginate(request, config, "get filtered bid history", move |request| {
+        let network = network.clone();
+        async move { network.get_filtered_bid_history(request).await }
+    })
+}
+
+/// Stream the prover stake balance logs that match `request`.
+pub fn stream_prover_stake_balance_logs<N: ProverNetworkApi>(
+    network: &N,
+    request: GetFilteredProverStakeBalanceLogsRequest,
+    config: PageConfig,
+) -> impl Stream<Item = Result<StakeBalanceLog>> {
+    let network = network.clone();
+    paginate(request, config, "get filtered prover stake balance logs", move |request| {
+        let network = network.clone();
+        async move { network.get_filtered_prover_stake_balance_logs(request).await }
+    })
+}
+
+/// Stream the withdrawal receipts that match `request`.
+pub fn stream_withdrawal_receipts<N: ProverNetworkApi>(
+    network: &N,
+    request: GetFilteredWithdrawalReceiptsRequest,
+    config: PageConfig,
+) -> impl Stream<Item = Result<WithdrawalReceipt>> {
+    let network = network.clone();
+    paginate(request, config, "get filtered withdrawal receipts", move |request| {
+        let network = network.clone();
+        async move { network.get_filtered_withdrawal_receipts(request).await }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicU32, Ordering},
+        Mutex,
+    };
+
+    use super::*;
+
+    /// A query over the numbers `0..total`.
+    #[derive(Debug, Clone, Default)]
+    struct NumbersRequest {
+        page: u32,
+        limit: u32,
+    }
+
+    impl PaginatedRequest for NumbersRequest {
+        type Response = Vec<u32>;
+        type Item = u32;
+
+        fn set_page(&mut self, page: u32, limit: u32) {
+            self.page = page;
+            self.limit = limit;
+        }
+
+        fn into_items(response: Self::Response) -> Vec<Self::Item> {
+            response
+        }
+    }
+
+    fn page(request: &NumbersRequest, total: u32) -> Vec<u32> {
+        let start = (request.page - 1) * request.limit;
+        (start..total.min(start + request.limit)).collect()
+    }
+
+    #[tokio::test]
+    async fn test_walks_every_page() {
+        let pages = Mutex::new(Vec::new());
+        let config = PageConfig { page_size: 40, max_items: None };
+        let items: Vec<u32> = paginate(NumbersRequest::default(), config, "test walk", |request| {
+            pages.lock().unwrap().push((request.page, request.limit));
+            async move { Ok(page(&request, 100)) }
+        })
+        .try_collect()
+        .await
+        .unwrap();
+
+        assert_eq!(items, (0..100).collect::<Vec<_>>());
+        assert_eq!(*pages.lock().unwrap(), vec![(1, 40), (2, 40), (3, 40)]);
+    }
+
+    #[tokio::test]
+    async fn test_stops_at_max_items() {
+        let pages = Mutex::new(Vec::new());
+        let config = PageConfig { page_size: 500, max_items: Some(150) };
+        let items: Vec<u32> =
+            paginate(NumbersRequest::default(), config, "test max items", |request| {
+                pages.lock().unwrap().push((request.page, request.limit));
+                async move { Ok(page(&request, 1000)) }
+            })
+            .try_collect()
+            .await
+            .unwrap();
+
+        // The page size is capped and kept for the last page, whose extra items are dropped.
+        assert_eq!(items, (0..150).collect::<Vec<_>>());
+        assert_eq!(*pages.lock().unwrap(), vec![(1, 100), (2, 100)]);
+    }
+
+    #[tokio::test]
+    async fn test_retries_failed_pages() {
+        let calls = AtomicU32::new(0);
+        let items: Vec<u32> =
+            paginate(NumbersRequest::default(), PageConfig::default(), "test retry", |request| {
+                let call = calls.fetch_add(1, Ordering::SeqCst);
+                async move {
+                    if call == 0 {
+                        return Err(Status::unavailable("down"));
+                    }
+                    Ok(page(&request, 10))
+                }
+            })
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert_eq!(items.len(), 10);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_ends_on_permanent_error() {
+        let result: Result<Vec<u32>> =
+            paginate(NumbersRequest::default(), PageConfig::default(), "test error", |_| async {
+                Err(Status::invalid_argument("bad filter"))
+            })
+            .try_collect()
+            .await;
+        assert!(result.is_err());
+    }
+}
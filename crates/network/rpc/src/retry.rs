@@ -1,14 +1,178 @@
 use anyhow::Result;
-use backoff::{future::retry, Error as BackoffError, ExponentialBackoff};
-use spn_network_types::prover_network_client::ProverNetworkClient;
-use std::time::Duration;
-use tonic::{async_trait, transport::Channel, Code};
+use backoff::{backoff::Backoff, ExponentialBackoff};
+use metrics::counter;
+use std::{
+    collections::HashMap,
+    io::ErrorKind,
+    sync::{LazyLock, RwLock},
+    time::{Duration, Instant},
+};
+use tonic::{async_trait, Code, Status};
 use tracing::{error, warn};
 
+use crate::{CircuitBreaker, CircuitBreakerConfig, ProverNetworkApi};
+
 /// Default timeout for retry operations.
 pub const DEFAULT_RETRY_TIMEOUT: Duration = Duration::from_secs(120);
 
+/// The retry policy used by [`retry_operation`] and [`RetryableRpc`].
+static RETRY_POLICY: LazyLock<RwLock<RetryPolicy>> =
+    LazyLock::new(|| RwLock::new(RetryPolicy::default()));
+
+/// How failed operations are retried.
+///
+/// Delays grow exponentially from `initial_interval` by `multiplier` up to `max_interval`, and
+/// each delay is randomized by up to `jitter` in either direction. Retries stop once `max_attempts`
+/// attempts have been made or `timeout` has elapsed, whichever comes first.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    /// The delay before the first retry.
+    pub initial_interval: Duration,
+    /// The maximum delay between two attempts.
+    pub max_interval: Duration,
+    /// The factor by which the delay grows after each attempt.
+    pub multiplier: f64,
+    /// The randomization factor of each delay, between 0 and 1.
+    pub jitter: f64,
+    /// The maximum number of attempts, including the first one.
+    pub max_attempts: Option<u32>,
+    /// The maximum time spent retrying.
+    pub timeout: Option<Duration>,
+    /// The circuit breaker applied to each operation, if any. Off by default.
+    ///
+    /// The breaker is only checked before the first attempt of an operation, so an operation that
+    /// was let through keeps retrying according to this policy, and its failures count towards
+    /// opening the circuit for later operations.
+    pub circuit_breaker: Option<CircuitBreakerConfig>,
+    /// Policies that replace this one for specific operations, keyed by operation name.
+    pub overrides: HashMap<String, RetryPolicy>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            initial_interval: Duration::from_secs(1),
+            max_interval: Duration::from_secs(120),
+            multiplier: 1.5,
+            jitter: 0.5,
+            max_attempts: None,
+            timeout: Some(DEFAULT_RETRY_TIMEOUT),
+            circuit_breaker: None,
+            overrides: HashMap::new(),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Use `policy` for the operation named `operation`.
+    #[must_use]
+    pub fn with_override(mut self, operation: &str, policy: RetryPolicy) -> Self {
+        self.overrides.insert(operation.to_string(), policy);
+        self
+    }
+
+    /// The policy for the operation named `operation`.
+    #[must_use]
+    pub fn for_operation(&self, operation: &str) -> &RetryPolicy {
+        self.overrides.get(operation).unwrap_or(self)
+    }
+
+    /// The backoff that produces the delays between attempts.
+    fn backoff(&self) -> ExponentialBackoff {
+        ExponentialBackoff {
+            current_interval: self.initial_interval,
+            initial_interval: self.initial_interval,
+            randomization_factor: self.jitter,
+            multiplier: self.multiplier,
+            max_interval: self.max_interval,
+            max_elapsed_time: self.timeout,
+            ..Default::default()
+        }
+    }
+}
+
+/// Replace the retry policy used by [`retry_operation`] and [`RetryableRpc`].
+pub fn set_retry_policy(policy: RetryPolicy) {
+    *RETRY_POLICY.write().unwrap() = policy;
+}
+
+/// The retry policy used by [`retry_operation`] and [`RetryableRpc`].
+#[must_use]
+pub fn retry_policy() -> RetryPolicy {
+    RETRY_POLICY.read().unwrap().clone()
+}
+
+/// Whether an error is worth retrying.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorClass {
+    /// The error is likely to go away on its own, e.g. the network is unreachable.
+    Transient,
+    /// Retrying will not help, e.g. the request was rejected.
+    Permanent,
+}
+
+impl ErrorClass {
+    /// The name of the class, used as a metric label.
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Transient => "transient",
+            Self::Permanent => "permanent",
+        }
+    }
+}
+
+/// Classify an error by walking its chain of sources.
+///
+/// A [`Status`] is classified by its code. Statuses with an `Unknown` code, and errors that are not
+/// statuses, are classified by their sources: transport, HTTP and I/O errors on the connection are
+/// transient. Anything else is permanent.
+#[must_use]
+pub fn classify_error(error: &anyhow::Error) -> ErrorClass {
+    for cause in error.chain() {
+        if let Some(status) = cause.downcast_ref::<Status>() {
+            match status.code() {
+                Code::Unavailable
+                | Code::DeadlineExceeded
+                | Code::Internal
+                | Code::Aborted
+                | Code::ResourceExhausted => return ErrorClass::Transient,
+                // The code is not conclusive, so look at the source of the status.
+                Code::Unknown => continue,
+                _ => return ErrorClass::Permanent,
+            }
+        }
+        if cause.is::<tonic::transport::Error>() || cause.is::<tokio::time::error::Elapsed>() {
+            return ErrorClass::Transient;
+        }
+        if let Some(e) = cause.downcast_ref::<hyper::Error>() {
+            if !(e.is_parse() || e.is_user()) {
+                return ErrorClass::Transient;
+            }
+        }
+        if let Some(e) = cause.downcast_ref::<std::io::Error>() {
+            if matches!(
+                e.kind(),
+                ErrorKind::ConnectionRefused
+                    | ErrorKind::ConnectionReset
+                    | ErrorKind::ConnectionAborted
+                    | ErrorKind::NotConnected
+                    | ErrorKind::BrokenPipe
+                    | ErrorKind::TimedOut
+                    | ErrorKind::UnexpectedEof
+            ) {
+                return ErrorClass::Transient;
+            }
+        }
+    }
+    ErrorClass::Permanent
+}
+
 /// Trait for implementing retryable RPC operations.
+///
+/// Implemented for every [`ProverNetworkApi`]. Calls to other services, e.g. through an
+/// `ArtifactStoreClient`, are retried with [`retry_operation`] or [`retry_with_policy`], so they
+/// share the same [`RetryPolicy`] and circuit breakers.
 #[async_trait]
 pub trait RetryableRpc {
     /// Execute an operation with retries using default timeout.
@@ -31,7 +195,9 @@ pub trait RetryableRpc {
         T: Send;
 }
 
-/// Execute an async operation with exponential backoff retries.
+/// Execute an async operation with retries according to the current [`RetryPolicy`].
+///
+/// If `timeout` is set, it replaces the timeout of the policy.
 pub async fn retry_operation<T, F, Fut>(
     operation: F,
     timeout: Option<Duration>,
@@ -41,83 +207,91 @@ where
     F: Fn() -> Fut + Send + Sync,
     Fut: std::future::Future<Output = Result<T>> + Send,
 {
-    let backoff = ExponentialBackoff {
-        initial_interval: Duration::from_secs(1),
-        max_interval: Duration::from_secs(120),
-        max_elapsed_time: timeout,
-        ..Default::default()
-    };
-
-    retry(backoff, || async {
-        match operation().await {
-            Ok(result) => Ok(result),
-            Err(e) => {
-                // Check for tonic status errors.
-                if let Some(status) = e.downcast_ref::<tonic::Status>() {
-                    match status.code() {
-                        Code::Unavailable
-                        | Code::DeadlineExceeded
-                        | Code::Internal
-                        | Code::Aborted => {
-                            warn!(
-                                "Network temporarily unavailable when {} due to {}, retrying...",
-                                operation_name,
-                                status.message(),
-                            );
-                            Err(BackoffError::transient(e))
-                        }
-                        Code::NotFound => {
-                            error!("{} not found due to {}", operation_name, status.message(),);
-                            Err(BackoffError::permanent(e))
-                        }
-                        _ => {
-                            error!(
-                                "Permanent error encountered when {}: {} ({})",
-                                operation_name,
-                                status.message(),
-                                status.code()
-                            );
-                            Err(BackoffError::permanent(e))
-                        }
-                    }
-                } else {
-                    // Check for common transport errors.
-                    let error_msg = e.to_string().to_lowercase();
-                    let is_transient = error_msg.contains("tls handshake")
-                        || error_msg.contains("dns error")
-                        || error_msg.contains("connection reset")
-                        || error_msg.contains("broken pipe")
-                        || error_msg.contains("transport error")
-                        || error_msg.contains("failed to lookup")
-                        || error_msg.contains("timeout")
-                        || error_msg.contains("deadline exceeded");
-
-                    if is_transient {
-                        warn!(
-                            "Transient transport error when {}: {}, retrying...",
-                            operation_name, error_msg
-                        );
-                        Err(BackoffError::transient(e))
-                    } else {
-                        error!("Permanent error when {}: {}", operation_name, error_msg);
-                        Err(BackoffError::permanent(e))
-                    }
+    let mut policy = retry_policy().for_operation(operation_name).clone();
+    if timeout.is_some() {
+        policy.timeout = timeout;
+    }
+    retry_with_policy(operation, &policy, operation_name).await
+}
+
+/// Execute an async operation with retries according to `policy`, ignoring the current
+/// [`RetryPolicy`].
+pub async fn retry_with_policy<T, F, Fut>(
+    operation: F,
+    policy: &RetryPolicy,
+    operation_name: &str,
+) -> Result<T>
+where
+    F: Fn() -> Fut + Send + Sync,
+    Fut: std::future::Future<Output = Result<T>> + Send,
+{
+    let breaker = policy.circuit_breaker.map(|config| {
+        let breaker = CircuitBreaker::for_operation(operation_name);
+        (breaker, config)
+    });
+    if let Some((breaker, config)) = &breaker {
+        breaker.acquire(config, Instant::now())?;
+    }
+    let mut backoff = policy.backoff();
+    let mut attempt = 0;
+
+    loop {
+        attempt += 1;
+
+        let e = match operation().await {
+            Ok(result) => {
+                if let Some((breaker, _)) = &breaker {
+                    breaker.record_success();
                 }
+                return Ok(result);
             }
+            Err(e) => e,
+        };
+
+        let class = classify_error(&e);
+        counter!(
+            "spn_rpc_errors_total",
+            "operation" => operation_name.to_string(),
+            "class" => class.as_str()
+        )
+        .increment(1);
+        if class == ErrorClass::Permanent {
+            error!("Permanent error when {operation_name}: {e:#}");
+            return Err(e);
+        }
+        if let Some((breaker, config)) = &breaker {
+            breaker.record_failure(config, Instant::now());
         }
-    })
-    .await
+
+        let delay = if policy.max_attempts.is_some_and(|max| attempt >= max) {
+            None
+        } else {
+            backoff.next_backoff()
+        };
+        let Some(delay) = delay else {
+            error!("Giving up on {operation_name} after {attempt} attempts: {e:#}");
+            counter!("spn_rpc_retries_exhausted_total", "operation" => operation_name.to_string())
+                .increment(1);
+            return Err(e);
+        };
+
+        warn!(
+            "Transient error when {operation_name} (attempt {attempt}), retrying in {delay:?}: {e:#}"
+        );
+        counter!("spn_rpc_retries_total", "operation" => operation_name.to_string()).increment(1);
+        tokio::time::sleep(delay).await;
+    }
 }
 
 #[async_trait]
-impl RetryableRpc for ProverNetworkClient<Channel> {
+impl<N: ProverNetworkApi> RetryableRpc for N {
     async fn with_retry<'a, T, F, Fut>(&'a self, operation: F, operation_name: &str) -> Result<T>
     where
         F: Fn() -> Fut + Send + Sync + 'a,
         Fut: std::future::Future<Output = Result<T>> + Send,
         T: Send,
     {
-        self.with_retry_timeout(operation, DEFAULT_RETRY_TIMEOUT, operation_name).await
+        retry_operation(operation, None, operation_name).await
     }
 
     async fn with_retry_timeout<'a, T, F, Fut>(
@@ -134,3 +308,161 @@ impl RetryableRpc for ProverNetworkClient<Channel> {
         retry_operation(operation, Some(timeout), operation_name).await
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use anyhow::anyhow;
+
+    use super::*;
+
+    fn fast_policy() -> RetryPolicy {
+        RetryPolicy {
+            initial_interval: Duration::from_millis(1),
+            max_interval: Duration::from_millis(1),
+            jitter: 0.0,
+            ..RetryPolicy::default()
+        }
+    }
+
+    #[test]
+    fn test_classify_status_codes() {
+        let transient = anyhow::Error::new(Status::unavailable("down"));
+        assert_eq!(classify_error(&transient), ErrorClass::Transient);
+
+        let permanent = anyhow::Error::new(Status::invalid_argument("bad nonce"));
+        assert_eq!(classify_error(&permanent), ErrorClass::Permanent);
+
+        let wrapped = anyhow::Error::new(Status::deadline_exceeded("slow")).context("get nonce");
+        assert_eq!(classify_error(&wrapped), ErrorClass::Transient);
+    }
+
+    #[test]
+    fn test_classify_source_chain() {
+        let reset = std::io::Error::new(ErrorKind::ConnectionReset, "reset by peer");
+        let status = Status::from_error(Box::new(reset));
+        assert_eq!(classify_error(&anyhow::Error::new(status)), ErrorClass::Transient);
+
+        // Error messages alone are not enough to be considered transient.
+        assert_eq!(classify_error(&anyhow!("transport error")), ErrorClass::Permanent);
+    }
+
+    #[tokio::test]
+    async fn test_retry_stops_after_max_attempts() {
+        let policy = RetryPolicy { max_attempts: Some(3), ..fast_policy() };
+        let calls = AtomicU32::new(0);
+        let result: Result<()> = retry_with_policy(
+            || async {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Err(Status::unavailable("down").into())
+            },
+            &policy,
+            "test max attempts",
+        )
+        .await;
+        assert!(result.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_retry_does_not_retry_permanent_errors() {
+        let calls = AtomicU32::new(0);
+        let result: Result<()> = retry_with_policy(
+            || async {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Err(Status::permission_denied("nope").into())
+            },
+            &fast_policy(),
+            "test permanent",
+        )
+        .await;
+        assert!(result.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_open_circuit_fails_fast() {
+        let policy = RetryPolicy {
+            max_attempts: Some(2),
+            circuit_breaker: Some(CircuitBreakerConfig {
+                failure_threshold: 2,
+                open_duration: Duration::from_secs(60),
+            }),
+            ..fast_policy()
+        };
+        let calls = AtomicU32::new(0);
+        let operation = || async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            Err::<(), _>(Status::unavailable("down").into())
+        };
+
+        assert!(retry_with_policy(operation, &policy, "test circuit").await.is_err());
+        let err = retry_with_policy(operation, &policy, "test circuit").await.unwrap_err();
+        assert!(err.is::<crate::CircuitOpenError>());
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_circuit_does_not_interrupt_retries() {
+        let policy = RetryPolicy {
+            max_attempts: Some(4),
+            circuit_breaker: Some(CircuitBreakerConfig {
+                failure_threshold: 2,
+                open_duration: Duration::from_secs(60),
+            }),
+            ..fast_policy()
+        };
+        let calls = AtomicU32::new(0);
+        let result: Result<()> = retry_with_policy(
+            || async {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Err(Status::unavailable("down").into())
+            },
+            &policy,
+            "test circuit retries",
+        )
+        .await;
+        assert!(!result.unwrap_err().is::<crate::CircuitOpenError>());
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test]
+    async fn test_retry_other_clients() {
+        use spn_artifact_types::{
+            artifact_store_client::ArtifactStoreClient, CreateArtifactRequest,
+        };
+        use tonic::transport::Endpoint;
+
+        let channel = Endpoint::from_static("http://127.0.0.1:1").connect_lazy();
+        let client = ArtifactStoreClient::new(channel);
+        let policy = RetryPolicy { max_attempts: Some(2), ..fast_policy() };
+        let calls = AtomicU32::new(0);
+
+        let result = retry_with_policy(
+            || async {
+                calls.fetch_add(1, Ordering::SeqCst);
+                let req = CreateArtifactRequest::default();
+                Ok(client.clone().create_artifact(req).await?.into_inner())
+            },
+            &policy,
+            "test other clients",
+        )
+        .await;
+        assert_eq!(classify_error(&result.unwrap_err()), ErrorClass::Transient);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_circuit_breaker_is_opt_in() {
+        assert_eq!(RetryPolicy::default().circuit_breaker, None);
+    }
+
+    #[test]
+    fn test_policy_overrides() {
+        let bid = RetryPolicy { max_attempts: Some(1), ..RetryPolicy::default() };
+        let policy = RetryPolicy::default().with_override("Bid", bid.clone());
+        assert_eq!(policy.for_operation("Bid"), &bid);
+        assert_eq!(policy.for_operation("get nonce").max_attempts, None);
+    }
+}
@@ -0,0 +1,328 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    future::Future,
+    time::Duration,
+};
+
+use anyhow::Result;
+use backoff::{backoff::Backoff, ExponentialBackoff};
+use futures::{stream, stream::BoxStream, Stream, StreamExt, TryStreamExt};
+use metrics::counter;
+use spn_network_types::{GetFilteredProofRequestsRequest, ProofRequest};
+use tonic::Status;
+use tracing::{info, warn};
+
+use crate::{stream_proof_requests, PageConfig, ProverNetworkApi};
+
+/// The number of requests remembered to deduplicate a subscription.
+const DEDUP_CAPACITY: usize = 10_000;
+
+const SUBSCRIPTION_TAG: &str = "\x1b[36m[Subscription]\x1b[0m";
+
+/// How a [`subscribe_proof_requests`] stream reconnects.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SubscriptionConfig {
+    /// The delay before the first reconnection attempt.
+    pub initial_backoff: Duration,
+    /// The maximum delay between reconnection attempts.
+    pub max_backoff: Duration,
+}
+
+impl Default for SubscriptionConfig {
+    fn default() -> Self {
+        Self { initial_backoff: Duration::from_secs(1), max_backoff: Duration::from_secs(60) }
+    }
+}
+
+/// Subscribe to the proof requests that match `filter`, reconnecting whenever the subscription
+/// fails or ends.
+///
+/// After every reconnection, the requests created since the last one seen are fetched with
+/// `GetFilteredProofRequests`, so no request is missed while disconnected. A request is only yielded
+/// again when its `updated_at` is newer than the last time it was yielded.
+pub fn subscribe_proof_requests<N: ProverNetworkApi>(
+    network: &N,
+    filter: GetFilteredProofRequestsRequest,
+    config: SubscriptionConfig,
+) -> impl Stream<Item = ProofRequest> + Send {
+    let subscribe_network = network.clone();
+    let catch_up_network = network.clone();
+    resumable(
+        filter,
+        config,
+        move |filter| {
+            let network = subscribe_network.clone();
+            async move { network.subscribe_proof_requests(filter).await }
+        },
+        move |filter| {
+            stream_proof_requests(&catch_up_network, filter, PageConfig::default()).boxed()
+        },
+    )
+}
+
+/// A live subscription to proof requests.
+type LiveRequests = BoxStream<'static, Result<ProofRequest, Status>>;
+
+/// Turn subscriptions opened with `subscribe` into a single stream, catching up with `catch_up`
+/// after every reconnection.
+fn resumable<S, SFut, C>(
+    filter: GetFilteredProofRequestsRequest,
+    config: SubscriptionConfig,
+    subscribe: S,
+    catch_up: C,
+) -> impl Stream<Item = ProofRequest> + Send
+where
+    S: Fn(GetFilteredProofRequestsRequest) -> SFut + Send + 'static,
+    SFut: Future<Output = Result<LiveRequests, Status>> + Send,
+    C: Fn(GetFilteredProofRequestsRequest) -> BoxStream<'static, Result<ProofRequest>>
+        + Send
+        + 'static,
+{
+    let state = Subscription {
+        subscribe,
+        catch_up,
+        filter,
+        backoff: ExponentialBackoff {
+            current_interval: config.initial_backoff,
+            initial_interval: config.initial_backoff,
+            max_interval: config.max_backoff,
+            max_elapsed_time: None,
+            ..Default::default()
+        },
+        live: None,
+        connected: false,
+        pending: VecDeque::new(),
+        seen: HashMap::new(),
+        last_created_at: None,
+    };
+    stream::unfold(state, |mut state| async move {
+        let request = state.next().await;
+        Some((request, state))
+    })
+}
+
+/// The state of a resumable subscription.
+struct Subscription<S, C> {
+    /// Opens a live subscription.
+    subscribe: S,
+    /// Fetches the requests that match a filter.
+    catch_up: C,
+    /// The filter of the subscription.
+    filter: GetFilteredProofRequestsRequest,
+    /// The delay before the next reconnection attempt.
+    backoff: ExponentialBackoff,
+    /// The current live subscription, if connected.
+    live: Option<LiveRequests>,
+    /// Whether a subscription was opened before.
+    connected: bool,
+    /// The requests fetched while catching up that were not yielded yet.
+    pending: VecDeque<ProofRequest>,
+    /// The `updated_at` and `created_at` of the requests yielded so far, keyed by request id.
+    seen: HashMap<Vec<u8>, (u64, u64)>,
+    /// The latest `created_at` of the requests yielded so far.
+    last_created_at: Option<u64>,
+}
+
+impl<S, SFut, C> Subscription<S, C>
+where
+    S: Fn(GetFilteredProofRequestsRequest) -> SFut,
+    SFut: Future<Output = Result<LiveRequests, Status>>,
+    C: Fn(GetFilteredProofRequestsRequest) -> BoxStream<'static, Result<ProofRequest>>,
+{
+    /// Wait for the next request that was not yielded before.
+    async fn next(&mut self) -> ProofRequest {
+        loop {
+            if let Some(request) = self.pending.pop_front() {
+                if self.observe(&request) {
+                    return request;
+                }
+                continue;
+            }
+
+            let Some(live) = &mut self.live else {
+                self.reconnect().await;
+                continue;
+            };
+            match live.next().await {
+                Some(Ok(request)) => {
+                    self.backoff.reset();
+                    if self.observe(&request) {
+                        return request;
+                    }
+                }
+                Some(Err(e)) => {
+                    warn!("{SUBSCRIPTION_TAG} Proof request subscription failed: {e}");
+                    self.disconnect().await;
+                }
+                None => {
+                    warn!("{SUBSCRIPTION_TAG} Proof request subscription ended.");
+                    self.disconnect().await;
+                }
+            }
+        }
+    }
+
+    /// Open a new subscription and, if this is a reconnection, fetch the requests that were
+    /// missed while disconnected.
+    async fn reconnect(&mut self) {
+        let live = match (self.subscribe)(self.filter.clone()).await {
+            Ok(live) => live,
+            Err(e) => {
+                warn!("{SUBSCRIPTION_TAG} Failed to subscribe to proof requests: {e}");
+                self.wait().await;
+                return;
+            }
+        };
+
+        if self.connected {
+            counter!("spn_rpc_subscription_reconnects_total").increment(1);
+            if let Some(from) = self.last_created_at {
+                let filter =
+                    GetFilteredProofRequestsRequest { from: Some(from), ..self.filter.clone() };
+                match (self.catch_up)(filter).try_collect::<Vec<_>>().await {
+                    Ok(requests) => {
+                        info!(
+                            count = requests.len(),
+                            "{SUBSCRIPTION_TAG} Caught up on missed proof requests."
+                        );
+                        self.pending.extend(requests);
+                    }
+                    Err(e) => {
+                        warn!(
+                            "{SUBSCRIPTION_TAG} Failed to catch up on missed proof requests: {e:#}"
+                        );
+                        self.wait().await;
+                        return;
+                    }
+                }
+            }
+        }
+
+        self.connected = true;
+        self.live = Some(live);
+    }
+
+    /// Drop the current subscription and wait before reconnecting.
+    async fn disconnect(&mut self) {
+        self.live = None;
+        self.wait().await;
+    }
+
+    /// Wait before the next reconnection attempt.
+    async fn wait(&mut self) {
+        let delay = self.backoff.next_backoff().unwrap_or(self.backoff.max_interval);
+        tokio::time::sleep(delay).await;
+    }
+
+    /// Record that `request` is about to be yielded, returning false if it was yielded before.
+    fn observe(&mut self, request: &ProofRequest) -> bool {
+        if self
+            .seen
+            .get(&request.request_id)
+            .is_some_and(|&(updated_at, _)| updated_at >= request.updated_at)
+        {
+            return false;
+        }
+
+        if self.seen.len() >= DEDUP_CAPACITY {
+            // Forget the oldest half of the requests.
+            let mut created: Vec<u64> =
+                self.seen.values().map(|&(_, created_at)| created_at).collect();
+            created.sort_unstable();
+            let cutoff = created[created.len() / 2];
+            self.seen.retain(|_, &mut (_, created_at)| created_at > cutoff);
+        }
+        self.seen.insert(request.request_id.clone(), (request.updated_at, request.created_at));
+        self.last_created_at = self.last_created_at.max(Some(request.created_at));
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+
+    const CONFIG: SubscriptionConfig = SubscriptionConfig {
+        initial_backoff: Duration::from_millis(1),
+        max_backoff: Duration::from_millis(1),
+    };
+
+    fn request(id: u8, created_at: u64, updated_at: u64) -> ProofRequest {
+        ProofRequest { request_id: vec![id; 32], created_at, updated_at, ..Default::default() }
+    }
+
+    #[tokio::test]
+    async fn test_reconnects_deduplicates_and_catches_up() {
+        // The first subscription fails after two requests, the second one repeats a request that
+        // was caught up on and then updates another one.
+        let sessions = Arc::new(Mutex::new(VecDeque::from([
+            vec![Ok(request(1, 10, 10)), Ok(request(2, 20, 20)), Err(Status::unavailable("reset"))],
+            vec![Ok(request(3, 30, 30)), Ok(request(2, 20, 25))],
+        ])));
+        let catch_ups = Arc::new(Mutex::new(Vec::new()));
+
+        let subscribe = {
+            let sessions = sessions.clone();
+            move |_| {
+                let session = sessions.lock().unwrap().pop_front();
+                async move {
+                    Ok(match session {
+                        Some(session) => stream::iter(session).boxed(),
+                        None => stream::pending().boxed(),
+                    })
+                }
+            }
+        };
+        let catch_up = {
+            let catch_ups = catch_ups.clone();
+            move |filter: GetFilteredProofRequestsRequest| {
+                catch_ups.lock().unwrap().push(filter.from);
+                stream::iter(vec![Ok(request(2, 20, 20)), Ok(request(3, 30, 30))]).boxed()
+            }
+        };
+
+        let requests: Vec<_> =
+            resumable(GetFilteredProofRequestsRequest::default(), CONFIG, subscribe, catch_up)
+                .take(4)
+                .collect()
+                .await;
+
+        let ids: Vec<_> = requests.iter().map(|r| (r.request_id[0], r.updated_at)).collect();
+        assert_eq!(ids, vec![(1, 10), (2, 20), (3, 30), (2, 25)]);
+        assert_eq!(*catch_ups.lock().unwrap(), vec![Some(20)]);
+    }
+
+    #[tokio::test]
+    async fn test_retries_failed_subscriptions() {
+        let attempts = Arc::new(Mutex::new(0));
+        let subscribe = {
+            let attempts = attempts.clone();
+            move |_| {
+                let attempt = {
+                    let mut attempts = attempts.lock().unwrap();
+                    *attempts += 1;
+                    *attempts
+                };
+                async move {
+                    if attempt < 3 {
+                        return Err(Status::unavailable("down"));
+                    }
+                    Ok(stream::iter(vec![Ok(request(1, 10, 10))]).chain(stream::pending()).boxed())
+                }
+            }
+        };
+        let catch_up = |_| stream::empty().boxed();
+
+        let request =
+            resumable(GetFilteredProofRequestsRequest::default(), CONFIG, subscribe, catch_up)
+                .boxed()
+                .next()
+                .await
+                .unwrap();
+
+        assert_eq!(request.request_id, vec![1; 32]);
+        assert_eq!(*attempts.lock().unwrap(), 3);
+    }
+}
@@ -0,0 +1,354 @@
+use std::{
+    collections::BTreeMap,
+    fmt::Debug,
+    fs::File,
+    path::{Path, PathBuf},
+    process::{Child, Command},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, bail, Context, Result};
+use sp1_sdk::{EnvProver, SP1ProofMode, SP1Stdin};
+use tracing::info;
+
+use crate::{EventBus, NodeEvent};
+
+/// A proof generated by a [`ProverBackend`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BackendProof {
+    /// The bincode-serialized proof, in the format of the SP1 version of the backend.
+    pub proof: Vec<u8>,
+    /// The number of cycles the program executed.
+    pub cycles: u64,
+    /// How long generating the proof took.
+    pub proving_time: Duration,
+}
+
+/// How often a running worker is checked for completion or cancellation.
+const WORKER_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// A flag that asks a running proof to stop, shared between the prover and its backend.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    /// Ask the proof to stop.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    /// Whether the proof was asked to stop.
+    #[must_use]
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+/// A prover for the requests of one SP1 version.
+///
+/// Proofs are generated on a blocking thread, so backends may block.
+pub trait ProverBackend: Send + Sync + 'static {
+    /// Prove the execution of `program` with `stdin` in `mode` for the request `request_id`,
+    /// emitting the progress of the proof to `events`.
+    ///
+    /// `stdin` is the bincode-serialized stdin of the request, as the requester uploaded it, so
+    /// that each backend decodes it in the format of its own SP1 version.
+    ///
+    /// Backends that can be interrupted stop with an error once `cancel` is cancelled.
+    fn prove(
+        &self,
+        request_id: &[u8],
+        program: &[u8],
+        stdin: &[u8],
+        mode: SP1ProofMode,
+        events: &EventBus,
+        cancel: &CancelToken,
+    ) -> Result<BackendProof>;
+}
+
+impl ProverBackend for EnvProver {
+    /// Proofs are generated in process, so they run to completion even when cancelled.
+    fn prove(
+        &self,
+        request_id: &[u8],
+        program: &[u8],
+        stdin: &[u8],
+        mode: SP1ProofMode,
+        events: &EventBus,
+        _cancel: &CancelToken,
+    ) -> Result<BackendProof> {
+        const SERIAL_PROVER_TAG: &str = "\x1b[33m[SerialProver]\x1b[0m";
+
+        let stdin: SP1Stdin = bincode::deserialize(stdin).context("failed to decode stdin")?;
+        let stdin = &stdin;
+
+        let start = Instant::now();
+        info!("{SERIAL_PROVER_TAG} Setting up proving key...");
+        let (pk, _) = self.setup(program);
+        let duration = start.elapsed();
+        info!(duration = %duration.as_secs_f64(), "{SERIAL_PROVER_TAG} Set up proving key.");
+        events.emit(NodeEvent::SetupDone { request_id: request_id.to_vec(), duration });
+
+        let start = Instant::now();
+        info!("{SERIAL_PROVER_TAG} Executing program...");
+        let (_, report) = self.execute(&pk.elf, stdin).run().unwrap();
+        let cycles = report.total_instruction_count();
+        let duration = start.elapsed();
+        info!(duration = %duration.as_secs_f64(), cycles = %cycles, "{SERIAL_PROVER_TAG} Executed program.");
+        events.emit(NodeEvent::Executed { request_id: request_id.to_vec(), cycles, duration });
+
+        let start = Instant::now();
+        info!("{SERIAL_PROVER_TAG} Generating proof...");
+        let proof = EnvProver::prove(self, &pk, stdin).mode(mode).run()?;
+        let proving_time = start.elapsed();
+        info!(duration = %proving_time.as_secs_f64(), cycles = %cycles, "{SERIAL_PROVER_TAG} Proof generation complete.");
+        events.emit(NodeEvent::ProofGenerated {
+            request_id: request_id.to_vec(),
+            cycles,
+            duration: proving_time,
+        });
+
+        let proof = bincode::serialize(&proof).context("failed to serialize proof")?;
+        Ok(BackendProof { proof, cycles, proving_time })
+    }
+}
+
+/// A backend that runs a worker binary built against another SP1 version, once per proof.
+///
+/// The worker is run as `<binary> prove --mode <mode> --program <path> --stdin <path> --output
+/// <path>`, where the program file holds the ELF and the stdin file the stdin of the request as
+/// it was uploaded, which the worker decodes in the format of its own SP1 version.
+/// It writes the bincode-serialized proof to the output file, and prints the number of cycles the
+/// program executed as the last line of its stdout. The worker is killed when the proof is
+/// cancelled. See the README for the full contract.
+#[derive(Debug, Clone)]
+pub struct WorkerBackend {
+    /// The path of the worker binary.
+    binary: PathBuf,
+}
+
+impl WorkerBackend {
+    /// Create a new [`WorkerBackend`].
+    #[must_use]
+    pub fn new(binary: impl Into<PathBuf>) -> Self {
+        Self { binary: binary.into() }
+    }
+
+    /// Run the worker on the inputs in `dir`, killing it if `cancel` is cancelled.
+    fn run(
+        &self,
+        dir: &Path,
+        program: &[u8],
+        stdin: &[u8],
+        mode: SP1ProofMode,
+        cancel: &CancelToken,
+    ) -> Result<(Vec<u8>, u64)> {
+        let program_path = dir.join("program.elf");
+        let stdin_path = dir.join("stdin.bin");
+        let output_path = dir.join("proof.bin");
+        let stdout_path = dir.join("stdout.log");
+        let stderr_path = dir.join("stderr.log");
+        std::fs::write(&program_path, program).context("failed to write worker program")?;
+        std::fs::write(&stdin_path, stdin).context("failed to write worker stdin")?;
+
+        // The output goes to files, so a chatty worker cannot block on a full pipe.
+        let child = Command::new(&self.binary)
+            .arg("prove")
+            .arg("--mode")
+            .arg(mode_name(mode))
+            .arg("--program")
+            .arg(&program_path)
+            .arg("--stdin")
+            .arg(&stdin_path)
+            .arg("--output")
+            .arg(&output_path)
+            .stdout(File::create(&stdout_path).context("failed to create worker stdout")?)
+            .stderr(File::create(&stderr_path).context("failed to create worker stderr")?)
+            .spawn()
+            .with_context(|| format!("failed to run worker {}", self.binary.display()))?;
+        let mut child = WorkerChild(child);
+        let status = loop {
+            if cancel.is_cancelled() {
+                bail!("worker {} was cancelled", self.binary.display());
+            }
+            if let Some(status) = child.0.try_wait().context("failed to wait for worker")? {
+                break status;
+            }
+            std::thread::sleep(WORKER_POLL_INTERVAL);
+        };
+        if !status.success() {
+            let stderr = std::fs::read(&stderr_path).unwrap_or_default();
+            bail!(
+                "worker {} exited with {}: {}",
+                self.binary.display(),
+                status,
+                String::from_utf8_lossy(&stderr).trim()
+            );
+        }
+
+        let stdout = std::fs::read(&stdout_path).context("failed to read worker stdout")?;
+        let stdout = String::from_utf8_lossy(&stdout);
+        let cycles = stdout
+            .lines()
+            .next_back()
+            .and_then(|line| line.trim().parse().ok())
+            .ok_or_else(|| anyhow!("worker did not report the cycles executed"))?;
+        let proof = std::fs::read(&output_path).context("failed to read worker proof")?;
+        Ok((proof, cycles))
+    }
+}
+
+/// A running worker, killed when dropped so that it never outlives its proof.
+struct WorkerChild(Child);
+
+impl Drop for WorkerChild {
+    fn drop(&mut self) {
+        if matches!(self.0.try_wait(), Ok(None)) {
+            let _ = self.0.kill();
+            let _ = self.0.wait();
+        }
+    }
+}
+
+impl ProverBackend for WorkerBackend {
+    fn prove(
+        &self,
+        request_id: &[u8],
+        program: &[u8],
+        stdin: &[u8],
+        mode: SP1ProofMode,
+        events: &EventBus,
+        cancel: &CancelToken,
+    ) -> Result<BackendProof> {
+        const SERIAL_PROVER_TAG: &str = "\x1b[33m[SerialProver]\x1b[0m";
+
+        // Each proof gets its own private directory, which is removed when it is dropped.
+        let dir = tempfile::Builder::new()
+            .prefix("spn-worker-")
+            .tempdir()
+            .context("failed to create worker directory")?;
+
+        let start = Instant::now();
+        info!(worker = %self.binary.display(), "{SERIAL_PROVER_TAG} Generating proof with worker...");
+        let (proof, cycles) = self.run(dir.path(), program, stdin, mode, cancel)?;
+        let proving_time = start.elapsed();
+        info!(duration = %proving_time.as_secs_f64(), cycles = %cycles, "{SERIAL_PROVER_TAG} Proof generation complete.");
+        events.emit(NodeEvent::ProofGenerated {
+            request_id: request_id.to_vec(),
+            cycles,
+            duration: proving_time,
+        });
+
+        Ok(BackendProof { proof, cycles, proving_time })
+    }
+}
+
+/// The name of `mode` on the command line of a worker.
+fn mode_name(mode: SP1ProofMode) -> &'static str {
+    match mode {
+        SP1ProofMode::Core => "core",
+        SP1ProofMode::Compressed => "compressed",
+        SP1ProofMode::Plonk => "plonk",
+        SP1ProofMode::Groth16 => "groth16",
+    }
+}
+
+/// The prover backends of a node, keyed by the SP1 version of the requests they prove, like
+/// [`crate::SP1_NETWORK_VERSION`].
+#[derive(Clone, Default)]
+pub struct ProverRegistry {
+    /// The backends, keyed by version.
+    backends: BTreeMap<String, Arc<dyn ProverBackend>>,
+}
+
+impl ProverRegistry {
+    /// Create a new, empty [`ProverRegistry`].
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Prove the requests of `version` with `backend`, replacing any previous backend.
+    pub fn insert(&mut self, version: impl Into<String>, backend: Arc<dyn ProverBackend>) {
+        self.backends.insert(version.into(), backend);
+    }
+
+    /// The backend for the requests of `version`, if one is installed.
+    #[must_use]
+    pub fn get(&self, version: &str) -> Option<Arc<dyn ProverBackend>> {
+        self.backends.get(version).cloned()
+    }
+
+    /// The versions with an installed backend, in order.
+    #[must_use]
+    pub fn versions(&self) -> Vec<String> {
+        self.backends.keys().cloned().collect()
+    }
+}
+
+impl Debug for ProverRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ProverRegistry").field("versions", &self.versions()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registry_routes_by_version() {
+        let mut registry = ProverRegistry::new();
+        registry.insert("sp1-v0.0.2", Arc::new(WorkerBackend::new("false")));
+        registry.insert("sp1-v0.0.1", Arc::new(WorkerBackend::new("echo")));
+        assert_eq!(registry.versions(), vec!["sp1-v0.0.1", "sp1-v0.0.2"]);
+        assert!(registry.get("sp1-v0.0.3").is_none());
+
+        // Failing workers fail the proof instead of panicking.
+        let events = EventBus::default();
+        let prove = |version: &str| {
+            let backend = registry.get(version).unwrap();
+            let cancel = CancelToken::default();
+            backend.prove(&[1; 32], b"elf", b"stdin", SP1ProofMode::Core, &events, &cancel)
+        };
+        assert!(prove("sp1-v0.0.1").unwrap_err().to_string().contains("cycles"));
+        assert!(prove("sp1-v0.0.2").unwrap_err().to_string().contains("exited with"));
+    }
+
+    #[test]
+    fn test_cancel_kills_worker() {
+        // A worker that never finishes on its own.
+        let dir = tempfile::tempdir().unwrap();
+        let binary = dir.path().join("worker.sh");
+        std::fs::write(&binary, "#!/bin/sh\nexec sleep 60\n").unwrap();
+        let mut permissions = std::fs::metadata(&binary).unwrap().permissions();
+        std::os::unix::fs::PermissionsExt::set_mode(&mut permissions, 0o755);
+        std::fs::set_permissions(&binary, permissions).unwrap();
+
+        let cancel = CancelToken::default();
+        let canceller = {
+            let cancel = cancel.clone();
+            std::thread::spawn(move || {
+                std::thread::sleep(Duration::from_millis(200));
+                cancel.cancel();
+            })
+        };
+        let start = Instant::now();
+        let events = EventBus::default();
+        let result = WorkerBackend::new(&binary).prove(
+            &[1; 32],
+            b"elf",
+            b"stdin",
+            SP1ProofMode::Core,
+            &events,
+            &cancel,
+        );
+        canceller.join().unwrap();
+        assert!(result.unwrap_err().to_string().contains("cancelled"));
+        assert!(start.elapsed() < Duration::from_secs(10));
+    }
+}
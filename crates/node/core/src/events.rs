@@ -0,0 +1,203 @@
+use std::time::{Duration, SystemTime};
+
+use alloy_primitives::{Address, U256};
+use tokio::sync::broadcast;
+
+/// The default number of events buffered for each subscriber of an [`EventBus`].
+pub const DEFAULT_EVENT_BUS_CAPACITY: usize = 1024;
+
+/// An event emitted by the node while it processes requests.
+///
+/// Events are emitted on the [`EventBus`] of the [`crate::NodeContext`], so that metrics,
+/// notifications, journals and dashboards can observe the node without touching the bidder or the
+/// prover.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NodeEvent {
+    /// The bidder saw an unassigned request it may bid on.
+    RequestSeen {
+        /// The id of the request.
+        request_id: Vec<u8>,
+        /// The gas limit of the request.
+        gas_limit: u64,
+        /// The deadline of the request, as a Unix timestamp.
+        deadline: u64,
+    },
+    /// The bidder submitted a bid for a request.
+    BidSubmitted {
+        /// The id of the request.
+        request_id: Vec<u8>,
+        /// The amount that was bid.
+        amount: U256,
+        /// The prover the bid was submitted on behalf of.
+        prover: Address,
+    },
+    /// A request was assigned to the node and the prover picked it up.
+    Assigned {
+        /// The id of the request.
+        request_id: Vec<u8>,
+        /// The deadline of the request, as a Unix timestamp.
+        deadline: u64,
+    },
+    /// The proving key for a request was set up.
+    SetupDone {
+        /// The id of the request.
+        request_id: Vec<u8>,
+        /// How long the setup took.
+        duration: Duration,
+    },
+    /// The program of a request was executed.
+    Executed {
+        /// The id of the request.
+        request_id: Vec<u8>,
+        /// The number of cycles the program took.
+        cycles: u64,
+        /// How long the execution took.
+        duration: Duration,
+    },
+    /// A proof for a request was generated.
+    ProofGenerated {
+        /// The id of the request.
+        request_id: Vec<u8>,
+        /// The number of cycles that were proven.
+        cycles: u64,
+        /// How long the proof generation took.
+        duration: Duration,
+    },
+    /// A request was fulfilled on the network.
+    Fulfilled {
+        /// The id of the request.
+        request_id: Vec<u8>,
+        /// The size of the submitted proof in bytes.
+        proof_size: usize,
+    },
+    /// Proving a request failed and the request was reported as failed.
+    Failed {
+        /// The id of the request.
+        request_id: Vec<u8>,
+        /// A description of why the request failed.
+        cause: String,
+    },
+    /// Proving a request was cancelled because it was marked as unexecutable.
+    Cancelled {
+        /// The id of the request.
+        request_id: Vec<u8>,
+    },
+}
+
+impl NodeEvent {
+    /// The id of the request the event belongs to.
+    #[must_use]
+    pub fn request_id(&self) -> &[u8] {
+        match self {
+            Self::RequestSeen { request_id, .. }
+            | Self::BidSubmitted { request_id, .. }
+            | Self::Assigned { request_id, .. }
+            | Self::SetupDone { request_id, .. }
+            | Self::Executed { request_id, .. }
+            | Self::ProofGenerated { request_id, .. }
+            | Self::Fulfilled { request_id, .. }
+            | Self::Failed { request_id, .. }
+            | Self::Cancelled { request_id } => request_id,
+        }
+    }
+}
+
+/// A [`NodeEvent`] as received by subscribers of an [`EventBus`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EmittedEvent {
+    /// The event.
+    pub event: NodeEvent,
+    /// When the event was emitted.
+    pub emitted_at: SystemTime,
+}
+
+/// A broadcast bus of [`NodeEvent`]s.
+///
+/// Emitting never blocks and never fails: if there are no subscribers the event is dropped, and
+/// subscribers that fall behind by more than the capacity of the bus skip the oldest events.
+#[derive(Debug, Clone)]
+pub struct EventBus {
+    /// The sender half of the broadcast channel.
+    sender: broadcast::Sender<EmittedEvent>,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new(DEFAULT_EVENT_BUS_CAPACITY)
+    }
+}
+
+impl EventBus {
+    /// Create a new [`EventBus`] that buffers up to `capacity` events per subscriber.
+    #[must_use]
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity);
+        Self { sender }
+    }
+
+    /// Emit an event to all current subscribers, stamped with the current time.
+    pub fn emit(&self, event: NodeEvent) {
+        // An error only means that there are no subscribers right now.
+        let _ = self.sender.send(EmittedEvent { event, emitted_at: SystemTime::now() });
+    }
+
+    /// Subscribe to all events emitted from now on, in the order they are emitted.
+    #[must_use]
+    pub fn subscribe(&self) -> broadcast::Receiver<EmittedEvent> {
+        self.sender.subscribe()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_subscribers_receive_stamped_events_in_order() {
+        let bus = EventBus::default();
+        // Events emitted before subscribing, or without subscribers, are dropped.
+        bus.emit(NodeEvent::Cancelled { request_id: vec![0; 32] });
+        let mut events = bus.subscribe();
+
+        let before = SystemTime::now();
+        bus.emit(NodeEvent::RequestSeen { request_id: vec![1; 32], gas_limit: 100, deadline: 10 });
+        bus.emit(NodeEvent::BidSubmitted {
+            request_id: vec![1; 32],
+            amount: U256::from(5),
+            prover: Address::repeat_byte(1),
+        });
+        let after = SystemTime::now();
+
+        let seen = events.recv().await.unwrap();
+        assert_eq!(
+            seen.event,
+            NodeEvent::RequestSeen { request_id: vec![1; 32], gas_limit: 100, deadline: 10 }
+        );
+        let bid = events.recv().await.unwrap();
+        assert_eq!(
+            bid.event,
+            NodeEvent::BidSubmitted {
+                request_id: vec![1; 32],
+                amount: U256::from(5),
+                prover: Address::repeat_byte(1),
+            }
+        );
+        assert!(before <= seen.emitted_at);
+        assert!(seen.emitted_at <= bid.emitted_at);
+        assert!(bid.emitted_at <= after);
+        assert!(events.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_lagging_subscribers_skip_oldest_events() {
+        let bus = EventBus::new(2);
+        let mut events = bus.subscribe();
+        for id in 0..3 {
+            bus.emit(NodeEvent::Cancelled { request_id: vec![id; 32] });
+        }
+
+        assert!(matches!(events.recv().await, Err(broadcast::error::RecvError::Lagged(1))));
+        assert_eq!(events.recv().await.unwrap().event.request_id(), &[1; 32]);
+        assert_eq!(events.recv().await.unwrap().event.request_id(), &[2; 32]);
+    }
+}
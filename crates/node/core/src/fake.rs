@@ -0,0 +1,325 @@
+//! An in-memory fake of the prover network for unit tests.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use alloy_primitives::Signature;
+use futures::stream::BoxStream;
+use prost::Message;
+use spn_network_types::{
+    BidRequest, BidRequestBody, BidResponse, FailFulfillmentRequest, FailFulfillmentRequestBody,
+    FailFulfillmentResponse, FulfillProofRequest, FulfillProofRequestBody, FulfillProofResponse,
+    FulfillmentStatus, GetBalanceRequest, GetBalanceResponse, GetDelegationParamsRequest,
+    GetDelegationParamsResponse, GetFilteredBalanceLogsRequest, GetFilteredBalanceLogsResponse,
+    GetFilteredBidHistoryRequest, GetFilteredBidHistoryResponse, GetFilteredProofRequestsRequest,
+    GetFilteredProofRequestsResponse, GetFilteredProverStakeBalanceLogsRequest,
+    GetFilteredProverStakeBalanceLogsResponse, GetFilteredWithdrawalReceiptsRequest,
+    GetFilteredWithdrawalReceiptsResponse, GetNonceRequest, GetNonceResponse, GetOwnerRequest,
+    GetOwnerResponse, GetProofRequestDetailsRequest, GetProofRequestDetailsResponse,
+    GetProverStakeBalanceRequest, GetProverStakeBalanceResponse, GetTransferParamsRequest,
+    GetTransferParamsResponse, GetWithdrawParamsRequest, GetWithdrawParamsResponse, ProofRequest,
+    RequestProofRequest, RequestProofResponse, SetAccountNameRequest, SetAccountNameResponse,
+    SetDelegationRequest, SetDelegationResponse, TransferRequest, TransferResponse,
+    WithdrawRequest, WithdrawResponse,
+};
+use spn_rpc::ProverNetworkApi;
+use tonic::{async_trait, Status};
+
+/// The state of a [`FakeNetwork`].
+#[derive(Debug, Default)]
+struct FakeState {
+    /// The proof requests, keyed by request id.
+    requests: HashMap<Vec<u8>, ProofRequest>,
+    /// The nonce of each account.
+    nonces: HashMap<Vec<u8>, u64>,
+    /// The owner of each account. Accounts without an entry own themselves.
+    owners: HashMap<Vec<u8>, Vec<u8>>,
+    /// The balance of each account.
+    balances: HashMap<Vec<u8>, String>,
+    /// The accounts that bid on each request.
+    bidders: HashMap<Vec<u8>, Vec<Vec<u8>>>,
+    /// Every accepted bid.
+    bids: Vec<BidRequestBody>,
+    /// Every accepted fulfillment.
+    fulfillments: Vec<FulfillProofRequestBody>,
+    /// Every accepted failure.
+    failures: Vec<FailFulfillmentRequestBody>,
+    /// An error returned by every `fulfill_proof` call, if set.
+    fulfill_error: Option<Status>,
+}
+
+/// An in-memory [`ProverNetworkApi`] with scriptable request lifecycles.
+///
+/// Signed transactions are checked against the signer's nonce, which is bumped for every accepted
+/// transaction.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct FakeNetwork {
+    state: Arc<Mutex<FakeState>>,
+}
+
+impl FakeNetwork {
+    /// Insert or replace a proof request.
+    pub(crate) fn insert_request(&self, request: ProofRequest) {
+        self.state.lock().unwrap().requests.insert(request.request_id.clone(), request);
+    }
+
+    /// Make every subsequent `fulfill_proof` call fail with `status`.
+    pub(crate) fn fail_fulfillments_with(&self, status: Status) {
+        self.state.lock().unwrap().fulfill_error = Some(status);
+    }
+
+    /// The bids accepted so far.
+    pub(crate) fn bids(&self) -> Vec<BidRequestBody> {
+        self.state.lock().unwrap().bids.clone()
+    }
+
+    /// The fulfillments accepted so far.
+    pub(crate) fn fulfillments(&self) -> Vec<FulfillProofRequestBody> {
+        self.state.lock().unwrap().fulfillments.clone()
+    }
+
+    /// The failures accepted so far.
+    pub(crate) fn failures(&self) -> Vec<FailFulfillmentRequestBody> {
+        self.state.lock().unwrap().failures.clone()
+    }
+}
+
+impl FakeState {
+    /// Recover the signer of `body` and check and bump its nonce.
+    fn accept<M: Message>(
+        &mut self,
+        body: &M,
+        nonce: u64,
+        signature: &[u8],
+    ) -> Result<Vec<u8>, Status> {
+        let signature = Signature::try_from(signature)
+            .map_err(|e| Status::invalid_argument(format!("invalid signature: {e}")))?;
+        let signer = signature
+            .recover_address_from_msg(body.encode_to_vec())
+            .map_err(|e| Status::invalid_argument(format!("invalid signature: {e}")))?
+            .to_vec();
+
+        let expected = self.nonces.entry(signer.clone()).or_default();
+        if *expected != nonce {
+            return Err(Status::invalid_argument(format!(
+                "invalid nonce: expected {expected}, got {nonce}"
+            )));
+        }
+        *expected += 1;
+        Ok(signer)
+    }
+
+    /// The owner of `address`.
+    fn owner(&self, address: &[u8]) -> Vec<u8> {
+        self.owners.get(address).cloned().unwrap_or_else(|| address.to_vec())
+    }
+}
+
+#[async_trait]
+impl ProverNetworkApi for FakeNetwork {
+    async fn get_filtered_proof_requests(
+        &self,
+        request: GetFilteredProofRequestsRequest,
+    ) -> Result<GetFilteredProofRequestsResponse, Status> {
+        let state = self.state.lock().unwrap();
+        let mut requests: Vec<ProofRequest> = state
+            .requests
+            .values()
+            .filter(|r| request.version.as_ref().map_or(true, |v| &r.version == v))
+            .filter(|r| request.fulfillment_status.map_or(true, |s| r.fulfillment_status == s))
+            .filter(|r| request.execution_status.map_or(true, |s| r.execution_status == s))
+            .filter(|r| request.minimum_deadline.map_or(true, |d| r.deadline >= d))
+            .filter(|r| request.fulfiller.is_none() || r.fulfiller == request.fulfiller)
+            .filter(|r| {
+                request.not_bid_by.as_ref().map_or(true, |owner| {
+                    state.bidders.get(&r.request_id).map_or(true, |b| !b.contains(owner))
+                })
+            })
+            .cloned()
+            .collect();
+        requests.sort_by_key(|r| r.created_at);
+        if let Some(limit) = request.limit {
+            let page = request.page.unwrap_or(1).max(1) - 1;
+            requests =
+                requests.into_iter().skip((page * limit) as usize).take(limit as usize).collect();
+        }
+        Ok(GetFilteredProofRequestsResponse { requests })
+    }
+
+    async fn subscribe_proof_requests(
+        &self,
+        _: GetFilteredProofRequestsRequest,
+    ) -> Result<BoxStream<'static, Result<ProofRequest, Status>>, Status> {
+        Err(Status::unimplemented("subscribe_proof_requests"))
+    }
+
+    async fn get_proof_request_details(
+        &self,
+        request: GetProofRequestDetailsRequest,
+    ) -> Result<GetProofRequestDetailsResponse, Status> {
+        let state = self.state.lock().unwrap();
+        let request = state.requests.get(&request.request_id).cloned();
+        Ok(GetProofRequestDetailsResponse { request })
+    }
+
+    async fn get_nonce(&self, request: GetNonceRequest) -> Result<GetNonceResponse, Status> {
+        let state = self.state.lock().unwrap();
+        let nonce = state.nonces.get(&request.address).copied().unwrap_or_default();
+        Ok(GetNonceResponse { nonce })
+    }
+
+    async fn get_owner(&self, request: GetOwnerRequest) -> Result<GetOwnerResponse, Status> {
+        let state = self.state.lock().unwrap();
+        Ok(GetOwnerResponse { owner: state.owner(&request.address) })
+    }
+
+    async fn get_balance(&self, request: GetBalanceRequest) -> Result<GetBalanceResponse, Status> {
+        let state = self.state.lock().unwrap();
+        let amount = state.balances.get(&request.address).cloned().unwrap_or_else(|| "0".into());
+        Ok(GetBalanceResponse { amount })
+    }
+
+    async fn get_prover_stake_balance(
+        &self,
+        request: GetProverStakeBalanceRequest,
+    ) -> Result<GetProverStakeBalanceResponse, Status> {
+        let state = self.state.lock().unwrap();
+        let amount = state.balances.get(&request.prover).cloned().unwrap_or_else(|| "0".into());
+        Ok(GetProverStakeBalanceResponse { amount })
+    }
+
+    async fn get_filtered_balance_logs(
+        &self,
+        _: GetFilteredBalanceLogsRequest,
+    ) -> Result<GetFilteredBalanceLogsResponse, Status> {
+        Err(Status::unimplemented("get_filtered_balance_logs"))
+    }
+
+    async fn get_filtered_bid_history(
+        &self,
+        _: GetFilteredBidHistoryRequest,
+    ) -> Result<GetFilteredBidHistoryResponse, Status> {
+        Err(Status::unimplemented("get_filtered_bid_history"))
+    }
+
+    async fn get_filtered_prover_stake_balance_logs(
+        &self,
+        _: GetFilteredProverStakeBalanceLogsRequest,
+    ) -> Result<GetFilteredProverStakeBalanceLogsResponse, Status> {
+        Err(Status::unimplemented("get_filtered_prover_stake_balance_logs"))
+    }
+
+    async fn get_filtered_withdrawal_receipts(
+        &self,
+        _: GetFilteredWithdrawalReceiptsRequest,
+    ) -> Result<GetFilteredWithdrawalReceiptsResponse, Status> {
+        Err(Status::unimplemented("get_filtered_withdrawal_receipts"))
+    }
+
+    async fn bid(&self, request: BidRequest) -> Result<BidResponse, Status> {
+        let body = request.body.ok_or_else(|| Status::invalid_argument("missing body"))?;
+        let mut state = self.state.lock().unwrap();
+        let signer = state.accept(&body, body.nonce, &request.signature)?;
+        let owner = state.owner(&signer);
+
+        let Some(proof_request) = state.requests.get(&body.request_id) else {
+            return Err(Status::not_found("request not found"));
+        };
+        if proof_request.fulfillment_status != FulfillmentStatus::Requested as i32 {
+            return Err(Status::failed_precondition("request is not open for bids"));
+        }
+
+        state.bidders.entry(body.request_id.clone()).or_default().push(owner);
+        state.bids.push(body);
+        Ok(BidResponse::default())
+    }
+
+    async fn fulfill_proof(
+        &self,
+        request: FulfillProofRequest,
+    ) -> Result<FulfillProofResponse, Status> {
+        let body = request.body.ok_or_else(|| Status::invalid_argument("missing body"))?;
+        let mut state = self.state.lock().unwrap();
+        if let Some(status) = state.fulfill_error.clone() {
+            return Err(status);
+        }
+        let signer = state.accept(&body, body.nonce, &request.signature)?;
+        let owner = state.owner(&signer);
+
+        let Some(proof_request) = state.requests.get_mut(&body.request_id) else {
+            return Err(Status::not_found("request not found"));
+        };
+        if proof_request.fulfiller.as_ref() != Some(&owner) {
+            return Err(Status::permission_denied("only the fulfiller can fulfill the request"));
+        }
+        proof_request.fulfillment_status = FulfillmentStatus::Fulfilled.into();
+
+        state.fulfillments.push(body);
+        Ok(FulfillProofResponse::default())
+    }
+
+    async fn fail_fulfillment(
+        &self,
+        request: FailFulfillmentRequest,
+    ) -> Result<FailFulfillmentResponse, Status> {
+        let body = request.body.ok_or_else(|| Status::invalid_argument("missing body"))?;
+        let mut state = self.state.lock().unwrap();
+        state.accept(&body, body.nonce, &request.signature)?;
+
+        if let Some(proof_request) = state.requests.get_mut(&body.request_id) {
+            proof_request.fulfillment_status = FulfillmentStatus::Unfulfillable.into();
+        }
+
+        state.failures.push(body);
+        Ok(FailFulfillmentResponse::default())
+    }
+
+    async fn request_proof(&self, _: RequestProofRequest) -> Result<RequestProofResponse, Status> {
+        Err(Status::unimplemented("request_proof"))
+    }
+
+    async fn get_transfer_params(
+        &self,
+        _: GetTransferParamsRequest,
+    ) -> Result<GetTransferParamsResponse, Status> {
+        Err(Status::unimplemented("get_transfer_params"))
+    }
+
+    async fn transfer(&self, _: TransferRequest) -> Result<TransferResponse, Status> {
+        Err(Status::unimplemented("transfer"))
+    }
+
+    async fn get_withdraw_params(
+        &self,
+        _: GetWithdrawParamsRequest,
+    ) -> Result<GetWithdrawParamsResponse, Status> {
+        Err(Status::unimplemented("get_withdraw_params"))
+    }
+
+    async fn withdraw(&self, _: WithdrawRequest) -> Result<WithdrawResponse, Status> {
+        Err(Status::unimplemented("withdraw"))
+    }
+
+    async fn get_delegation_params(
+        &self,
+        _: GetDelegationParamsRequest,
+    ) -> Result<GetDelegationParamsResponse, Status> {
+        Err(Status::unimplemented("get_delegation_params"))
+    }
+
+    async fn set_delegation(
+        &self,
+        _: SetDelegationRequest,
+    ) -> Result<SetDelegationResponse, Status> {
+        Err(Status::unimplemented("set_delegation"))
+    }
+
+    async fn set_account_name(
+        &self,
+        _: SetAccountNameRequest,
+    ) -> Result<SetAccountNameResponse, Status> {
+        Err(Status::unimplemented("set_account_name"))
+    }
+}
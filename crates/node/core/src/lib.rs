@@ -7,9 +7,19 @@
 #![allow(clippy::cast_sign_loss)]
 #![allow(clippy::cast_possible_truncation)]
 
+mod backends;
+mod events;
+#[cfg(test)]
+mod fake;
+mod notify;
 mod serial;
+mod supervisor;
 
+pub use backends::*;
+pub use events::*;
+pub use notify::*;
 pub use serial::*;
+pub use supervisor::*;
 
 use std::{
     sync::Arc,
@@ -17,12 +27,12 @@ use std::{
 };
 
 use alloy_signer_local::PrivateKeySigner;
-use anyhow::Result;
-use tokio::{sync::Mutex, time::sleep};
-use tonic::{async_trait, transport::Channel};
+use anyhow::{anyhow, Result};
+use tokio::sync::Mutex;
+use tonic::async_trait;
 
 use sp1_sdk::SP1_CIRCUIT_VERSION;
-use spn_network_types::prover_network_client::ProverNetworkClient;
+use spn_rpc::ProverNetworkApi;
 
 /// The version identifier for SP1 used on the network.
 pub const SP1_NETWORK_VERSION: &str = const_str::concat!("sp1-", SP1_CIRCUIT_VERSION);
@@ -44,6 +54,8 @@ pub struct Node<C, B, P, M> {
     pub prover: Arc<P>,
     /// The monitor for the node.
     pub monitor: Arc<M>,
+    /// The configuration of the task loops of the node.
+    pub config: NodeConfig,
 }
 
 impl<C, B, P, M> Node<C, B, P, M> {
@@ -54,8 +66,16 @@ impl<C, B, P, M> Node<C, B, P, M> {
             bidder: Arc::new(bidder),
             prover: Arc::new(prover),
             monitor: Arc::new(metrics),
+            config: NodeConfig::default(),
         }
     }
+
+    /// Set the configuration of the task loops of the node.
+    #[must_use]
+    pub fn with_config(mut self, config: NodeConfig) -> Self {
+        self.config = config;
+        self
+    }
 }
 
 /// The standard context for a node.
@@ -63,12 +83,20 @@ impl<C, B, P, M> Node<C, B, P, M> {
 /// This is usually used to access shared state that can be extended across the node, bidder, and
 /// the prover.
 pub trait NodeContext: Send + Sync + 'static {
+    /// The type of the network client for the node.
+    type Network: ProverNetworkApi;
     /// The network client for the node.
-    fn network(&self) -> &ProverNetworkClient<Channel>;
+    fn network(&self) -> &Self::Network;
     /// The signer for the node.
     fn signer(&self) -> &PrivateKeySigner;
     /// The metrics for the node.
     fn metrics(&self) -> &NodeMetrics;
+    /// The notifier for the node.
+    fn notifier(&self) -> &Notifier;
+    /// The event bus for the node.
+    ///
+    /// Bidders and provers emit [`NodeEvent`]s on this bus, and anything else can subscribe to it.
+    fn events(&self) -> &EventBus;
 }
 
 /// The bidder for a node.
@@ -113,57 +141,59 @@ pub struct NodeMetrics {
     pub total_cycles: Mutex<u64>,
     /// The total time the node has spent proving.
     pub total_proving_time: Mutex<Duration>,
+    /// The total number of failed iterations of the node's task loops.
+    pub loop_errors: Mutex<u64>,
 }
 
 impl<C: NodeContext, B: NodeBidder<C>, P: NodeProver<C>, M: NodeMonitor<C>> Node<C, B, P, M> {
     /// Run the node.
     pub async fn run(self) -> Result<()> {
+        // Turn node events into notifications.
+        if self.ctx.notifier().is_enabled() {
+            self.ctx.notifier().listen(self.ctx.events().subscribe());
+        }
+
+        // Let the operator know that the node (re)started.
+        self.ctx
+            .notifier()
+            .notify(Notification::NodeStarted { version: SP1_NETWORK_VERSION.to_string() })
+            .await;
+
         // Run the bid and prove task.
         let ctx = self.ctx.clone();
         let bidder = self.bidder.clone();
         let prover = self.prover.clone();
+        let supervisor =
+            self.config.supervisor("bid-and-prove", self.config.bid_and_prove_interval);
         let bid_and_prove_task = tokio::spawn(async move {
-            let result: Result<()> = async {
-                loop {
+            supervisor
+                .run(&*ctx, || async {
                     let bid_future = bidder.bid(&ctx);
                     let prove_future = prover.prove(&ctx);
-                    let _ = tokio::join!(bid_future, prove_future);
-
-                    sleep(Duration::from_secs(3)).await;
-                }
-            }
-            .await;
-            result
+                    match tokio::join!(bid_future, prove_future) {
+                        (Ok(()), Ok(())) => Ok(()),
+                        (Err(e), Ok(())) => Err(e.context("failed to bid")),
+                        (Ok(()), Err(e)) => Err(e.context("failed to prove")),
+                        (Err(bid), Err(prove)) => {
+                            Err(anyhow!("failed to bid: {bid:?}; failed to prove: {prove:?}"))
+                        }
+                    }
+                })
+                .await
         });
 
         // Run the system monitor task.
         let ctx = self.ctx.clone();
         let monitor = self.monitor.clone();
+        let supervisor = self.config.supervisor("monitor", self.config.monitor_interval);
         let monitor_task = tokio::spawn(async move {
-            let result: Result<()> = async {
-                loop {
-                    monitor.record(&ctx).await?;
-                    sleep(Duration::from_secs(30)).await;
-                }
-            }
-            .await;
-            result
+            supervisor.run(&*ctx, || async { monitor.record(&ctx).await }).await
         });
 
         // Wait until one of the tasks fails.
         tokio::select! {
-            result = bid_and_prove_task => {
-                if let Err(e) = result {
-                    return Err(e.into());
-                }
-            },
-            result = monitor_task => {
-                if let Err(e) = result {
-                    return Err(e.into());
-                }
-            },
+            result = bid_and_prove_task => result?,
+            result = monitor_task => result?,
         }
-
-        Ok(())
     }
 }
@@ -0,0 +1,557 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    process::Stdio,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, Context, Result};
+use serde::Serialize;
+use spn_utils::time_now;
+use tokio::{
+    io::AsyncWriteExt,
+    process::Command,
+    sync::{broadcast, broadcast::error::RecvError, Mutex},
+};
+use tonic::async_trait;
+use tracing::{debug, warn};
+
+use crate::{EmittedEvent, NodeEvent};
+
+/// The default window during which duplicate notifications are suppressed.
+pub const DEFAULT_NOTIFY_DEDUP_WINDOW: Duration = Duration::from_secs(600);
+
+/// The default maximum number of notifications sent per [`DEFAULT_NOTIFY_RATE_WINDOW`].
+pub const DEFAULT_NOTIFY_RATE_LIMIT: usize = 10;
+
+/// The default window over which the notification rate limit is applied.
+pub const DEFAULT_NOTIFY_RATE_WINDOW: Duration = Duration::from_secs(60);
+
+/// The maximum time a notification sink is given to deliver a notification.
+const NOTIFY_SINK_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// An event that an operator should be notified about.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Notification {
+    /// The node won the auction for a request.
+    BidWon {
+        /// The hex-encoded request id.
+        request_id: String,
+    },
+    /// The node fulfilled a request.
+    ProofFulfilled {
+        /// The hex-encoded request id.
+        request_id: String,
+    },
+    /// The node failed to prove a request.
+    ProofFailed {
+        /// The hex-encoded request id.
+        request_id: String,
+        /// A description of why the proof failed.
+        cause: String,
+    },
+    /// A request assigned to the node was marked as unexecutable.
+    RequestUnexecutable {
+        /// The hex-encoded request id.
+        request_id: String,
+    },
+    /// An RPC kept failing after all retries were exhausted.
+    RpcFailure {
+        /// The name of the operation that failed.
+        operation: String,
+        /// The last error returned by the operation.
+        error: String,
+    },
+    /// The balance of the node's owner dropped below the configured threshold.
+    LowBalance {
+        /// The current balance.
+        balance: String,
+        /// The configured threshold.
+        threshold: String,
+    },
+    /// The stake of the node's prover dropped below the configured threshold.
+    LowStake {
+        /// The current stake.
+        stake: String,
+        /// The configured threshold.
+        threshold: String,
+    },
+    /// The node (re)started.
+    NodeStarted {
+        /// The SP1 version the node is proving for.
+        version: String,
+    },
+}
+
+/// How urgently a [`Notification`] should be looked at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationSeverity {
+    /// Informational, no action needed.
+    Info,
+    /// Something went wrong, but the node keeps running.
+    Warning,
+    /// The node needs operator attention.
+    Critical,
+}
+
+impl Notification {
+    /// The notification for a [`NodeEvent`], if the event is worth notifying about.
+    #[must_use]
+    pub fn from_event(event: &NodeEvent) -> Option<Self> {
+        let request_id = hex::encode(event.request_id());
+        match event {
+            NodeEvent::Assigned { .. } => Some(Self::BidWon { request_id }),
+            NodeEvent::Fulfilled { .. } => Some(Self::ProofFulfilled { request_id }),
+            NodeEvent::Failed { cause, .. } => {
+                Some(Self::ProofFailed { request_id, cause: cause.clone() })
+            }
+            NodeEvent::Cancelled { .. } => Some(Self::RequestUnexecutable { request_id }),
+            _ => None,
+        }
+    }
+
+    /// The severity of the notification.
+    #[must_use]
+    pub fn severity(&self) -> NotificationSeverity {
+        match self {
+            Self::BidWon { .. } | Self::ProofFulfilled { .. } | Self::NodeStarted { .. } => {
+                NotificationSeverity::Info
+            }
+            Self::ProofFailed { .. } | Self::RequestUnexecutable { .. } => {
+                NotificationSeverity::Warning
+            }
+            Self::RpcFailure { .. } | Self::LowBalance { .. } | Self::LowStake { .. } => {
+                NotificationSeverity::Critical
+            }
+        }
+    }
+
+    /// The key used to deduplicate notifications.
+    ///
+    /// Two notifications with the same key that are sent within the dedup window are considered
+    /// duplicates. Balance and stake alerts deliberately ignore the amounts so that a slowly
+    /// draining balance doesn't produce a new alert on every check.
+    #[must_use]
+    pub fn dedup_key(&self) -> String {
+        match self {
+            Self::BidWon { request_id } => format!("bid_won:{request_id}"),
+            Self::ProofFulfilled { request_id } => format!("proof_fulfilled:{request_id}"),
+            Self::ProofFailed { request_id, .. } => format!("proof_failed:{request_id}"),
+            Self::RequestUnexecutable { request_id } => format!("unexecutable:{request_id}"),
+            Self::RpcFailure { operation, .. } => format!("rpc_failure:{operation}"),
+            Self::LowBalance { .. } => "low_balance".to_string(),
+            Self::LowStake { .. } => "low_stake".to_string(),
+            Self::NodeStarted { .. } => "node_started".to_string(),
+        }
+    }
+
+    /// A short human readable summary of the notification.
+    #[must_use]
+    pub fn summary(&self) -> String {
+        match self {
+            Self::BidWon { request_id } => format!("Won the auction for request {request_id}."),
+            Self::ProofFulfilled { request_id } => format!("Fulfilled request {request_id}."),
+            Self::ProofFailed { request_id, cause } => {
+                format!("Failed to prove request {request_id}: {cause}.")
+            }
+            Self::RequestUnexecutable { request_id } => {
+                format!("Request {request_id} was marked as unexecutable.")
+            }
+            Self::RpcFailure { operation, error } => {
+                format!("RPC {operation} kept failing after retries: {error}.")
+            }
+            Self::LowBalance { balance, threshold } => {
+                format!("Owner balance {balance} is below the threshold of {threshold}.")
+            }
+            Self::LowStake { stake, threshold } => {
+                format!("Prover stake {stake} is below the threshold of {threshold}.")
+            }
+            Self::NodeStarted { version } => format!("Node started proving for {version}."),
+        }
+    }
+}
+
+/// The JSON payload delivered to [`WebhookSink`] and [`CommandSink`].
+#[derive(Debug, Serialize)]
+struct NotificationPayload<'a> {
+    /// The Unix timestamp at which the notification was sent.
+    timestamp: u64,
+    /// The severity of the notification.
+    severity: NotificationSeverity,
+    /// A human readable summary of the notification.
+    summary: String,
+    /// The notification itself.
+    #[serde(flatten)]
+    notification: &'a Notification,
+}
+
+impl<'a> NotificationPayload<'a> {
+    fn new(notification: &'a Notification) -> Self {
+        Self {
+            timestamp: time_now(),
+            severity: notification.severity(),
+            summary: notification.summary(),
+            notification,
+        }
+    }
+}
+
+/// A destination that notifications are delivered to.
+#[async_trait]
+pub trait NotificationSink: Send + Sync + 'static {
+    /// Deliver a notification.
+    async fn send(&self, notification: &Notification) -> Result<()>;
+}
+
+/// A sink that posts each notification as a JSON object to a webhook.
+#[derive(Debug, Clone)]
+pub struct WebhookSink {
+    /// The URL of the webhook.
+    url: String,
+    /// The HTTP client used to post notifications.
+    client: reqwest::Client,
+}
+
+impl WebhookSink {
+    /// Create a new [`WebhookSink`].
+    #[must_use]
+    pub fn new(url: impl Into<String>) -> Self {
+        Self { url: url.into(), client: reqwest::Client::new() }
+    }
+}
+
+#[async_trait]
+impl NotificationSink for WebhookSink {
+    async fn send(&self, notification: &Notification) -> Result<()> {
+        let body = serde_json::to_vec(&NotificationPayload::new(notification))?;
+        post_json(&self.client, &self.url, body).await
+    }
+}
+
+/// A sink that posts each notification to a Slack-compatible incoming webhook.
+#[derive(Debug, Clone)]
+pub struct SlackSink {
+    /// The URL of the incoming webhook.
+    url: String,
+    /// The HTTP client used to post notifications.
+    client: reqwest::Client,
+}
+
+impl SlackSink {
+    /// Create a new [`SlackSink`].
+    #[must_use]
+    pub fn new(url: impl Into<String>) -> Self {
+        Self { url: url.into(), client: reqwest::Client::new() }
+    }
+}
+
+#[async_trait]
+impl NotificationSink for SlackSink {
+    async fn send(&self, notification: &Notification) -> Result<()> {
+        let emoji = match notification.severity() {
+            NotificationSeverity::Info => ":white_check_mark:",
+            NotificationSeverity::Warning => ":warning:",
+            NotificationSeverity::Critical => ":rotating_light:",
+        };
+        let body = serde_json::to_vec(&serde_json::json!({
+            "text": format!("{emoji} *[spn-node]* {}", notification.summary()),
+        }))?;
+        post_json(&self.client, &self.url, body).await
+    }
+}
+
+/// A sink that runs a shell command for each notification.
+///
+/// The JSON payload is written to the command's stdin, and the kind, severity, and summary are
+/// also exposed through the `SPN_NOTIFICATION_KIND`, `SPN_NOTIFICATION_SEVERITY`, and
+/// `SPN_NOTIFICATION_SUMMARY` environment variables.
+#[derive(Debug, Clone)]
+pub struct CommandSink {
+    /// The shell command to run.
+    command: String,
+}
+
+impl CommandSink {
+    /// Create a new [`CommandSink`].
+    #[must_use]
+    pub fn new(command: impl Into<String>) -> Self {
+        Self { command: command.into() }
+    }
+}
+
+#[async_trait]
+impl NotificationSink for CommandSink {
+    async fn send(&self, notification: &Notification) -> Result<()> {
+        let payload = NotificationPayload::new(notification);
+        let body = serde_json::to_vec(&payload)?;
+        let kind = notification.dedup_key();
+        let kind = kind.split(':').next().unwrap_or_default();
+        let severity = serde_json::to_value(payload.severity)?;
+
+        let mut child = Command::new("sh")
+            .arg("-c")
+            .arg(&self.command)
+            .env("SPN_NOTIFICATION_KIND", kind)
+            .env("SPN_NOTIFICATION_SEVERITY", severity.as_str().unwrap_or_default())
+            .env("SPN_NOTIFICATION_SUMMARY", &payload.summary)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()
+            .context("failed to spawn notification command")?;
+
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(&body).await.context("failed to write notification to command")?;
+        }
+
+        let status = child.wait().await.context("failed to wait for notification command")?;
+        if !status.success() {
+            return Err(anyhow!("notification command exited with {status}"));
+        }
+        Ok(())
+    }
+}
+
+/// Posts a JSON body to a URL and checks the response status.
+async fn post_json(client: &reqwest::Client, url: &str, body: Vec<u8>) -> Result<()> {
+    let response = client
+        .post(url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .body(body)
+        .timeout(NOTIFY_SINK_TIMEOUT)
+        .send()
+        .await
+        .context("failed to post notification")?;
+    if !response.status().is_success() {
+        return Err(anyhow!("webhook responded with status {}", response.status()));
+    }
+    Ok(())
+}
+
+/// The configuration for a [`Notifier`].
+#[derive(Debug, Clone, Copy)]
+pub struct NotifierConfig {
+    /// The window during which notifications with the same dedup key are suppressed.
+    pub dedup_window: Duration,
+    /// The maximum number of notifications sent per `rate_window`.
+    pub rate_limit: usize,
+    /// The window over which `rate_limit` is applied.
+    pub rate_window: Duration,
+}
+
+impl Default for NotifierConfig {
+    fn default() -> Self {
+        Self {
+            dedup_window: DEFAULT_NOTIFY_DEDUP_WINDOW,
+            rate_limit: DEFAULT_NOTIFY_RATE_LIMIT,
+            rate_window: DEFAULT_NOTIFY_RATE_WINDOW,
+        }
+    }
+}
+
+/// The mutable state of a [`Notifier`].
+#[derive(Debug, Default)]
+struct NotifierState {
+    /// The last time a notification was sent for each dedup key.
+    last_sent: HashMap<String, Instant>,
+    /// The times at which recent notifications were sent, oldest first.
+    recent: VecDeque<Instant>,
+    /// The number of notifications dropped by the rate limiter since the last one was sent.
+    dropped: u64,
+}
+
+/// Delivers [`Notification`]s to a set of sinks.
+///
+/// Notifications are deduplicated by [`Notification::dedup_key`] and rate limited, so that a
+/// flapping failure doesn't spam the channel. Delivery happens in the background and never blocks
+/// the caller.
+#[derive(Clone)]
+pub struct Notifier {
+    /// The sinks that notifications are delivered to.
+    sinks: Vec<Arc<dyn NotificationSink>>,
+    /// The configuration for the notifier.
+    config: NotifierConfig,
+    /// The dedup and rate limiting state.
+    state: Arc<Mutex<NotifierState>>,
+}
+
+impl std::fmt::Debug for Notifier {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Notifier")
+            .field("sinks", &self.sinks.len())
+            .field("config", &self.config)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Default for Notifier {
+    fn default() -> Self {
+        Self::new(NotifierConfig::default())
+    }
+}
+
+impl Notifier {
+    /// Create a new [`Notifier`] without any sinks.
+    #[must_use]
+    pub fn new(config: NotifierConfig) -> Self {
+        Self { sinks: Vec::new(), config, state: Arc::new(Mutex::new(NotifierState::default())) }
+    }
+
+    /// Add a sink to the notifier.
+    #[must_use]
+    pub fn with_sink(mut self, sink: impl NotificationSink) -> Self {
+        self.sinks.push(Arc::new(sink));
+        self
+    }
+
+    /// Whether the notifier has any sinks configured.
+    #[must_use]
+    pub fn is_enabled(&self) -> bool {
+        !self.sinks.is_empty()
+    }
+
+    /// Send a notification to all sinks, unless it is a duplicate or the rate limit is exceeded.
+    pub async fn notify(&self, notification: Notification) {
+        if !self.is_enabled() {
+            return;
+        }
+
+        let key = notification.dedup_key();
+        let Some(dropped) = self.admit(&key, Instant::now()).await else {
+            debug!(key = %key, "suppressed notification");
+            return;
+        };
+        if dropped > 0 {
+            warn!(dropped = %dropped, "dropped notifications due to rate limiting");
+        }
+
+        let notification = Arc::new(notification);
+        for sink in &self.sinks {
+            let sink = sink.clone();
+            let notification = notification.clone();
+            tokio::spawn(async move {
+                match tokio::time::timeout(NOTIFY_SINK_TIMEOUT, sink.send(&notification)).await {
+                    Ok(Ok(())) => {}
+                    Ok(Err(e)) => {
+                        warn!(key = %notification.dedup_key(), "failed to deliver notification: {:?}", e);
+                    }
+                    Err(_) => {
+                        warn!(key = %notification.dedup_key(), "timed out delivering notification");
+                    }
+                }
+            });
+        }
+    }
+
+    /// Spawn a task that turns the node events received on `events` into notifications.
+    pub fn listen(&self, mut events: broadcast::Receiver<EmittedEvent>) {
+        let notifier = self.clone();
+        tokio::spawn(async move {
+            loop {
+                match events.recv().await {
+                    Ok(emitted) => {
+                        if let Some(notification) = Notification::from_event(&emitted.event) {
+                            notifier.notify(notification).await;
+                        }
+                    }
+                    Err(RecvError::Lagged(skipped)) => {
+                        warn!(skipped = %skipped, "notifier fell behind the node event bus");
+                    }
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        });
+    }
+
+    /// Decide whether a notification with the given key may be sent at `now`.
+    ///
+    /// Returns the number of notifications dropped by the rate limiter since the last admitted
+    /// one, or `None` if this notification should be suppressed.
+    async fn admit(&self, key: &str, now: Instant) -> Option<u64> {
+        let mut state = self.state.lock().await;
+
+        // Suppress duplicates within the dedup window.
+        if let Some(last) = state.last_sent.get(key) {
+            if now.duration_since(*last) < self.config.dedup_window {
+                return None;
+            }
+        }
+
+        // Enforce the rate limit over a sliding window.
+        while let Some(oldest) = state.recent.front() {
+            if now.duration_since(*oldest) < self.config.rate_window {
+                break;
+            }
+            state.recent.pop_front();
+        }
+        if state.recent.len() >= self.config.rate_limit {
+            state.dropped += 1;
+            return None;
+        }
+
+        // Forget dedup keys that are outside the window so the map doesn't grow forever.
+        let dedup_window = self.config.dedup_window;
+        state.last_sent.retain(|_, last| now.duration_since(*last) < dedup_window);
+
+        state.last_sent.insert(key.to_string(), now);
+        state.recent.push_back(now);
+        Some(std::mem::take(&mut state.dropped))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct NoopSink;
+
+    #[async_trait]
+    impl NotificationSink for NoopSink {
+        async fn send(&self, _: &Notification) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    fn notifier(rate_limit: usize) -> Notifier {
+        Notifier::new(NotifierConfig {
+            dedup_window: Duration::from_secs(60),
+            rate_limit,
+            rate_window: Duration::from_secs(10),
+        })
+        .with_sink(NoopSink)
+    }
+
+    #[tokio::test]
+    async fn test_dedup_suppresses_within_window() {
+        let notifier = notifier(100);
+        let start = Instant::now();
+
+        assert_eq!(notifier.admit("a", start).await, Some(0));
+        assert_eq!(notifier.admit("a", start + Duration::from_secs(30)).await, None);
+        assert_eq!(notifier.admit("b", start + Duration::from_secs(30)).await, Some(0));
+        assert_eq!(notifier.admit("a", start + Duration::from_secs(61)).await, Some(0));
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_drops_and_reports() {
+        let notifier = notifier(2);
+        let start = Instant::now();
+
+        assert_eq!(notifier.admit("a", start).await, Some(0));
+        assert_eq!(notifier.admit("b", start).await, Some(0));
+        assert_eq!(notifier.admit("c", start).await, None);
+        assert_eq!(notifier.admit("d", start).await, None);
+
+        // Once the window has passed, the dropped count is reported with the next notification.
+        assert_eq!(notifier.admit("e", start + Duration::from_secs(11)).await, Some(2));
+    }
+
+    #[test]
+    fn test_dedup_key_ignores_amounts() {
+        let a = Notification::LowBalance { balance: "1".into(), threshold: "10".into() };
+        let b = Notification::LowBalance { balance: "2".into(), threshold: "10".into() };
+        assert_eq!(a.dedup_key(), b.dedup_key());
+    }
+}
@@ -3,47 +3,60 @@ use std::{
     env,
     panic::{self, AssertUnwindSafe},
     sync::{atomic, Arc},
-    time::{Duration, Instant, SystemTime},
+    time::{Duration, SystemTime},
 };
 
 use alloy_primitives::{Address, U256};
 use alloy_signer_local::PrivateKeySigner;
-use anyhow::{Context, Result};
+use anyhow::Result;
 use chrono::{self, DateTime};
+use futures::{future, stream, StreamExt, TryStreamExt};
 use nvml_wrapper::Nvml;
 use sp1_sdk::{EnvProver, SP1ProofMode, SP1Stdin};
-use spn_artifacts::{extract_artifact_name, Artifact};
+use spn_artifacts::{
+    attach_proof, extract_artifact_name, Artifact, ArtifactError, ArtifactSecretKey, ProofPayload,
+    ProofUploadPolicy,
+};
 use spn_network_types::{
-    prover_network_client::ProverNetworkClient, BidRequest, BidRequestBody, ExecutionStatus,
-    FailFulfillmentRequest, FailFulfillmentRequestBody, FulfillProofRequest,
-    FulfillProofRequestBody, FulfillmentStatus, GetFilteredProofRequestsRequest, GetNonceRequest,
-    GetProofRequestDetailsRequest, MessageFormat, ProofMode, Signable, TransactionVariant,
+    prover_network_client::ProverNetworkClient, ExecutionStatus, FulfillmentStatus,
+    GetFilteredProofRequestsRequest, GetProofRequestDetailsRequest, ProofMode, ProofRequest,
+};
+use spn_rpc::{
+    fetch_balance, fetch_owner, fetch_stake_balance, stream_proof_requests, NetworkClient,
+    PageConfig, ProverNetworkApi, RetryableRpc,
 };
-use spn_rpc::{fetch_owner, RetryableRpc};
 use spn_utils::{time_now, SPN_MAINNET_V1_DOMAIN};
 use sysinfo::{CpuExt, System, SystemExt};
 use tokio::sync::Mutex;
 use tonic::{async_trait, transport::Channel};
 use tracing::{error, info, warn};
 
-use crate::{NodeBidder, NodeContext, NodeMetrics, NodeMonitor, NodeProver, SP1_NETWORK_VERSION};
+use crate::{
+    BackendProof, CancelToken, EventBus, NodeBidder, NodeContext, NodeEvent, NodeMetrics,
+    NodeMonitor, NodeProver, Notification, Notifier, ProverBackend, ProverRegistry,
+    SP1_NETWORK_VERSION,
+};
 
 /// A context that implements [`NodeContext`] for a serial node.
 ///
 /// This context is compatible with both [`SerialBidder`] and [`SerialProver`].
 #[derive(Debug)]
-pub struct SerialContext {
+pub struct SerialContext<N = ProverNetworkClient<Channel>> {
     /// The network client for the node.
-    pub network: ProverNetworkClient<Channel>,
+    pub network: N,
     /// The signer for the node.
     pub signer: PrivateKeySigner,
     /// The metrics for the node.
     pub metrics: NodeMetrics,
+    /// The notifier for the node.
+    pub notifier: Notifier,
+    /// The event bus for the node.
+    pub events: EventBus,
 }
 
-impl SerialContext {
+impl<N> SerialContext<N> {
     /// Create a new [`SerialContext`].
-    pub fn new(network: ProverNetworkClient<Channel>, signer: PrivateKeySigner) -> Self {
+    pub fn new(network: N, signer: PrivateKeySigner) -> Self {
         Self {
             network,
             signer,
@@ -52,13 +65,25 @@ impl SerialContext {
                 online_since: SystemTime::now(),
                 total_cycles: Mutex::new(0),
                 total_proving_time: Mutex::new(Duration::from_secs(0)),
+                loop_errors: Mutex::new(0),
             },
+            notifier: Notifier::default(),
+            events: EventBus::default(),
         }
     }
+
+    /// Set the notifier used to alert the operator about node events.
+    #[must_use]
+    pub fn with_notifier(mut self, notifier: Notifier) -> Self {
+        self.notifier = notifier;
+        self
+    }
 }
 
-impl NodeContext for SerialContext {
-    fn network(&self) -> &ProverNetworkClient<Channel> {
+impl<N: ProverNetworkApi> NodeContext for SerialContext<N> {
+    type Network = N;
+
+    fn network(&self) -> &N {
         &self.network
     }
 
@@ -69,6 +94,14 @@ impl NodeContext for SerialContext {
     fn metrics(&self) -> &NodeMetrics {
         &self.metrics
     }
+
+    fn notifier(&self) -> &Notifier {
+        &self.notifier
+    }
+
+    fn events(&self) -> &EventBus {
+        &self.events
+    }
 }
 
 /// A serial bidder.
@@ -84,13 +117,22 @@ pub struct SerialBidder {
     pub throughput: f64,
     /// The prover we are bidding on behalf of.
     pub prover: Address,
+    /// The SP1 versions of the requests we bid on.
+    pub versions: Vec<String>,
 }
 
 impl SerialBidder {
-    /// Create a new [`SerialBidder`].
+    /// Create a new [`SerialBidder`] for the requests of [`SP1_NETWORK_VERSION`].
     #[must_use]
     pub fn new(bid: U256, throughput: f64, prover: Address) -> Self {
-        Self { bid, throughput, prover }
+        Self { bid, throughput, prover, versions: vec![SP1_NETWORK_VERSION.to_string()] }
+    }
+
+    /// Bid on the requests of `versions`, such as the [`SerialProver::versions`] of the prover.
+    #[must_use]
+    pub fn with_versions(mut self, versions: Vec<String>) -> Self {
+        self.versions = versions;
+        self
     }
 }
 
@@ -106,20 +148,18 @@ impl<C: NodeContext> NodeBidder<C> for SerialBidder {
         info!(owner = %hex::encode(&owner), signer = %hex::encode(&signer), "{SERIAL_BIDDER_TAG} Fetched owner.");
 
         // Fetch for assigned requests.
-        let assigned_requests = ctx
-            .network()
-            .clone()
-            .get_filtered_proof_requests(GetFilteredProofRequestsRequest {
-                version: Some(SP1_NETWORK_VERSION.to_string()),
+        let assigned_requests = get_requests_for_versions(
+            ctx.network(),
+            &self.versions,
+            GetFilteredProofRequestsRequest {
                 fulfillment_status: Some(FulfillmentStatus::Assigned.into()),
                 minimum_deadline: Some(time_now()),
                 fulfiller: Some(owner.clone()),
                 limit: Some(1),
                 ..Default::default()
-            })
-            .await?
-            .into_inner()
-            .requests;
+            },
+        )
+        .await?;
         info!(count = %assigned_requests.len(), "{SERIAL_BIDDER_TAG} Fetched assigned proof requests.");
 
         if !assigned_requests.is_empty() {
@@ -130,20 +170,18 @@ impl<C: NodeContext> NodeBidder<C> for SerialBidder {
         }
 
         // Fetch for unassigned requests.
-        let unassigned_requests = ctx
-            .network()
-            .clone()
-            .get_filtered_proof_requests(GetFilteredProofRequestsRequest {
-                version: Some(SP1_NETWORK_VERSION.to_string()),
+        let unassigned_requests = get_requests_for_versions(
+            ctx.network(),
+            &self.versions,
+            GetFilteredProofRequestsRequest {
                 fulfillment_status: Some(FulfillmentStatus::Requested.into()),
                 minimum_deadline: Some(time_now()),
                 limit: Some(1),
                 not_bid_by: Some(owner.clone()),
                 ..Default::default()
-            })
-            .await?
-            .into_inner()
-            .requests;
+            },
+        )
+        .await?;
         info!(count = %unassigned_requests.len(), "{SERIAL_BIDDER_TAG} Fetched unassigned proof requests.");
 
         // If there are no open requests, return.
@@ -160,108 +198,114 @@ impl<C: NodeContext> NodeBidder<C> for SerialBidder {
             return Ok(());
         }
 
-        let request = unassigned_requests.first().unwrap();
-        let request_id = hex::encode(&request.request_id);
-        let address = ctx.signer().address().to_vec();
-
         info!("{SERIAL_BIDDER_TAG} Found one unassigned request to bid on.");
-        ctx.network()
-            .clone()
+        let result = self.bid_on_request(ctx, &unassigned_requests[0].request_id).await;
+
+        if let Err(e) = &result {
+            ctx.notifier()
+                .notify(Notification::RpcFailure {
+                    operation: "Bid".to_string(),
+                    error: e.to_string(),
+                })
+                .await;
+        }
+
+        result
+    }
+}
+
+impl SerialBidder {
+    /// Bid on the request `request_id` if there is enough time left to prove it.
+    async fn bid_on_request<C: NodeContext>(&self, ctx: &C, request_id: &[u8]) -> Result<()> {
+        const SERIAL_BIDDER_TAG: &str = "\x1b[34m[SerialBidder]\x1b[0m";
+
+        // Get request details to access the deadline.
+        let request = ctx
+            .network()
             .with_retry(
                 || async {
-                    // Get the nonce.
-                    let nonce = ctx
-                        .network()
-                        .clone()
-                        .get_nonce(GetNonceRequest { address: address.clone() })
-                        .await?
-                        .into_inner()
-                        .nonce;
-                    info!(nonce = %nonce, "{SERIAL_BIDDER_TAG} Fetched account nonce.");
-
-                    // Get request details to access the deadline.
-                    let request = ctx
-                        .network()
-                        .clone()
+                    ctx.network()
                         .get_proof_request_details(GetProofRequestDetailsRequest {
-                            request_id: hex::decode(request_id.clone())?,
+                            request_id: request_id.to_vec(),
                         })
                         .await?
-                        .into_inner()
                         .request
-                        .ok_or_else(|| anyhow::anyhow!("request details not found"))?;
-
-                    // Log the request details in a structured format.
-                    let current_time = time_now();
-                    let remaining_time = request.deadline.saturating_sub(current_time);
-                    let required_time = ((request.gas_limit as f64) / self.throughput) as u64;
-
-                    info!(
-                        request_id = %request_id,
-                        vk_hash = %hex::encode(request.vk_hash),
-                        version = %request.version,
-                        mode = %request.mode,
-                        strategy = %request.strategy,
-                        requester = %hex::encode(request.requester),
-                        tx_hash = %hex::encode(request.tx_hash),
-                        program_uri = %request.program_public_uri,
-                        stdin_uri = %request.stdin_public_uri,
-                        gas_limit = %request.gas_limit,
-                        cycle_limit = %request.cycle_limit,
-                        created_at = %request.created_at,
-                        created_at_utc = %DateTime::from_timestamp(i64::try_from(request.created_at).unwrap_or_default(), 0).unwrap_or_default(),
-                        deadline = %request.deadline,
-                        deadline_utc = %DateTime::from_timestamp(i64::try_from(request.deadline).unwrap_or_default(), 0).unwrap_or_default(),
-                        remaining_time = %remaining_time,
-                        remaining_time_minutes = %remaining_time / 60,
-                        remaining_time_seconds = %remaining_time % 60,
-                        required_time = %required_time,
-                        required_time_minutes = %required_time / 60,
-                        required_time_seconds = %required_time % 60,
-                        "{SERIAL_BIDDER_TAG} Fetched request details."
-                    );
-
-                    if remaining_time < required_time {
-                        info!(request_id = %request_id, remaining_time = %remaining_time, required_time = %required_time, "{SERIAL_BIDDER_TAG} Not enough time to bid on request. Skipping...");
-                        return Ok(());
-                    }
-
-                    // Bid on the request.
-                    info!(request_id = %request_id, bid = %self.bid, "{SERIAL_BIDDER_TAG} Submitting a bid for request");
-                    let body = BidRequestBody {
-                        nonce,
-                        request_id: hex::decode(request_id.clone())
-                            .context("failed to decode request_id")?,
-                        amount: self.bid.to_string(),
-                        prover: self.prover.to_vec(),
-                        domain: SPN_MAINNET_V1_DOMAIN.to_vec(),
-                        variant: TransactionVariant::BidVariant as i32,
-                    };
-                    let bid_request = BidRequest {
-                        format: MessageFormat::Binary.into(),
-                        signature: body.sign(&ctx.signer()).into(),
-                        body: Some(body),
-                    };
-                    ctx.network().clone().bid(bid_request).await?;
-
-                    Ok(())
+                        .ok_or_else(|| anyhow::anyhow!("request details not found"))
                 },
-                "Bid",
+                "get proof request details",
             )
             .await?;
 
+        // Log the request details in a structured format.
+        let current_time = time_now();
+        let remaining_time = request.deadline.saturating_sub(current_time);
+        let required_time = ((request.gas_limit as f64) / self.throughput) as u64;
+
+        info!(
+            request_id = %hex::encode(request_id),
+            vk_hash = %hex::encode(request.vk_hash),
+            version = %request.version,
+            mode = %request.mode,
+            strategy = %request.strategy,
+            requester = %hex::encode(request.requester),
+            tx_hash = %hex::encode(request.tx_hash),
+            program_uri = %request.program_public_uri,
+            stdin_uri = %request.stdin_public_uri,
+            gas_limit = %request.gas_limit,
+            cycle_limit = %request.cycle_limit,
+            created_at = %request.created_at,
+            created_at_utc = %DateTime::from_timestamp(i64::try_from(request.created_at).unwrap_or_default(), 0).unwrap_or_default(),
+            deadline = %request.deadline,
+            deadline_utc = %DateTime::from_timestamp(i64::try_from(request.deadline).unwrap_or_default(), 0).unwrap_or_default(),
+            remaining_time = %remaining_time,
+            remaining_time_minutes = %remaining_time / 60,
+            remaining_time_seconds = %remaining_time % 60,
+            required_time = %required_time,
+            required_time_minutes = %required_time / 60,
+            required_time_seconds = %required_time % 60,
+            "{SERIAL_BIDDER_TAG} Fetched request details."
+        );
+
+        ctx.events().emit(NodeEvent::RequestSeen {
+            request_id: request.request_id.clone(),
+            gas_limit: request.gas_limit,
+            deadline: request.deadline,
+        });
+
+        if remaining_time < required_time {
+            info!(request_id = %hex::encode(request_id), remaining_time = %remaining_time, required_time = %required_time, "{SERIAL_BIDDER_TAG} Not enough time to bid on request. Skipping...");
+            return Ok(());
+        }
+
+        // Bid on the request.
+        info!(request_id = %hex::encode(request_id), bid = %self.bid, "{SERIAL_BIDDER_TAG} Submitting a bid for request");
+        network_client(ctx).bid(&request.request_id, self.bid, self.prover).await?;
+        ctx.events().emit(NodeEvent::BidSubmitted {
+            request_id: request.request_id.clone(),
+            amount: self.bid,
+            prover: self.prover,
+        });
+
         Ok(())
     }
 }
 
 /// A serial prover.
 ///
-/// This prover will generate proofs for requests sequentially using an [`EnvProver`].
+/// This prover will generate proofs for requests sequentially, using the [`ProverBackend`] of the
+/// version of each request. By default, requests of [`SP1_NETWORK_VERSION`] are proven with an
+/// [`EnvProver`].
 pub struct SerialProver {
-    /// The underlying prover for the node that will be used to generate proofs.
-    prover: Arc<EnvProver>,
+    /// The backends that generate proofs, keyed by the SP1 version of the requests.
+    backends: ProverRegistry,
     /// Registry of unexecutable request IDs that should be cancelled.
     unexecutable_requests: Arc<Mutex<HashSet<Vec<u8>>>>,
+    /// Whether the background task that fills the unexecutable registry was started.
+    unexecutable_task_started: atomic::AtomicBool,
+    /// How proofs are attached to their fulfillment.
+    proof_upload: ProofUploadPolicy,
+    /// The key encrypted stdins are decrypted with, if any.
+    stdin_key: Option<ArtifactSecretKey>,
 }
 
 impl Default for SerialProver {
@@ -283,20 +327,55 @@ impl SerialProver {
             env::set_var("SP1_PROVER", "cpu");
         }
 
+        let mut backends = ProverRegistry::new();
+        backends.insert(SP1_NETWORK_VERSION, Arc::new(EnvProver::new()));
+
         Self {
-            prover: Arc::new(EnvProver::new()),
+            backends,
             unexecutable_requests: Arc::new(Mutex::new(HashSet::new())),
+            unexecutable_task_started: atomic::AtomicBool::new(false),
+            proof_upload: ProofUploadPolicy::default(),
+            stdin_key: None,
         }
     }
 
+    /// Prove the requests of `version` with `backend`, such as a [`crate::WorkerBackend`] for
+    /// another SP1 version.
+    #[must_use]
+    pub fn with_backend(
+        mut self,
+        version: impl Into<String>,
+        backend: Arc<dyn ProverBackend>,
+    ) -> Self {
+        self.backends.insert(version, backend);
+        self
+    }
+
+    /// The SP1 versions of the requests this prover can prove.
+    #[must_use]
+    pub fn versions(&self) -> Vec<String> {
+        self.backends.versions()
+    }
+
+    /// Decrypt stdins that requesters encrypted to the public key of `key`.
+    #[must_use]
+    pub fn with_stdin_key(mut self, key: ArtifactSecretKey) -> Self {
+        self.stdin_key = Some(key);
+        self
+    }
+
+    /// Upload large proofs as artifacts according to `policy`, instead of sending them inline.
+    #[must_use]
+    pub fn with_proof_upload(mut self, policy: ProofUploadPolicy) -> Self {
+        self.proof_upload = policy;
+        self
+    }
+
     /// Checks the network for unexecutable requests and maintains a registry.
     fn ensure_unexecutable_check_task_running<C: NodeContext>(&self, ctx: &C) {
-        // Use a static AtomicBool to ensure we only start the task once across the entire
-        // application.
-        static TASK_STARTED: atomic::AtomicBool = atomic::AtomicBool::new(false);
-
         // If the task is already running, don't start another one.
-        if TASK_STARTED
+        if self
+            .unexecutable_task_started
             .compare_exchange(false, true, atomic::Ordering::SeqCst, atomic::Ordering::SeqCst)
             .is_err()
         {
@@ -307,6 +386,7 @@ impl SerialProver {
         let unexecutable_requests = self.unexecutable_requests.clone();
         let network = ctx.network().clone();
         let signer_address = ctx.signer().address().to_vec();
+        let versions = self.versions();
 
         // Spawn a background task to check for unexecutable requests.
         tokio::spawn(async move {
@@ -323,20 +403,21 @@ impl SerialProver {
                     }
                 };
 
-                // Check for unexecutable requests.
-                let response = match network
-                    .clone()
-                    .get_filtered_proof_requests(GetFilteredProofRequestsRequest {
-                        version: Some(SP1_NETWORK_VERSION.to_string()),
-                        fulfillment_status: Some(FulfillmentStatus::Assigned.into()),
-                        execution_status: Some(ExecutionStatus::Unexecutable.into()),
-                        fulfiller: Some(owner),
-                        limit: Some(100),
-                        ..Default::default()
+                // Check for unexecutable requests of every version.
+                let requests = stream::iter(versions.clone())
+                    .map(|version| {
+                        let request = GetFilteredProofRequestsRequest {
+                            version: Some(version),
+                            fulfillment_status: Some(FulfillmentStatus::Assigned.into()),
+                            execution_status: Some(ExecutionStatus::Unexecutable.into()),
+                            fulfiller: Some(owner.clone()),
+                            ..Default::default()
+                        };
+                        stream_proof_requests(&network, request, PageConfig::default())
                     })
-                    .await
-                {
-                    Ok(resp) => resp.into_inner(),
+                    .flatten();
+                let requests: Vec<ProofRequest> = match requests.try_collect().await {
+                    Ok(requests) => requests,
                     Err(e) => {
                         tracing::warn!(
                             "{SERIAL_PROVER_TAG} Failed to check for unexecutable requests: {:?}",
@@ -349,7 +430,7 @@ impl SerialProver {
 
                 // Update the registry with unexecutable request IDs.
                 let mut registry = unexecutable_requests.lock().await;
-                for request in response.requests {
+                for request in requests {
                     let request_id_hex = hex::encode(&request.request_id);
                     if registry.insert(request.request_id) {
                         // Only log if this is a new insertion.
@@ -367,41 +448,63 @@ impl SerialProver {
     }
 }
 
+/// Fetch the requests matching `request` for each of `versions`, and keep the ones with the
+/// earliest deadlines, up to the limit of `request`.
+///
+/// Every version is queried, so a busy version cannot starve the others.
+async fn get_requests_for_versions<N: ProverNetworkApi>(
+    network: &N,
+    versions: &[String],
+    request: GetFilteredProofRequestsRequest,
+) -> Result<Vec<ProofRequest>> {
+    let responses = future::try_join_all(versions.iter().map(|version| {
+        let request =
+            GetFilteredProofRequestsRequest { version: Some(version.clone()), ..request.clone() };
+        network.get_filtered_proof_requests(request)
+    }))
+    .await?;
+    let mut requests: Vec<ProofRequest> =
+        responses.into_iter().flat_map(|response| response.requests).collect();
+    requests.sort_by_key(|request| request.deadline);
+    if let Some(limit) = request.limit {
+        requests.truncate(limit as usize);
+    }
+    Ok(requests)
+}
+
+/// The client that signs and submits the transactions of the node.
+fn network_client<C: NodeContext>(ctx: &C) -> NetworkClient<C::Network> {
+    NetworkClient::new(ctx.network().clone(), ctx.signer().clone(), *SPN_MAINNET_V1_DOMAIN)
+}
+
 /// Attempts to notify the network that proving a request failed.
 async fn fail_request<C: NodeContext>(ctx: &C, request_id: Vec<u8>) -> Result<()> {
     const SERIAL_PROVER_TAG: &str = "\x1b[33m[SerialProver]\x1b[0m";
-    let address = ctx.signer().address().to_vec();
-    ctx.network()
-        .clone()
-        .with_retry(
-            || async {
-                // Get the nonce.
-                let nonce = ctx
-                    .network()
-                    .clone()
-                    .get_nonce(GetNonceRequest { address: address.clone() })
-                    .await?
-                    .into_inner()
-                    .nonce;
-
-                // Create and submit the fail request.
-                let body = FailFulfillmentRequestBody {
-                    nonce,
-                    request_id: request_id.clone(),
-                    error: None,
-                };
-                let fail_request = FailFulfillmentRequest {
-                    format: MessageFormat::Binary.into(),
-                    signature: body.sign(&ctx.signer()).into(),
-                    body: Some(body),
-                };
-                ctx.network().clone().fail_fulfillment(fail_request).await?;
-                info!(request_id = %hex::encode(&request_id), "{SERIAL_PROVER_TAG} Notified network of failed fulfillment.");
-                Ok(())
-            },
-            "FailFulfillment",
-        )
-        .await?;
+    network_client(ctx).fail(&request_id, None).await?;
+    info!(request_id = %hex::encode(&request_id), "{SERIAL_PROVER_TAG} Notified network of failed fulfillment.");
+    Ok(())
+}
+
+/// Submits a proof for a request to the network, inline or as a proof artifact according to
+/// `policy`.
+async fn fulfill_request<C: NodeContext>(
+    ctx: &C,
+    request_id: &[u8],
+    proof: Vec<u8>,
+    policy: &ProofUploadPolicy,
+) -> Result<()> {
+    const SERIAL_PROVER_TAG: &str = "\x1b[33m[SerialProver]\x1b[0m";
+    let proof_size = proof.len();
+    let client = network_client(ctx);
+    match attach_proof(request_id, proof, policy).await? {
+        ProofPayload::Inline(proof) => client.fulfill(request_id, &proof).await?,
+        ProofPayload::Uri(uri) => client.fulfill_with_uri(request_id, &uri).await?,
+    };
+    info!(
+        request_id = %hex::encode(request_id),
+        proof_size = %proof_size,
+        "{SERIAL_PROVER_TAG} Proof fulfillment submitted."
+    );
     Ok(())
 }
 
@@ -409,6 +512,10 @@ async fn fail_request<C: NodeContext>(ctx: &C, request_id: Vec<u8>) -> Result<()
 #[derive(Debug, Clone)]
 pub struct SerialMonitor {
     pub has_cuda_support: bool,
+    /// Notify when the balance of the node's owner drops below this amount.
+    pub min_balance: Option<U256>,
+    /// Notify when the stake of the prover drops below this amount.
+    pub min_stake: Option<(Address, U256)>,
 }
 
 /// Holds GPU metrics obtained from NVML.
@@ -428,7 +535,56 @@ impl Default for SerialMonitor {
 impl SerialMonitor {
     #[must_use]
     pub fn new() -> Self {
-        Self { has_cuda_support: spn_utils::has_cuda_support() }
+        Self { has_cuda_support: spn_utils::has_cuda_support(), min_balance: None, min_stake: None }
+    }
+
+    /// Notify when the balance of the node's owner drops below `min_balance`.
+    #[must_use]
+    pub fn with_min_balance(mut self, min_balance: U256) -> Self {
+        self.min_balance = Some(min_balance);
+        self
+    }
+
+    /// Notify when the stake of `prover` drops below `min_stake`.
+    #[must_use]
+    pub fn with_min_stake(mut self, prover: Address, min_stake: U256) -> Self {
+        self.min_stake = Some((prover, min_stake));
+        self
+    }
+
+    /// Checks the owner balance and prover stake against the configured thresholds.
+    async fn check_thresholds<N: ProverNetworkApi>(&self, ctx: &SerialContext<N>) -> Result<()> {
+        const SERIAL_MONITOR_TAG: &str = "\x1b[35m[SerialMonitor]\x1b[0m";
+
+        if let Some(min_balance) = self.min_balance {
+            let signer = ctx.signer().address().to_vec();
+            let owner = fetch_owner(ctx.network(), &signer).await?;
+            let balance = fetch_balance(ctx.network(), &owner).await?;
+            info!(balance = %balance, min_balance = %min_balance, "{SERIAL_MONITOR_TAG} Checking owner balance...");
+            if balance < min_balance {
+                ctx.notifier()
+                    .notify(Notification::LowBalance {
+                        balance: balance.to_string(),
+                        threshold: min_balance.to_string(),
+                    })
+                    .await;
+            }
+        }
+
+        if let Some((prover, min_stake)) = self.min_stake {
+            let stake = fetch_stake_balance(ctx.network(), prover.as_slice()).await?;
+            info!(stake = %stake, min_stake = %min_stake, "{SERIAL_MONITOR_TAG} Checking prover stake...");
+            if stake < min_stake {
+                ctx.notifier()
+                    .notify(Notification::LowStake {
+                        stake: stake.to_string(),
+                        threshold: min_stake.to_string(),
+                    })
+                    .await;
+            }
+        }
+
+        Ok(())
     }
 
     /// Attempts to fetch GPU metrics using NVML.
@@ -448,8 +604,8 @@ impl SerialMonitor {
 }
 
 #[async_trait]
-impl NodeMonitor<SerialContext> for SerialMonitor {
-    async fn record(&self, ctx: &SerialContext) -> Result<()> {
+impl<N: ProverNetworkApi> NodeMonitor<SerialContext<N>> for SerialMonitor {
+    async fn record(&self, ctx: &SerialContext<N>) -> Result<()> {
         const SERIAL_MONITOR_TAG: &str = "\x1b[35m[SerialMonitor]\x1b[0m";
 
         // Log the node metrics.
@@ -511,6 +667,11 @@ impl NodeMonitor<SerialContext> for SerialMonitor {
             }
         }
 
+        // Check the balance and stake thresholds. A failed check shouldn't take down the monitor.
+        if let Err(e) = self.check_thresholds(ctx).await {
+            warn!("{SERIAL_MONITOR_TAG} Failed to check balance and stake thresholds: {:?}", e);
+        }
+
         Ok(())
     }
 }
@@ -530,20 +691,18 @@ impl<C: NodeContext> NodeProver<C> for SerialProver {
         info!(owner = %hex::encode(&owner), signer = %hex::encode(&signer), "{SERIAL_PROVER_TAG} Fetched owner.");
 
         // Fetch for assigned requests.
-        let requests = ctx
-            .network()
-            .clone()
-            .get_filtered_proof_requests(GetFilteredProofRequestsRequest {
-                version: Some(SP1_NETWORK_VERSION.to_string()),
+        let requests = get_requests_for_versions(
+            ctx.network(),
+            &self.versions(),
+            GetFilteredProofRequestsRequest {
                 fulfillment_status: Some(FulfillmentStatus::Assigned.into()),
                 minimum_deadline: Some(time_now()),
                 fulfiller: Some(owner.clone()),
                 limit: Some(1),
                 ..Default::default()
-            })
-            .await?
-            .into_inner()
-            .requests;
+            },
+        )
+        .await?;
         info!(count = %requests.len(), "{SERIAL_PROVER_TAG} Fetched assigned proof requests.");
 
         // If there are no assigned requests, return.
@@ -565,6 +724,8 @@ impl<C: NodeContext> NodeProver<C> for SerialProver {
                 // Release lock early.
                 drop(unexecutable_registry);
 
+                ctx.events().emit(NodeEvent::Cancelled { request_id: request_id.clone() });
+
                 // Notify the network about the failure.
                 report_request_status(ctx, request_id.clone(), &request_id, "skipped UNEXECUTABLE")
                     .await;
@@ -577,6 +738,10 @@ impl<C: NodeContext> NodeProver<C> for SerialProver {
 
             // Log the request details.
             let request_id_hex = hex::encode(&request.request_id);
+            ctx.events().emit(NodeEvent::Assigned {
+                request_id: request.request_id.clone(),
+                deadline: request.deadline,
+            });
             info!(
                 request_id = %request_id_hex,
                 vk_hash = %hex::encode(request.vk_hash),
@@ -602,8 +767,18 @@ impl<C: NodeContext> NodeProver<C> for SerialProver {
                 label: "program".to_string(),
                 expiry: None,
             };
-            let program: Vec<u8> =
-                program_artifact.download_program_from_uri(&request.program_public_uri, "").await?;
+            let program: Vec<u8> = match program_artifact
+                .download_program_from_uri(&request.program_public_uri, "")
+                .await
+            {
+                Ok(program) => program,
+                // Transient failures are retried on the next cycle.
+                Err(e) if e.is_transient() => return Err(e.into()),
+                Err(e) => {
+                    fail_artifact_request(ctx, &request.request_id, &e).await;
+                    continue;
+                }
+            };
             info!(program_size = %program.len(), artifact_id = %hex::encode(program_artifact_id), "{SERIAL_PROVER_TAG} Downloaded program.");
 
             // Download the stdin.
@@ -613,12 +788,29 @@ impl<C: NodeContext> NodeProver<C> for SerialProver {
                 label: "stdin".to_string(),
                 expiry: None,
             };
-            let stdin: SP1Stdin =
-                stdin_artifact.download_stdin_from_uri(&request.stdin_public_uri, "").await?;
-            info!(stdin_size = %stdin.buffer.iter().map(std::vec::Vec::len).sum::<usize>(), artifact_id = %hex::encode(stdin_artifact_id), "{SERIAL_PROVER_TAG} Downloaded stdin.");
+            let stdin = match stdin_artifact
+                .download_raw_stdin_from_uri_with_key(
+                    &request.stdin_public_uri,
+                    "",
+                    self.stdin_key.as_ref(),
+                )
+                .await
+            {
+                Ok(stdin) => stdin,
+                Err(e) if e.is_transient() => return Err(e.into()),
+                Err(e) => {
+                    fail_artifact_request(ctx, &request.request_id, &e).await;
+                    continue;
+                }
+            };
+            info!(stdin_size = %stdin.len(), artifact_id = %hex::encode(stdin_artifact_id), "{SERIAL_PROVER_TAG} Downloaded stdin.");
 
             // Generate the proving keys and the proof in a separate thread to catch panics.
-            let prover = self.prover.clone();
+            let Some(backend) = self.backends.get(&request.version) else {
+                // Only requests of installed versions are fetched, so this is not expected.
+                warn!(version = %request.version, "{SERIAL_PROVER_TAG} No prover backend for version. Skipping...");
+                continue;
+            };
             let mode = ProofMode::try_from(request.mode).unwrap_or(ProofMode::Core);
             let mode = match mode {
                 ProofMode::Core => SP1ProofMode::Core,
@@ -628,27 +820,15 @@ impl<C: NodeContext> NodeProver<C> for SerialProver {
                 ProofMode::UnspecifiedProofMode => unreachable!(),
             };
 
-            // Store the join handle and extract its abort handle.
+            // Store the join handle and extract its abort handle. A running blocking task cannot
+            // be aborted, so the backend is also asked to stop through a cancel token.
+            let events = ctx.events().clone();
+            let request_id = request.request_id.clone();
+            let cancel = CancelToken::default();
+            let backend_cancel = cancel.clone();
             let proving_handle = tokio::task::spawn_blocking(move || {
                 panic::catch_unwind(AssertUnwindSafe(move || {
-                    let start = Instant::now();
-                    info!("{SERIAL_PROVER_TAG} Setting up proving key...");
-
-                    let (pk, _) = prover.setup(&program);
-                    info!(duration = %start.elapsed().as_secs_f64(), "{SERIAL_PROVER_TAG} Set up proving key.");
-
-                    let start = Instant::now();
-                    info!("{SERIAL_PROVER_TAG} Executing program...");
-                    let (_, report) = prover.execute(&pk.elf, &stdin).run().unwrap();
-                    let cycles = report.total_instruction_count();
-                    info!(duration = %start.elapsed().as_secs_f64(), cycles = %cycles, "{SERIAL_PROVER_TAG} Executed program.");
-
-                    let start = Instant::now();
-                    info!("{SERIAL_PROVER_TAG} Generating proof...");
-                    let proof = prover.prove(&pk, &stdin).mode(mode).run();
-                    let proving_time = start.elapsed();
-                    info!(duration = %proving_time.as_secs_f64(), cycles = %cycles, "{SERIAL_PROVER_TAG} Proof generation complete.");
-                    (proof, cycles, proving_time)
+                    backend.prove(&request_id, &program, &stdin, mode, &events, &backend_cancel)
                 }))
             });
             let proving_abort_handle = proving_handle.abort_handle();
@@ -656,6 +836,7 @@ impl<C: NodeContext> NodeProver<C> for SerialProver {
             // Create a check task for this specific request.
             let request_id = request.request_id.clone();
             let unexecutable_registry = self.unexecutable_requests.clone();
+            let monitoring_cancel = cancel.clone();
 
             // Spawn a task to periodically check if the request became UNEXECUTABLE.
             let monitoring_task = tokio::spawn(async move {
@@ -676,7 +857,8 @@ impl<C: NodeContext> NodeProver<C> for SerialProver {
                             "{SERIAL_PROVER_TAG} Request now marked as UNEXECUTABLE, aborting proof generation"
                         );
 
-                        // Abort the proving task.
+                        // Abort the proving task, and stop the backend if it already started.
+                        monitoring_cancel.cancel();
                         proving_abort_handle.abort();
 
                         info!("{SERIAL_PROVER_TAG} Aborted proving task.");
@@ -694,66 +876,62 @@ impl<C: NodeContext> NodeProver<C> for SerialProver {
 
             match result {
                 Ok(panic_result) => match panic_result {
-                    Ok((proof_result, cycles, proving_time)) => {
+                    Ok(proof_result) => {
                         match proof_result {
-                            Ok(proof) => {
+                            Ok(BackendProof { proof: proof_bytes, cycles, proving_time }) => {
                                 // Update the metrics.
                                 let metrics = ctx.metrics();
                                 *metrics.total_cycles.lock().await += cycles;
                                 *metrics.total_proving_time.lock().await += proving_time;
                                 *metrics.fulfilled.lock().await += 1;
 
-                                // Now serialize the actual proof value
-                                let proof_bytes = bincode::serialize(&proof)
-                                    .context("failed to serialize proof")?;
-
-                                // Fulfill the proof.
-                                let address = ctx.signer().address().to_vec();
-                                if let Err(e) = ctx.network()
-                                    .clone()
-                                    .with_retry(
-                                        || async {
-                                            // Get the nonce.
-                                            let nonce = ctx
-                                                .network()
-                                                .clone()
-                                                .get_nonce(GetNonceRequest { address: address.clone() })
-                                                .await?
-                                                .into_inner()
-                                                .nonce;
-                                            info!(nonce = %nonce, "{SERIAL_PROVER_TAG} Fetched account nonce.");
-
-                                            // Create and submit the fulfill request.
-                                            let body = FulfillProofRequestBody {
-                                                nonce,
-                                                request_id: request.request_id.clone(),
-                                                proof: proof_bytes.clone(),
-                                                reserved_metadata: None,
-                                                domain: SPN_MAINNET_V1_DOMAIN.to_vec(),
-                                                variant: TransactionVariant::FulfillVariant as i32,
-                                            };
-                                            let fulfill_request = FulfillProofRequest {
-                                                format: MessageFormat::Binary.into(),
-                                                signature: body.sign(&ctx.signer()).into(),
-                                                body: Some(body),
-                                            };
-                                            ctx.network().clone().fulfill_proof(fulfill_request).await?;
-                                            info!(
-                                                request_id = %hex::encode(&request.request_id),
-                                                proof_size = %proof_bytes.len(),
-                                                "{SERIAL_PROVER_TAG} Proof fulfillment submitted."
-                                            );
-                                            Ok(())
-                                        },
-                                        "Fulfill",
-                                    )
-                                    .await
+                                let proof_size = proof_bytes.len();
+
+                                // Fulfill the proof, uploading it first if it is too large.
+                                if let Err(e) = fulfill_request(
+                                    ctx,
+                                    &request.request_id,
+                                    proof_bytes,
+                                    &self.proof_upload,
+                                )
+                                .await
                                 {
                                     error!("{SERIAL_PROVER_TAG} Failed to fulfill proof: {:?}", e);
+                                    ctx.notifier()
+                                        .notify(Notification::RpcFailure {
+                                            operation: "Fulfill".to_string(),
+                                            error: e.to_string(),
+                                        })
+                                        .await;
+                                } else {
+                                    ctx.events().emit(NodeEvent::Fulfilled {
+                                        request_id: request.request_id.clone(),
+                                        proof_size,
+                                    });
                                 }
                             }
+                            Err(_) if cancel.is_cancelled() => {
+                                warn!(
+                                    request_id = %hex::encode(&request.request_id),
+                                    "{SERIAL_PROVER_TAG} Proving was aborted because request is UNEXECUTABLE"
+                                );
+                                ctx.events().emit(NodeEvent::Cancelled {
+                                    request_id: request.request_id.clone(),
+                                });
+                                report_request_status(
+                                    ctx,
+                                    request.request_id.clone(),
+                                    &request.request_id,
+                                    "cancellation",
+                                )
+                                .await;
+                            }
                             Err(e) => {
                                 error!("{SERIAL_PROVER_TAG} Proof generation failed: {:?}", e);
+                                ctx.events().emit(NodeEvent::Failed {
+                                    request_id: request.request_id.clone(),
+                                    cause: format!("proof generation failed: {e}"),
+                                });
 
                                 // Report failure to the network
                                 report_request_status(
@@ -776,6 +954,10 @@ impl<C: NodeContext> NodeProver<C> for SerialProver {
                         };
 
                         error!("{SERIAL_PROVER_TAG} Proving panicked: {}", panic_msg);
+                        ctx.events().emit(NodeEvent::Failed {
+                            request_id: request.request_id.clone(),
+                            cause: format!("proving panicked: {panic_msg}"),
+                        });
 
                         // Attempt to mark the request as failed on the network.
                         report_request_status(
@@ -796,8 +978,14 @@ impl<C: NodeContext> NodeProver<C> for SerialProver {
                             request_id = %hex::encode(&request.request_id),
                             "{SERIAL_PROVER_TAG} Proving was aborted because request is UNEXECUTABLE"
                         );
+                        ctx.events()
+                            .emit(NodeEvent::Cancelled { request_id: request.request_id.clone() });
                     } else {
                         error!("{SERIAL_PROVER_TAG} Proving was aborted because: {:?}", e);
+                        ctx.events().emit(NodeEvent::Failed {
+                            request_id: request.request_id.clone(),
+                            cause: format!("proving task failed: {e}"),
+                        });
                     }
 
                     // Always notify network about task failure.
@@ -817,6 +1005,27 @@ impl<C: NodeContext> NodeProver<C> for SerialProver {
     }
 }
 
+/// Fail a request whose artifact cannot be downloaded for a reason that retrying will not fix,
+/// such as a missing, corrupt, expired or oversized artifact, or one the prover may not read.
+///
+/// Skipping such a request instead would download it again on every cycle until its deadline,
+/// while the node holds the assignment.
+async fn fail_artifact_request<C: NodeContext>(ctx: &C, request_id: &[u8], error: &ArtifactError) {
+    const SERIAL_PROVER_TAG: &str = "\x1b[33m[SerialProver]\x1b[0m";
+
+    error!(
+        request_id = %hex::encode(request_id),
+        kind = ?error.kind(),
+        "{SERIAL_PROVER_TAG} Artifact is unavailable: {:?}",
+        error
+    );
+    ctx.events().emit(NodeEvent::Failed {
+        request_id: request_id.to_vec(),
+        cause: format!("artifact unavailable: {error}"),
+    });
+    report_request_status(ctx, request_id.to_vec(), request_id, "artifact failure").await;
+}
+
 /// Helper function to report a request status to the network and log the result.
 /// This handles both success and failure of the reporting itself.
 async fn report_request_status<C: NodeContext>(
@@ -834,6 +1043,12 @@ async fn report_request_status<C: NodeContext>(
             status_type,
             fail_err
         );
+        ctx.notifier()
+            .notify(Notification::RpcFailure {
+                operation: "FailFulfillment".to_string(),
+                error: fail_err.to_string(),
+            })
+            .await;
     } else {
         info!(
             request_id = %hex::encode(display_request_id),
@@ -842,3 +1057,406 @@ async fn report_request_status<C: NodeContext>(
         );
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fake::FakeNetwork;
+    use spn_artifact_types::ArtifactType;
+    use spn_artifacts::MemoryBackend;
+    use tonic::Status;
+
+    fn context(network: &FakeNetwork) -> SerialContext<FakeNetwork> {
+        MemoryBackend::register();
+        SerialContext::new(network.clone(), PrivateKeySigner::random())
+    }
+
+    fn proof_request(id: u8, status: FulfillmentStatus, deadline: u64) -> ProofRequest {
+        ProofRequest {
+            request_id: vec![id; 32],
+            version: SP1_NETWORK_VERSION.to_string(),
+            fulfillment_status: status.into(),
+            deadline,
+            gas_limit: 1_000_000,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_bidder_bids_on_open_request() {
+        let network = FakeNetwork::default();
+        let deadline = time_now() + 3600;
+        network.insert_request(proof_request(1, FulfillmentStatus::Requested, deadline));
+        let ctx = context(&network);
+        let mut events = ctx.events().subscribe();
+        let bidder = SerialBidder::new(U256::from(100), 1_000_000.0, Address::repeat_byte(7));
+
+        bidder.bid(&ctx).await.unwrap();
+
+        let bids = network.bids();
+        assert_eq!(bids.len(), 1);
+        assert_eq!(bids[0].request_id, vec![1; 32]);
+        assert_eq!(bids[0].amount, "100");
+        assert_eq!(bids[0].prover, Address::repeat_byte(7).to_vec());
+        assert_eq!(bids[0].nonce, 0);
+        let seen = events.recv().await.unwrap();
+        assert_eq!(
+            seen.event,
+            NodeEvent::RequestSeen { request_id: vec![1; 32], gas_limit: 1_000_000, deadline }
+        );
+        let bid = events.recv().await.unwrap();
+        assert_eq!(
+            bid.event,
+            NodeEvent::BidSubmitted {
+                request_id: vec![1; 32],
+                amount: U256::from(100),
+                prover: Address::repeat_byte(7),
+            }
+        );
+        assert!(seen.emitted_at <= bid.emitted_at);
+
+        // The node already bid on the request, so it isn't offered again.
+        bidder.bid(&ctx).await.unwrap();
+        assert_eq!(network.bids().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_bidder_waits_for_assigned_request() {
+        let network = FakeNetwork::default();
+        let ctx = context(&network);
+        let mut assigned = proof_request(1, FulfillmentStatus::Assigned, time_now() + 3600);
+        assigned.fulfiller = Some(ctx.signer().address().to_vec());
+        network.insert_request(assigned);
+        network.insert_request(proof_request(2, FulfillmentStatus::Requested, time_now() + 3600));
+        let bidder = SerialBidder::new(U256::from(100), 1_000_000.0, Address::repeat_byte(7));
+
+        bidder.bid(&ctx).await.unwrap();
+
+        assert!(network.bids().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_bidder_bids_on_installed_versions() {
+        let network = FakeNetwork::default();
+        let mut old = proof_request(1, FulfillmentStatus::Requested, time_now() + 3600);
+        old.version = "sp1-v0.0.1".to_string();
+        network.insert_request(old);
+        let mut unsupported = proof_request(2, FulfillmentStatus::Requested, time_now() + 3600);
+        unsupported.version = "sp1-v0.0.2".to_string();
+        network.insert_request(unsupported);
+        let ctx = context(&network);
+        let bidder = SerialBidder::new(U256::from(100), 1_000_000.0, Address::repeat_byte(7))
+            .with_versions(vec![SP1_NETWORK_VERSION.to_string(), "sp1-v0.0.1".to_string()]);
+
+        bidder.bid(&ctx).await.unwrap();
+        bidder.bid(&ctx).await.unwrap();
+
+        // Requests of versions without a backend are never bid on.
+        let bids = network.bids();
+        assert_eq!(bids.len(), 1);
+        assert_eq!(bids[0].request_id, vec![1; 32]);
+    }
+
+    #[tokio::test]
+    async fn test_bidder_picks_earliest_deadline_across_versions() {
+        let network = FakeNetwork::default();
+        let mut later = proof_request(1, FulfillmentStatus::Requested, time_now() + 7200);
+        later.version = "sp1-v0.0.1".to_string();
+        network.insert_request(later);
+        let mut sooner = proof_request(2, FulfillmentStatus::Requested, time_now() + 3600);
+        sooner.version = "sp1-v0.0.2".to_string();
+        network.insert_request(sooner);
+        let ctx = context(&network);
+        let bidder = SerialBidder::new(U256::from(100), 1_000_000.0, Address::repeat_byte(7))
+            .with_versions(vec!["sp1-v0.0.1".to_string(), "sp1-v0.0.2".to_string()]);
+
+        bidder.bid(&ctx).await.unwrap();
+        bidder.bid(&ctx).await.unwrap();
+
+        // Later versions are not starved by earlier ones.
+        let bids: Vec<_> = network.bids().into_iter().map(|bid| bid.request_id).collect();
+        assert_eq!(bids, vec![vec![2; 32], vec![1; 32]]);
+    }
+
+    #[tokio::test]
+    async fn test_bidder_skips_expired_and_tight_deadlines() {
+        let network = FakeNetwork::default();
+        let ctx = context(&network);
+        let bidder = SerialBidder::new(U256::from(100), 1.0, Address::repeat_byte(7));
+
+        // The deadline already passed.
+        network.insert_request(proof_request(1, FulfillmentStatus::Requested, time_now() - 1));
+        bidder.bid(&ctx).await.unwrap();
+        assert!(network.bids().is_empty());
+
+        // The deadline is too close to prove 1M PGUs at 1 PGU per second.
+        network.insert_request(proof_request(2, FulfillmentStatus::Requested, time_now() + 10));
+        bidder.bid(&ctx).await.unwrap();
+        assert!(network.bids().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_fail_request_uses_fresh_nonces() {
+        let network = FakeNetwork::default();
+        let ctx = context(&network);
+
+        fail_request(&ctx, vec![1; 32]).await.unwrap();
+        fail_request(&ctx, vec![2; 32]).await.unwrap();
+
+        let failures = network.failures();
+        assert_eq!(failures.iter().map(|f| f.nonce).collect::<Vec<_>>(), vec![0, 1]);
+        assert_eq!(failures[1].request_id, vec![2; 32]);
+    }
+
+    #[tokio::test]
+    async fn test_fulfill_request_surfaces_network_errors() {
+        let network = FakeNetwork::default();
+        let ctx = context(&network);
+        let mut request = proof_request(1, FulfillmentStatus::Assigned, time_now() + 3600);
+        request.fulfiller = Some(ctx.signer().address().to_vec());
+        network.insert_request(request);
+
+        let policy = ProofUploadPolicy::default();
+        fulfill_request(&ctx, &[1; 32], b"proof".to_vec(), &policy).await.unwrap();
+        assert_eq!(network.fulfillments()[0].proof, b"proof".to_vec());
+
+        network.fail_fulfillments_with(Status::failed_precondition("deadline passed"));
+        let err = fulfill_request(&ctx, &[1; 32], b"proof".to_vec(), &policy).await.unwrap_err();
+        assert_eq!(err.downcast_ref::<Status>().unwrap().code(), tonic::Code::FailedPrecondition);
+        assert_eq!(network.fulfillments().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_fulfill_request_uploads_large_proofs() {
+        let network = FakeNetwork::default();
+        let ctx = context(&network);
+        let mut request = proof_request(1, FulfillmentStatus::Assigned, time_now() + 3600);
+        request.fulfiller = Some(ctx.signer().address().to_vec());
+        network.insert_request(request);
+        let policy = ProofUploadPolicy::default()
+            .with_destination("memory://node-proofs", "")
+            .with_inline_limit(4);
+
+        fulfill_request(&ctx, &[1; 32], b"large proof".to_vec(), &policy).await.unwrap();
+
+        let fulfillment = network.fulfillments().remove(0);
+        assert!(fulfillment.proof.is_empty());
+        let payload = ProofPayload::from_fields(fulfillment.proof, fulfillment.proof_uri);
+        assert!(
+            matches!(&payload, ProofPayload::Uri(uri) if uri.starts_with("memory://node-proofs/proofs/"))
+        );
+        assert_eq!(payload.fetch("").await.unwrap(), b"large proof".to_vec());
+    }
+
+    #[tokio::test]
+    async fn test_prover_fails_unexecutable_and_ignores_expired_requests() {
+        let network = FakeNetwork::default();
+        let ctx = context(&network);
+        let owner = ctx.signer().address().to_vec();
+        let prover = SerialProver::new();
+        let mut events = ctx.events().subscribe();
+
+        // An assignment whose deadline passed is not picked up.
+        let mut expired = proof_request(1, FulfillmentStatus::Assigned, time_now() - 1);
+        expired.fulfiller = Some(owner.clone());
+        network.insert_request(expired);
+        prover.prove(&ctx).await.unwrap();
+        assert!(network.failures().is_empty());
+
+        // An assignment that is known to be unexecutable is failed without proving it.
+        let mut unexecutable = proof_request(2, FulfillmentStatus::Assigned, time_now() + 3600);
+        unexecutable.fulfiller = Some(owner);
+        unexecutable.execution_status = ExecutionStatus::Unexecutable.into();
+        network.insert_request(unexecutable);
+        prover.unexecutable_requests.lock().await.insert(vec![2; 32]);
+        prover.prove(&ctx).await.unwrap();
+
+        assert_eq!(network.failures()[0].request_id, vec![2; 32]);
+        assert!(network.fulfillments().is_empty());
+        assert_eq!(
+            events.recv().await.unwrap().event,
+            NodeEvent::Cancelled { request_id: vec![2; 32] }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_prover_fails_requests_with_missing_artifacts() {
+        let network = FakeNetwork::default();
+        let ctx = context(&network);
+        let prover = SerialProver::new();
+        let mut events = ctx.events().subscribe();
+
+        let mut request = proof_request(3, FulfillmentStatus::Assigned, time_now() + 3600);
+        request.fulfiller = Some(ctx.signer().address().to_vec());
+        request.program_public_uri = "memory://bucket/programs/missing".to_string();
+        network.insert_request(request);
+        prover.prove(&ctx).await.unwrap();
+
+        // The artifact will never appear, so the request is failed instead of retried.
+        assert_eq!(network.failures()[0].request_id, vec![3; 32]);
+        assert!(matches!(events.recv().await.unwrap().event, NodeEvent::Assigned { .. }));
+        assert_eq!(
+            events.recv().await.unwrap().event,
+            NodeEvent::Failed {
+                request_id: vec![3; 32],
+                cause: "artifact unavailable: Program artifact missing was not found".to_string(),
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_prover_fails_encrypted_stdin_without_key() {
+        let network = FakeNetwork::default();
+        let ctx = context(&network);
+        let prover = SerialProver::new();
+        let mut events = ctx.events().subscribe();
+
+        let artifact =
+            |id: &str| Artifact { id: id.to_string(), label: "test".to_string(), expiry: None };
+        let program_uri = "memory://bucket/programs/program_private";
+        artifact("program_private")
+            .upload_to_uri(&vec![0u8; 4], program_uri, "", ArtifactType::Program)
+            .await
+            .unwrap();
+        let stdin_uri = "memory://bucket/stdins/stdin_private";
+        let prover_key = ArtifactSecretKey::generate();
+        artifact("stdin_private")
+            .upload_encrypted_stdin_to_uri(
+                &SP1Stdin::new(),
+                stdin_uri,
+                "",
+                &prover_key.public_key(),
+            )
+            .await
+            .unwrap();
+
+        let mut request = proof_request(4, FulfillmentStatus::Assigned, time_now() + 3600);
+        request.fulfiller = Some(ctx.signer().address().to_vec());
+        request.program_public_uri = program_uri.to_string();
+        request.stdin_public_uri = stdin_uri.to_string();
+        network.insert_request(request);
+        prover.prove(&ctx).await.unwrap();
+
+        // The stdin is encrypted to another prover, so the request can never be proven.
+        assert_eq!(network.failures()[0].request_id, vec![4; 32]);
+        assert!(network.fulfillments().is_empty());
+        assert!(matches!(events.recv().await.unwrap().event, NodeEvent::Assigned { .. }));
+        assert!(matches!(
+            events.recv().await.unwrap().event,
+            NodeEvent::Failed { cause, .. } if cause.contains("Stdin artifact stdin_private was denied")
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_prover_fails_undecodable_stdin() {
+        let network = FakeNetwork::default();
+        let ctx = context(&network);
+        let prover = SerialProver::new();
+        let mut events = ctx.events().subscribe();
+
+        let artifact =
+            |id: &str| Artifact { id: id.to_string(), label: "test".to_string(), expiry: None };
+        let program_uri = "memory://bucket/programs/program_garbage";
+        artifact("program_garbage")
+            .upload_to_uri(&vec![0u8; 4], program_uri, "", ArtifactType::Program)
+            .await
+            .unwrap();
+        let stdin_uri = "memory://bucket/stdins/stdin_garbage";
+        artifact("stdin_garbage")
+            .upload_raw_to_uri(vec![1u8, 2, 3].into(), stdin_uri, "", ArtifactType::Stdin)
+            .await
+            .unwrap();
+
+        let mut request = proof_request(6, FulfillmentStatus::Assigned, time_now() + 3600);
+        request.fulfiller = Some(ctx.signer().address().to_vec());
+        request.program_public_uri = program_uri.to_string();
+        request.stdin_public_uri = stdin_uri.to_string();
+        network.insert_request(request);
+
+        // The stdin is not valid bincode, so the backend fails to decode it and the request fails.
+        prover.prove(&ctx).await.unwrap();
+        assert_eq!(network.failures()[0].request_id, vec![6; 32]);
+        assert!(network.fulfillments().is_empty());
+        assert!(matches!(events.recv().await.unwrap().event, NodeEvent::Assigned { .. }));
+        assert!(matches!(events.recv().await.unwrap().event, NodeEvent::Failed { .. }));
+    }
+
+    /// A backend that returns the program as the proof.
+    struct EchoBackend;
+
+    impl ProverBackend for EchoBackend {
+        fn prove(
+            &self,
+            request_id: &[u8],
+            program: &[u8],
+            _stdin: &[u8],
+            _mode: SP1ProofMode,
+            events: &EventBus,
+            _cancel: &CancelToken,
+        ) -> Result<BackendProof> {
+            let request_id = request_id.to_vec();
+            let duration = Duration::ZERO;
+            events.emit(NodeEvent::Executed {
+                request_id: request_id.clone(),
+                cycles: 7,
+                duration,
+            });
+            events.emit(NodeEvent::ProofGenerated { request_id, cycles: 7, duration });
+            Ok(BackendProof { proof: program.to_vec(), cycles: 7, proving_time: duration })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_prover_routes_requests_to_version_backend() {
+        let network = FakeNetwork::default();
+        let ctx = context(&network);
+        let prover = SerialProver::new().with_backend("sp1-v0.0.1", Arc::new(EchoBackend));
+        assert!(prover.versions().contains(&"sp1-v0.0.1".to_string()));
+        let mut events = ctx.events().subscribe();
+
+        let artifact =
+            |id: &str| Artifact { id: id.to_string(), label: "test".to_string(), expiry: None };
+        let program_uri = "memory://bucket/programs/program_versioned";
+        artifact("program_versioned")
+            .upload_to_uri(&b"old elf".to_vec(), program_uri, "", ArtifactType::Program)
+            .await
+            .unwrap();
+        let stdin_uri = "memory://bucket/stdins/stdin_versioned";
+        artifact("stdin_versioned")
+            .upload_to_uri(&SP1Stdin::new(), stdin_uri, "", ArtifactType::Stdin)
+            .await
+            .unwrap();
+
+        let deadline = time_now() + 3600;
+        let mut request = proof_request(5, FulfillmentStatus::Assigned, deadline);
+        request.version = "sp1-v0.0.1".to_string();
+        request.fulfiller = Some(ctx.signer().address().to_vec());
+        request.program_public_uri = program_uri.to_string();
+        request.stdin_public_uri = stdin_uri.to_string();
+        network.insert_request(request);
+        prover.prove(&ctx).await.unwrap();
+
+        let fulfillments = network.fulfillments();
+        assert_eq!(fulfillments[0].request_id, vec![5; 32]);
+        assert_eq!(fulfillments[0].proof, b"old elf");
+        assert_eq!(*ctx.metrics().total_cycles.lock().await, 7);
+
+        // The prove cycle is observable in order, from the assignment to the fulfillment.
+        let request_id = vec![5; 32];
+        let duration = Duration::ZERO;
+        let expected = [
+            NodeEvent::Assigned { request_id: request_id.clone(), deadline },
+            NodeEvent::Executed { request_id: request_id.clone(), cycles: 7, duration },
+            NodeEvent::ProofGenerated { request_id: request_id.clone(), cycles: 7, duration },
+            NodeEvent::Fulfilled { request_id, proof_size: 7 },
+        ];
+        let mut emitted_at = SystemTime::UNIX_EPOCH;
+        for event in expected {
+            let emitted = events.recv().await.unwrap();
+            assert_eq!(emitted.event, event);
+            assert!(emitted.emitted_at >= emitted_at);
+            emitted_at = emitted.emitted_at;
+        }
+        assert!(events.try_recv().is_err());
+    }
+}
@@ -0,0 +1,234 @@
+use std::{any::Any, future::Future, panic::AssertUnwindSafe, time::Duration};
+
+use anyhow::{anyhow, Result};
+use futures::FutureExt;
+use tokio::time::sleep;
+use tracing::{error, info, warn};
+
+use crate::NodeContext;
+
+/// The default interval between iterations of the bid and prove loop.
+pub const DEFAULT_BID_AND_PROVE_INTERVAL: Duration = Duration::from_secs(3);
+
+/// The default interval between iterations of the monitor loop.
+pub const DEFAULT_MONITOR_INTERVAL: Duration = Duration::from_secs(30);
+
+/// The default number of consecutive failures after which a loop gives up.
+pub const DEFAULT_ERROR_BUDGET: u32 = 50;
+
+/// The default upper bound of the delay between failing iterations.
+pub const DEFAULT_MAX_BACKOFF: Duration = Duration::from_secs(300);
+
+/// The number of consecutive failures after which the delay between iterations starts to grow.
+const BACKOFF_THRESHOLD: u32 = 3;
+
+const SUPERVISOR_TAG: &str = "\x1b[35m[Supervisor]\x1b[0m";
+
+/// The configuration of the task loops of a [`crate::Node`].
+#[derive(Debug, Clone)]
+pub struct NodeConfig {
+    /// The interval between iterations of the bid and prove loop.
+    pub bid_and_prove_interval: Duration,
+    /// The interval between iterations of the monitor loop.
+    pub monitor_interval: Duration,
+    /// The number of consecutive failures after which a loop stops the node, or `None` to retry
+    /// forever.
+    pub error_budget: Option<u32>,
+    /// The upper bound of the delay between failing iterations.
+    pub max_backoff: Duration,
+}
+
+impl Default for NodeConfig {
+    fn default() -> Self {
+        Self {
+            bid_and_prove_interval: DEFAULT_BID_AND_PROVE_INTERVAL,
+            monitor_interval: DEFAULT_MONITOR_INTERVAL,
+            error_budget: Some(DEFAULT_ERROR_BUDGET),
+            max_backoff: DEFAULT_MAX_BACKOFF,
+        }
+    }
+}
+
+impl NodeConfig {
+    /// Create a [`Supervisor`] for the loop `name` that runs every `interval`.
+    #[must_use]
+    pub fn supervisor(&self, name: &'static str, interval: Duration) -> Supervisor {
+        Supervisor {
+            name,
+            interval,
+            error_budget: self.error_budget,
+            max_backoff: self.max_backoff,
+        }
+    }
+}
+
+/// Runs a task loop, keeping it alive through errors and panics.
+///
+/// Every failed or panicked iteration is logged and counted in [`crate::NodeMetrics`]. After
+/// repeated failures the delay before the next iteration doubles up to a maximum, and once the
+/// error budget of consecutive failures runs out the loop stops with the last error.
+#[derive(Debug, Clone)]
+pub struct Supervisor {
+    /// The name of the loop, used in logs and errors.
+    name: &'static str,
+    /// The interval between successful iterations.
+    interval: Duration,
+    /// The number of consecutive failures after which the loop stops.
+    error_budget: Option<u32>,
+    /// The upper bound of the delay between failing iterations.
+    max_backoff: Duration,
+}
+
+impl Supervisor {
+    /// Run `step` until the error budget runs out.
+    ///
+    /// A panic in `step` counts as a failed iteration, and the next iteration starts from a fresh
+    /// future.
+    pub async fn run<C, F, Fut>(&self, ctx: &C, mut step: F) -> Result<()>
+    where
+        C: NodeContext,
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<()>>,
+    {
+        let mut consecutive_failures = 0;
+        loop {
+            let result = match AssertUnwindSafe(step()).catch_unwind().await {
+                Ok(result) => result,
+                Err(panic) => Err(anyhow!("{} panicked: {}", self.name, panic_message(&*panic))),
+            };
+
+            let delay = match result {
+                Ok(()) => {
+                    if consecutive_failures > 0 {
+                        info!(
+                            task = self.name,
+                            failures = consecutive_failures,
+                            "{SUPERVISOR_TAG} Recovered after consecutive failures."
+                        );
+                    }
+                    consecutive_failures = 0;
+                    self.interval
+                }
+                Err(e) => {
+                    consecutive_failures += 1;
+                    let total = {
+                        let mut errors = ctx.metrics().loop_errors.lock().await;
+                        *errors += 1;
+                        *errors
+                    };
+                    error!(
+                        task = self.name,
+                        consecutive = consecutive_failures,
+                        total = total,
+                        "{SUPERVISOR_TAG} Iteration failed: {e:?}"
+                    );
+
+                    if self.error_budget.is_some_and(|budget| consecutive_failures >= budget) {
+                        return Err(e.context(format!(
+                            "{} exhausted its error budget after {consecutive_failures} \
+                             consecutive failures",
+                            self.name
+                        )));
+                    }
+
+                    let delay = self.backoff(consecutive_failures);
+                    if delay > self.interval {
+                        warn!(
+                            task = self.name,
+                            delay = %delay.as_secs_f64(),
+                            "{SUPERVISOR_TAG} Backing off after repeated failures."
+                        );
+                    }
+                    delay
+                }
+            };
+
+            sleep(delay).await;
+        }
+    }
+
+    /// The delay before the next iteration after `consecutive_failures` failures in a row.
+    fn backoff(&self, consecutive_failures: u32) -> Duration {
+        if consecutive_failures < BACKOFF_THRESHOLD {
+            return self.interval;
+        }
+        let factor = 2u32.saturating_pow(consecutive_failures - BACKOFF_THRESHOLD + 1);
+        self.interval.saturating_mul(factor).min(self.max_backoff.max(self.interval))
+    }
+}
+
+/// Extract the message of a panic payload.
+fn panic_message(panic: &(dyn Any + Send)) -> String {
+    if let Some(message) = panic.downcast_ref::<&str>() {
+        (*message).to_string()
+    } else if let Some(message) = panic.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use alloy_signer_local::PrivateKeySigner;
+
+    use super::*;
+    use crate::{fake::FakeNetwork, SerialContext};
+
+    fn supervisor(error_budget: Option<u32>) -> Supervisor {
+        NodeConfig { error_budget, max_backoff: Duration::from_millis(8), ..NodeConfig::default() }
+            .supervisor("test", Duration::from_millis(1))
+    }
+
+    #[test]
+    fn test_backoff_doubles_after_threshold_and_caps() {
+        let supervisor = supervisor(None);
+        assert_eq!(supervisor.backoff(1), Duration::from_millis(1));
+        assert_eq!(supervisor.backoff(2), Duration::from_millis(1));
+        assert_eq!(supervisor.backoff(3), Duration::from_millis(2));
+        assert_eq!(supervisor.backoff(4), Duration::from_millis(4));
+        assert_eq!(supervisor.backoff(5), Duration::from_millis(8));
+        assert_eq!(supervisor.backoff(100), Duration::from_millis(8));
+    }
+
+    #[tokio::test]
+    async fn test_panics_count_against_error_budget() {
+        let ctx = SerialContext::new(FakeNetwork::default(), PrivateKeySigner::random());
+        let calls = AtomicU32::new(0);
+
+        let err = supervisor(Some(3))
+            .run(&ctx, || async {
+                calls.fetch_add(1, Ordering::SeqCst);
+                panic!("boom");
+            })
+            .await
+            .unwrap_err();
+
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+        assert_eq!(*ctx.metrics.loop_errors.lock().await, 3);
+        assert!(format!("{err:#}").contains("error budget"));
+        assert!(format!("{err:#}").contains("boom"));
+    }
+
+    #[tokio::test]
+    async fn test_success_resets_error_budget() {
+        let ctx = SerialContext::new(FakeNetwork::default(), PrivateKeySigner::random());
+        let calls = AtomicU32::new(0);
+
+        // Two failures, one success, then failures until the budget of three runs out.
+        let result = supervisor(Some(3))
+            .run(&ctx, || async {
+                match calls.fetch_add(1, Ordering::SeqCst) {
+                    2 => Ok(()),
+                    _ => Err(anyhow!("failed")),
+                }
+            })
+            .await;
+
+        assert!(result.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 6);
+        assert_eq!(*ctx.metrics.loop_errors.lock().await, 5);
+    }
+}
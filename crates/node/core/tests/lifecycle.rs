@@ -0,0 +1,146 @@
+//! End-to-end tests that run a real node against an in-process prover network.
+
+use std::time::Duration;
+
+use alloy_primitives::U256;
+use alloy_signer_local::PrivateKeySigner;
+use sp1_sdk::{SP1ProofWithPublicValues, SP1Stdin};
+use spn_network_types::{
+    prover_network_client::ProverNetworkClient, CreateProgramRequest, CreateProgramRequestBody,
+    FulfillmentStatus, FulfillmentStrategy, MessageFormat, ProofMode, RequestProofRequestBody,
+    Signable,
+};
+use spn_node_core::{
+    Node, SerialBidder, SerialContext, SerialMonitor, SerialProver, SP1_NETWORK_VERSION,
+};
+use spn_node_testing::{ArtifactServer, MockProverNetwork, Settlement, FIBONACCI_ELF};
+use spn_rpc::NetworkClient;
+use spn_utils::{time_now, SPN_MAINNET_V1_DOMAIN};
+
+/// The verifying key hash the test program is registered under. The mock network does not check
+/// it against the program.
+const VK_HASH: [u8; 32] = [7; 32];
+
+/// How long a request may take to be proven.
+const PROVING_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// Start a mock network, an artifact server, and a node that bids and proves on the network.
+async fn setup(settlement: Settlement) -> (MockProverNetwork, ArtifactServer, Requester) {
+    let network = MockProverNetwork::new(settlement);
+    let endpoint = network.serve().await.unwrap();
+    let artifacts = ArtifactServer::start().await.unwrap();
+
+    let client = ProverNetworkClient::connect(endpoint).await.unwrap();
+    let requester = Requester {
+        client: NetworkClient::new(
+            client.clone(),
+            PrivateKeySigner::random(),
+            *SPN_MAINNET_V1_DOMAIN,
+        ),
+    };
+
+    // Run a node with its own key, bidding on behalf of itself.
+    let signer = PrivateKeySigner::random();
+    let prover = signer.address();
+    let node = Node::new(
+        SerialContext::new(client, signer),
+        SerialBidder::new(U256::from(1), 1_000_000.0, prover),
+        SerialProver::new(),
+        SerialMonitor::new(),
+    );
+    tokio::spawn(node.run());
+
+    (network, artifacts, requester)
+}
+
+/// A requester that submits signed transactions to the network.
+struct Requester {
+    client: NetworkClient,
+}
+
+impl Requester {
+    /// Register the fibonacci program.
+    async fn create_program(&self, artifacts: &ArtifactServer) {
+        let program_uri = artifacts.put("program_fibonacci", &FIBONACCI_ELF.to_vec()).unwrap();
+        let body = CreateProgramRequestBody {
+            nonce: self.client.nonce().await.unwrap(),
+            vk_hash: VK_HASH.to_vec(),
+            vk: Vec::new(),
+            program_uri,
+        };
+        let mut network = self.client.network().clone();
+        network
+            .create_program(CreateProgramRequest {
+                format: MessageFormat::Binary.into(),
+                signature: body.sign(self.client.signer()).into(),
+                body: Some(body),
+            })
+            .await
+            .unwrap();
+    }
+
+    /// Request a core proof of whether `n` is prime and return the request id.
+    async fn request_proof(&self, artifacts: &ArtifactServer, n: u64) -> Vec<u8> {
+        let mut stdin = SP1Stdin::new();
+        stdin.write(&n);
+        let stdin_uri = artifacts.put(&format!("stdin_{n}"), &stdin).unwrap();
+
+        self.client
+            .request_proof(RequestProofRequestBody {
+                vk_hash: VK_HASH.to_vec(),
+                version: SP1_NETWORK_VERSION.to_string(),
+                mode: ProofMode::Core.into(),
+                strategy: FulfillmentStrategy::Auction.into(),
+                stdin_uri,
+                deadline: time_now() + PROVING_TIMEOUT.as_secs(),
+                cycle_limit: 1_000_000,
+                gas_limit: 1_000_000,
+                ..Default::default()
+            })
+            .await
+            .unwrap()
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_node_fulfills_request() {
+    let (network, artifacts, requester) = setup(Settlement::Immediate).await;
+    requester.create_program(&artifacts).await;
+    let request_id = requester.request_proof(&artifacts, 7).await;
+
+    let request = network
+        .wait_for_status(&request_id, FulfillmentStatus::Fulfilled, PROVING_TIMEOUT)
+        .await
+        .unwrap();
+    assert_eq!(network.bids(&request_id).len(), 1);
+    assert_eq!(request.fulfiller, Some(network.bids(&request_id)[0].bidder.clone()));
+
+    // The submitted proof attests that 7 is prime.
+    let proof = network.proof(&request_id).unwrap();
+    let mut proof: SP1ProofWithPublicValues = bincode::deserialize(&proof).unwrap();
+    assert!(proof.public_values.read::<bool>());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_node_fails_unexecutable_request() {
+    let (network, artifacts, requester) = setup(Settlement::Manual).await;
+    requester.create_program(&artifacts).await;
+    let request_id = requester.request_proof(&artifacts, 7).await;
+
+    // Wait for the node to bid, then hand it a request that the executor gave up on.
+    tokio::time::timeout(Duration::from_secs(30), async {
+        while network.bids(&request_id).is_empty() {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+    })
+    .await
+    .unwrap();
+    network.mark_unexecutable(&request_id);
+    network.settle(&request_id).unwrap();
+
+    network
+        .wait_for_status(&request_id, FulfillmentStatus::Unfulfillable, Duration::from_secs(60))
+        .await
+        .unwrap();
+    assert!(network.proof(&request_id).is_none());
+}
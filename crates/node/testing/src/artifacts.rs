@@ -0,0 +1,100 @@
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+};
+
+use anyhow::{Context, Result};
+use axum::{
+    body::Bytes,
+    extract::{Path, State},
+    http::StatusCode,
+    routing::get,
+    Router,
+};
+use serde::Serialize;
+use tokio::net::TcpListener;
+
+/// The artifacts stored by an [`ArtifactServer`], keyed by name.
+type ArtifactMap = Arc<Mutex<HashMap<String, Bytes>>>;
+
+/// An in-memory HTTP artifact server.
+///
+/// Artifacts are served from `GET /artifacts/<name>` and can be uploaded with
+/// `PUT /artifacts/<name>`, so the URIs it hands out can be used as the public program and stdin
+/// URIs of proof requests.
+#[derive(Debug, Clone)]
+pub struct ArtifactServer {
+    /// The address the server is listening on.
+    addr: SocketAddr,
+    /// The stored artifacts.
+    artifacts: ArtifactMap,
+}
+
+impl ArtifactServer {
+    /// Start an artifact server on an ephemeral local port.
+    ///
+    /// The server runs in the background for as long as the current Tokio runtime is alive.
+    pub async fn start() -> Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0").await.context("failed to bind")?;
+        let addr = listener.local_addr()?;
+        let artifacts = ArtifactMap::default();
+
+        let app = Router::new()
+            .route("/artifacts/:name", get(get_artifact).put(put_artifact))
+            .with_state(artifacts.clone());
+        tokio::spawn(async move {
+            if let Err(e) = axum::serve(listener, app).await {
+                tracing::error!("artifact server failed: {e}");
+            }
+        });
+
+        Ok(Self { addr, artifacts })
+    }
+
+    /// The address the server is listening on.
+    #[must_use]
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// The URI of the artifact with the given name.
+    #[must_use]
+    pub fn uri(&self, name: &str) -> String {
+        format!("http://{}/artifacts/{name}", self.addr)
+    }
+
+    /// Store raw bytes under `name` and return the URI of the artifact.
+    pub fn put_raw(&self, name: &str, data: impl Into<Bytes>) -> String {
+        self.artifacts.lock().unwrap().insert(name.to_string(), data.into());
+        self.uri(name)
+    }
+
+    /// Store a bincode-serialized item under `name` and return the URI of the artifact.
+    pub fn put<T: Serialize>(&self, name: &str, item: &T) -> Result<String> {
+        let data = bincode::serialize(item).context("failed to serialize artifact")?;
+        Ok(self.put_raw(name, data))
+    }
+
+    /// Get the raw bytes stored under `name`.
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<Bytes> {
+        self.artifacts.lock().unwrap().get(name).cloned()
+    }
+}
+
+async fn get_artifact(
+    State(artifacts): State<ArtifactMap>,
+    Path(name): Path<String>,
+) -> Result<Bytes, StatusCode> {
+    artifacts.lock().unwrap().get(&name).cloned().ok_or(StatusCode::NOT_FOUND)
+}
+
+async fn put_artifact(
+    State(artifacts): State<ArtifactMap>,
+    Path(name): Path<String>,
+    body: Bytes,
+) -> StatusCode {
+    artifacts.lock().unwrap().insert(name, body);
+    StatusCode::OK
+}
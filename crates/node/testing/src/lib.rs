@@ -0,0 +1,22 @@
+//! SPN Node Testing.
+//!
+//! In-process stand-ins for the Succinct Prover Network and its artifact storage, so that a real
+//! node can be run end-to-end inside `cargo test`.
+
+#![warn(clippy::pedantic)]
+#![allow(clippy::module_name_repetitions)]
+#![allow(clippy::missing_errors_doc)]
+#![allow(clippy::missing_panics_doc)]
+#![allow(clippy::too_many_lines)]
+#![warn(missing_docs)]
+
+mod artifacts;
+mod network;
+
+pub use artifacts::*;
+pub use network::*;
+
+use sp1_sdk::include_elf;
+
+/// The ELF of the example fibonacci program, which reads a `u64` and commits whether it is prime.
+pub const FIBONACCI_ELF: &[u8] = include_elf!("spn-fibonacci-program");
@@ -0,0 +1,929 @@
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    pin::Pin,
+    str::FromStr,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use alloy_primitives::{Signature, U256};
+use anyhow::{anyhow, Context, Result};
+use futures::{stream, Stream, StreamExt};
+use prost::Message;
+use spn_network_types::{
+    prover_network_server::{ProverNetwork, ProverNetworkServer},
+    BidRequest, BidResponse, ConnectTwitterRequest, ConnectTwitterResponse, CreateProgramRequest,
+    CreateProgramResponse, ExecuteProofRequest, ExecuteProofResponse, ExecutionStatus,
+    FailFulfillmentRequest, FailFulfillmentResponse, FulfillProofRequest, FulfillProofResponse,
+    FulfillmentStatus, GetAccountNameRequest, GetAccountNameResponse, GetAccountRequest,
+    GetAccountResponse, GetAnalyticsGraphsRequest, GetAnalyticsGraphsResponse, GetBalanceRequest,
+    GetBalanceResponse, GetDelegationParamsRequest, GetDelegationParamsResponse,
+    GetDelegationRequest, GetDelegationResponse, GetFilteredBalanceLogsRequest,
+    GetFilteredBalanceLogsResponse, GetFilteredBidHistoryRequest, GetFilteredBidHistoryResponse,
+    GetFilteredProofRequestsRequest, GetFilteredProofRequestsResponse,
+    GetFilteredProverStatsRequest, GetFilteredProverStatsResponse, GetFilteredProversRequest,
+    GetFilteredProversResponse, GetGasPriceEstimateRequest, GetGasPriceEstimateResponse,
+    GetLatestBridgeBlockRequest, GetLatestBridgeBlockResponse, GetNonceRequest, GetNonceResponse,
+    GetOverviewGraphsRequest, GetOverviewGraphsResponse, GetOwnerRequest, GetOwnerResponse,
+    GetProgramRequest, GetProgramResponse, GetProofRequestDetailsRequest,
+    GetProofRequestDetailsResponse, GetProofRequestGraphRequest, GetProofRequestGraphResponse,
+    GetProofRequestMetricsRequest, GetProofRequestMetricsResponse, GetProofRequestParamsRequest,
+    GetProofRequestParamsResponse, GetProofRequestStatusRequest, GetProofRequestStatusResponse,
+    GetProverSearchResultsRequest, GetProverSearchResultsResponse, GetProverStakeBalanceRequest,
+    GetProverStakeBalanceResponse, GetProverStatsDetailRequest, GetProverStatsDetailResponse,
+    GetProverStatsRequest, GetProverStatsResponse, GetProversByUptimeRequest,
+    GetProversByUptimeResponse, GetSearchResultsRequest, GetSearchResultsResponse,
+    GetStakingProverSearchRequest, GetStakingProverSearchResponse, GetTeeWhitelistStatusRequest,
+    GetTeeWhitelistStatusResponse, GetTermsSignatureRequest, GetTermsSignatureResponse,
+    GetTransactionDetailsRequest, GetTransactionDetailsResponse, GetTransferParamsRequest,
+    GetTransferParamsResponse, GetWithdrawParamsRequest, GetWithdrawParamsResponse,
+    HashableWithSender, Program, ProofRequest, RequestProofRequest, RequestProofResponse,
+    RequestProofResponseBody, SetAccountNameRequest, SetAccountNameResponse, SetDelegationRequest,
+    SetDelegationResponse, SetProgramNameRequest, SetProgramNameResponse, SetTermsSignatureRequest,
+    SetTermsSignatureResponse, SettleRequest, SettleResponse, SignInRequest, SignInResponse,
+    SuspendProverRequest, SuspendProverResponse, TransferRequest, TransferResponse,
+    WithdrawRequest, WithdrawResponse,
+};
+use tokio::{net::TcpListener, sync::broadcast};
+use tonic::{
+    async_trait,
+    transport::{server::TcpIncoming, Server},
+    Request, Response, Status,
+};
+
+/// The number of request updates buffered for each `SubscribeProofRequests` stream.
+const UPDATES_CAPACITY: usize = 1024;
+
+/// The default number of requests returned by `GetFilteredProofRequests`.
+const DEFAULT_PAGE_LIMIT: u32 = 10;
+
+/// The maximum number of requests returned by `GetFilteredProofRequests`.
+const MAX_PAGE_LIMIT: u32 = 100;
+
+/// How a [`MockProverNetwork`] settles the auction of a proof request.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Settlement {
+    /// Assign the request to the lowest bidder as soon as a bid is accepted.
+    #[default]
+    Immediate,
+    /// Collect bids until the auction is settled with [`MockProverNetwork::settle`] or a `Settle`
+    /// transaction.
+    Manual,
+}
+
+/// A bid accepted by a [`MockProverNetwork`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MockBid {
+    /// The owner of the account that submitted the bid.
+    pub bidder: Vec<u8>,
+    /// The amount that was bid.
+    pub amount: U256,
+}
+
+/// The state of a [`MockProverNetwork`].
+#[derive(Debug, Default)]
+struct MockState {
+    /// The proof requests, keyed by request id.
+    requests: HashMap<Vec<u8>, ProofRequest>,
+    /// The programs, keyed by verifying key hash.
+    programs: HashMap<Vec<u8>, Program>,
+    /// The nonce of each account.
+    nonces: HashMap<Vec<u8>, u64>,
+    /// The owner of each account. Accounts without an entry own themselves.
+    owners: HashMap<Vec<u8>, Vec<u8>>,
+    /// The balance of each account.
+    balances: HashMap<Vec<u8>, U256>,
+    /// The stake balance of each prover.
+    stakes: HashMap<Vec<u8>, U256>,
+    /// The bids on each request.
+    bids: HashMap<Vec<u8>, Vec<MockBid>>,
+    /// The proof submitted for each fulfilled request.
+    proofs: HashMap<Vec<u8>, Vec<u8>>,
+}
+
+/// An in-memory implementation of the prover network gRPC service.
+///
+/// It covers the lifecycle of a proof request: programs and requests are created with signed
+/// transactions, provers bid on them, auctions are settled according to the configured
+/// [`Settlement`], and the assigned prover fulfills or fails the request. Every transaction is
+/// checked against the nonce of its signer. RPCs outside of that lifecycle return
+/// [`Status::unimplemented`].
+///
+/// Clones share the same state, so a test can keep a handle to script and inspect the network while
+/// a node talks to it over gRPC.
+#[derive(Debug, Clone)]
+pub struct MockProverNetwork {
+    /// The shared state.
+    state: Arc<Mutex<MockState>>,
+    /// How auctions are settled.
+    settlement: Settlement,
+    /// Every update to a proof request, for `SubscribeProofRequests`.
+    updates: broadcast::Sender<ProofRequest>,
+}
+
+impl Default for MockProverNetwork {
+    fn default() -> Self {
+        Self::new(Settlement::default())
+    }
+}
+
+impl MockProverNetwork {
+    /// Create a new [`MockProverNetwork`] that settles auctions according to `settlement`.
+    #[must_use]
+    pub fn new(settlement: Settlement) -> Self {
+        let (updates, _) = broadcast::channel(UPDATES_CAPACITY);
+        Self { state: Arc::default(), settlement, updates }
+    }
+
+    /// Serve the network on an ephemeral local port and return its endpoint.
+    ///
+    /// The server runs in the background for as long as the current Tokio runtime is alive.
+    pub async fn serve(&self) -> Result<String> {
+        let listener = TcpListener::bind("127.0.0.1:0").await.context("failed to bind")?;
+        let addr: SocketAddr = listener.local_addr()?;
+        let incoming = TcpIncoming::from_listener(listener, true, None)
+            .map_err(|e| anyhow!("failed to listen: {e}"))?;
+
+        let service = ProverNetworkServer::new(self.clone());
+        tokio::spawn(async move {
+            if let Err(e) =
+                Server::builder().add_service(service).serve_with_incoming(incoming).await
+            {
+                tracing::error!("mock prover network failed: {e}");
+            }
+        });
+
+        Ok(format!("http://{addr}"))
+    }
+
+    /// Insert or replace a proof request without a signed transaction.
+    pub fn insert_request(&self, request: ProofRequest) {
+        let mut state = self.state.lock().unwrap();
+        state.requests.insert(request.request_id.clone(), request.clone());
+        let _ = self.updates.send(request);
+    }
+
+    /// Get a proof request.
+    #[must_use]
+    pub fn request(&self, request_id: &[u8]) -> Option<ProofRequest> {
+        self.state.lock().unwrap().requests.get(request_id).cloned()
+    }
+
+    /// Register a program without a signed transaction.
+    pub fn insert_program(&self, program: Program) {
+        self.state.lock().unwrap().programs.insert(program.vk_hash.clone(), program);
+    }
+
+    /// Make `owner` the owner of `address`, as if `address` was delegated to by `owner`.
+    pub fn set_owner(&self, address: &[u8], owner: &[u8]) {
+        self.state.lock().unwrap().owners.insert(address.to_vec(), owner.to_vec());
+    }
+
+    /// Set the balance of an account.
+    pub fn set_balance(&self, address: &[u8], amount: U256) {
+        self.state.lock().unwrap().balances.insert(address.to_vec(), amount);
+    }
+
+    /// Set the stake balance of a prover.
+    pub fn set_stake(&self, prover: &[u8], amount: U256) {
+        self.state.lock().unwrap().stakes.insert(prover.to_vec(), amount);
+    }
+
+    /// The bids accepted for a request so far.
+    #[must_use]
+    pub fn bids(&self, request_id: &[u8]) -> Vec<MockBid> {
+        self.state.lock().unwrap().bids.get(request_id).cloned().unwrap_or_default()
+    }
+
+    /// The proof submitted for a fulfilled request.
+    #[must_use]
+    pub fn proof(&self, request_id: &[u8]) -> Option<Vec<u8>> {
+        self.state.lock().unwrap().proofs.get(request_id).cloned()
+    }
+
+    /// Settle the auction of a request by assigning it to the lowest bidder.
+    ///
+    /// Ties go to the earliest bid. Returns the winner, or `None` if the request is not open or has
+    /// no bids.
+    pub fn settle(&self, request_id: &[u8]) -> Option<Vec<u8>> {
+        let mut state = self.state.lock().unwrap();
+        let winner = state.lowest_bidder(request_id)?;
+        let request = state.assign(request_id, &winner).ok()?;
+        let _ = self.updates.send(request);
+        Some(winner)
+    }
+
+    /// Mark a request as unexecutable, as the network's executor would.
+    pub fn mark_unexecutable(&self, request_id: &[u8]) {
+        self.update(request_id, |request| {
+            request.execution_status = ExecutionStatus::Unexecutable.into();
+        });
+    }
+
+    /// Wait until a request reaches `status`, polling every 100ms for at most `timeout`.
+    pub async fn wait_for_status(
+        &self,
+        request_id: &[u8],
+        status: FulfillmentStatus,
+        timeout: Duration,
+    ) -> Result<ProofRequest> {
+        let wait = async {
+            loop {
+                if let Some(request) = self.request(request_id) {
+                    if request.fulfillment_status == status as i32 {
+                        return request;
+                    }
+                }
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+        };
+        tokio::time::timeout(timeout, wait).await.map_err(|_| {
+            let current = self.request(request_id).map(|r| r.fulfillment_status());
+            anyhow!("request did not reach {status:?} within {timeout:?} (currently {current:?})")
+        })
+    }
+
+    /// Apply `f` to a request and publish the update.
+    fn update(&self, request_id: &[u8], f: impl FnOnce(&mut ProofRequest)) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(request) = state.requests.get_mut(request_id) {
+            f(request);
+            request.updated_at = time_now();
+            let _ = self.updates.send(request.clone());
+        }
+    }
+
+    /// Whether a request matches a `GetFilteredProofRequests` filter, ignoring pagination.
+    fn matches(
+        state: &MockState,
+        filter: &GetFilteredProofRequestsRequest,
+        r: &ProofRequest,
+    ) -> bool {
+        filter.version.as_ref().map_or(true, |v| &r.version == v)
+            && filter.fulfillment_status.map_or(true, |s| r.fulfillment_status == s)
+            && filter.execution_status.map_or(true, |s| r.execution_status == s)
+            && filter.minimum_deadline.map_or(true, |d| r.deadline >= d)
+            && filter.vk_hash.as_ref().map_or(true, |h| &r.vk_hash == h)
+            && filter.requester.as_ref().map_or(true, |a| &r.requester == a)
+            && (filter.fulfiller.is_none() || r.fulfiller == filter.fulfiller)
+            && filter.from.map_or(true, |t| r.created_at >= t)
+            && filter.to.map_or(true, |t| r.created_at <= t)
+            && filter.mode.map_or(true, |m| r.mode == m)
+            && filter.execute_fail_cause.map_or(true, |c| r.execute_fail_cause == c)
+            && filter.settlement_status.map_or(true, |s| r.settlement_status == s)
+            && filter.error.map_or(true, |e| r.error == e)
+            && filter.not_bid_by.as_ref().map_or(true, |owner| {
+                state
+                    .bids
+                    .get(&r.request_id)
+                    .map_or(true, |bids| bids.iter().all(|b| &b.bidder != owner))
+            })
+    }
+}
+
+impl MockState {
+    /// Recover the signer of `body` and check and bump its nonce.
+    fn accept<M: Message>(
+        &mut self,
+        body: &M,
+        nonce: u64,
+        signature: &[u8],
+    ) -> Result<Vec<u8>, Status> {
+        let signature = Signature::try_from(signature)
+            .map_err(|e| Status::invalid_argument(format!("invalid signature: {e}")))?;
+        let signer = signature
+            .recover_address_from_msg(body.encode_to_vec())
+            .map_err(|e| Status::invalid_argument(format!("invalid signature: {e}")))?
+            .to_vec();
+
+        let expected = self.nonces.entry(signer.clone()).or_default();
+        if *expected != nonce {
+            return Err(Status::invalid_argument(format!(
+                "invalid nonce: expected {expected}, got {nonce}"
+            )));
+        }
+        *expected += 1;
+        Ok(signer)
+    }
+
+    /// The owner of `address`.
+    fn owner(&self, address: &[u8]) -> Vec<u8> {
+        self.owners.get(address).cloned().unwrap_or_else(|| address.to_vec())
+    }
+
+    /// The owner of the lowest bid on a request, preferring earlier bids on ties.
+    fn lowest_bidder(&self, request_id: &[u8]) -> Option<Vec<u8>> {
+        self.bids.get(request_id)?.iter().min_by_key(|b| b.amount).map(|b| b.bidder.clone())
+    }
+
+    /// Assign an open request to `winner`.
+    fn assign(&mut self, request_id: &[u8], winner: &[u8]) -> Result<ProofRequest, Status> {
+        let request = self.open_request(request_id)?;
+        request.fulfiller = Some(winner.to_vec());
+        request.fulfillment_status = FulfillmentStatus::Assigned.into();
+        request.updated_at = time_now();
+        Ok(request.clone())
+    }
+
+    /// Get a request that is still open for bids.
+    fn open_request(&mut self, request_id: &[u8]) -> Result<&mut ProofRequest, Status> {
+        let request = self
+            .requests
+            .get_mut(request_id)
+            .ok_or_else(|| Status::not_found("request not found"))?;
+        if request.fulfillment_status != FulfillmentStatus::Requested as i32 {
+            return Err(Status::failed_precondition("request is not open for bids"));
+        }
+        if request.deadline <= time_now() {
+            return Err(Status::failed_precondition("request deadline has passed"));
+        }
+        Ok(request)
+    }
+
+    /// Get a request that is assigned to `owner`.
+    fn assigned_request(
+        &mut self,
+        request_id: &[u8],
+        owner: &[u8],
+    ) -> Result<&mut ProofRequest, Status> {
+        let request = self
+            .requests
+            .get_mut(request_id)
+            .ok_or_else(|| Status::not_found("request not found"))?;
+        if request.fulfillment_status != FulfillmentStatus::Assigned as i32 {
+            return Err(Status::failed_precondition("request is not assigned"));
+        }
+        if request.fulfiller.as_deref() != Some(owner) {
+            return Err(Status::permission_denied("request is assigned to another prover"));
+        }
+        Ok(request)
+    }
+}
+
+/// The current Unix timestamp in seconds.
+fn time_now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).expect("time went backwards").as_secs()
+}
+
+/// Parse a decimal amount.
+fn parse_amount(amount: &str) -> Result<U256, Status> {
+    U256::from_str(amount).map_err(|e| Status::invalid_argument(format!("invalid amount: {e}")))
+}
+
+#[async_trait]
+impl ProverNetwork for MockProverNetwork {
+    type SubscribeProofRequestsStream =
+        Pin<Box<dyn Stream<Item = Result<ProofRequest, Status>> + Send + 'static>>;
+
+    async fn request_proof(
+        &self,
+        request: Request<RequestProofRequest>,
+    ) -> Result<Response<RequestProofResponse>, Status> {
+        let body = request.into_inner();
+        let signature = body.signature;
+        let body = body.body.ok_or_else(|| Status::invalid_argument("missing body"))?;
+        let mut state = self.state.lock().unwrap();
+        let signer = state.accept(&body, body.nonce, &signature)?;
+
+        let program = state
+            .programs
+            .get(&body.vk_hash)
+            .cloned()
+            .ok_or_else(|| Status::not_found("program not found"))?;
+        let request_id = body
+            .hash_with_signer(&signer)
+            .map_err(|e| Status::internal(format!("failed to hash request: {e}")))?
+            .to_vec();
+
+        let now = time_now();
+        let proof_request = ProofRequest {
+            request_id: request_id.clone(),
+            vk_hash: body.vk_hash,
+            version: body.version,
+            mode: body.mode,
+            strategy: body.strategy,
+            program_uri: program.program_uri.clone(),
+            stdin_uri: body.stdin_uri.clone(),
+            deadline: body.deadline,
+            cycle_limit: body.cycle_limit,
+            fulfillment_status: FulfillmentStatus::Requested.into(),
+            execution_status: ExecutionStatus::Unexecuted.into(),
+            requester: signer,
+            created_at: now,
+            updated_at: now,
+            gas_limit: body.gas_limit,
+            program_public_uri: program.program_uri,
+            stdin_public_uri: body.stdin_uri,
+            min_auction_period: body.min_auction_period,
+            whitelist: body.whitelist,
+            base_fee: Some(body.base_fee),
+            max_price_per_pgu: Some(body.max_price_per_pgu),
+            ..Default::default()
+        };
+        state.requests.insert(request_id.clone(), proof_request.clone());
+        let _ = self.updates.send(proof_request);
+
+        Ok(Response::new(RequestProofResponse {
+            tx_hash: request_id.clone(),
+            body: Some(RequestProofResponseBody { request_id }),
+        }))
+    }
+
+    async fn fulfill_proof(
+        &self,
+        request: Request<FulfillProofRequest>,
+    ) -> Result<Response<FulfillProofResponse>, Status> {
+        let request = request.into_inner();
+        let body = request.body.ok_or_else(|| Status::invalid_argument("missing body"))?;
+        let mut state = self.state.lock().unwrap();
+        let signer = state.accept(&body, body.nonce, &request.signature)?;
+        let owner = state.owner(&signer);
+
+        let proof_request = state.assigned_request(&body.request_id, &owner)?;
+        let now = time_now();
+        proof_request.fulfillment_status = FulfillmentStatus::Fulfilled.into();
+        proof_request.fulfilled_at = Some(now);
+        proof_request.updated_at = now;
+        let proof_request = proof_request.clone();
+
+        state.proofs.insert(body.request_id, body.proof);
+        let _ = self.updates.send(proof_request);
+        Ok(Response::new(FulfillProofResponse::default()))
+    }
+
+    async fn execute_proof(
+        &self,
+        _request: Request<ExecuteProofRequest>,
+    ) -> Result<Response<ExecuteProofResponse>, Status> {
+        Err(Status::unimplemented("execute_proof is not supported by the mock network"))
+    }
+
+    async fn fail_fulfillment(
+        &self,
+        request: Request<FailFulfillmentRequest>,
+    ) -> Result<Response<FailFulfillmentResponse>, Status> {
+        let request = request.into_inner();
+        let body = request.body.ok_or_else(|| Status::invalid_argument("missing body"))?;
+        let mut state = self.state.lock().unwrap();
+        let signer = state.accept(&body, body.nonce, &request.signature)?;
+        let owner = state.owner(&signer);
+
+        let proof_request = state.assigned_request(&body.request_id, &owner)?;
+        proof_request.fulfillment_status = FulfillmentStatus::Unfulfillable.into();
+        proof_request.error = body.error.unwrap_or_default();
+        proof_request.updated_at = time_now();
+
+        let _ = self.updates.send(proof_request.clone());
+        Ok(Response::new(FailFulfillmentResponse::default()))
+    }
+
+    async fn get_proof_request_status(
+        &self,
+        request: Request<GetProofRequestStatusRequest>,
+    ) -> Result<Response<GetProofRequestStatusResponse>, Status> {
+        let request = request.into_inner();
+        let request = self
+            .request(&request.request_id)
+            .ok_or_else(|| Status::not_found("request not found"))?;
+        Ok(Response::new(GetProofRequestStatusResponse {
+            fulfillment_status: request.fulfillment_status,
+            execution_status: request.execution_status,
+            request_tx_hash: request.request_id,
+            deadline: request.deadline,
+            ..Default::default()
+        }))
+    }
+
+    async fn get_proof_request_details(
+        &self,
+        request: Request<GetProofRequestDetailsRequest>,
+    ) -> Result<Response<GetProofRequestDetailsResponse>, Status> {
+        let request = self.request(&request.into_inner().request_id);
+        Ok(Response::new(GetProofRequestDetailsResponse { request }))
+    }
+
+    async fn get_filtered_proof_requests(
+        &self,
+        request: Request<GetFilteredProofRequestsRequest>,
+    ) -> Result<Response<GetFilteredProofRequestsResponse>, Status> {
+        let filter = request.into_inner();
+        let limit = filter.limit.unwrap_or(DEFAULT_PAGE_LIMIT).min(MAX_PAGE_LIMIT) as usize;
+        let page = filter.page.unwrap_or(1).max(1) as usize;
+
+        let state = self.state.lock().unwrap();
+        let mut requests: Vec<ProofRequest> = state
+            .requests
+            .values()
+            .filter(|r| Self::matches(&state, &filter, r))
+            .cloned()
+            .collect();
+        requests.sort_by(|a, b| {
+            a.created_at.cmp(&b.created_at).then_with(|| a.request_id.cmp(&b.request_id))
+        });
+        let requests = requests.into_iter().skip((page - 1) * limit).take(limit).collect();
+        Ok(Response::new(GetFilteredProofRequestsResponse { requests }))
+    }
+
+    async fn subscribe_proof_requests(
+        &self,
+        request: Request<GetFilteredProofRequestsRequest>,
+    ) -> Result<Response<Self::SubscribeProofRequestsStream>, Status> {
+        let filter = request.into_inner();
+
+        // Subscribe before taking the snapshot so that no update falls in between.
+        let updates = self.updates.subscribe();
+        let snapshot: Vec<_> = {
+            let state = self.state.lock().unwrap();
+            state
+                .requests
+                .values()
+                .filter(|r| Self::matches(&state, &filter, r))
+                .cloned()
+                .map(Ok)
+                .collect()
+        };
+
+        let network = self.clone();
+        let live = stream::unfold(updates, move |mut updates| {
+            let network = network.clone();
+            let filter = filter.clone();
+            async move {
+                loop {
+                    match updates.recv().await {
+                        Ok(request) => {
+                            let matches = {
+                                let state = network.state.lock().unwrap();
+                                Self::matches(&state, &filter, &request)
+                            };
+                            if matches {
+                                return Some((Ok(request), updates));
+                            }
+                        }
+                        Err(broadcast::error::RecvError::Lagged(_)) => {}
+                        Err(broadcast::error::RecvError::Closed) => return None,
+                    }
+                }
+            }
+        });
+        Ok(Response::new(stream::iter(snapshot).chain(live).boxed()))
+    }
+
+    async fn get_search_results(
+        &self,
+        _request: Request<GetSearchResultsRequest>,
+    ) -> Result<Response<GetSearchResultsResponse>, Status> {
+        Err(Status::unimplemented("get_search_results is not supported by the mock network"))
+    }
+
+    async fn get_proof_request_metrics(
+        &self,
+        _request: Request<GetProofRequestMetricsRequest>,
+    ) -> Result<Response<GetProofRequestMetricsResponse>, Status> {
+        Err(Status::unimplemented("get_proof_request_metrics is not supported by the mock network"))
+    }
+
+    async fn get_proof_request_graph(
+        &self,
+        _request: Request<GetProofRequestGraphRequest>,
+    ) -> Result<Response<GetProofRequestGraphResponse>, Status> {
+        Err(Status::unimplemented("get_proof_request_graph is not supported by the mock network"))
+    }
+
+    async fn get_analytics_graphs(
+        &self,
+        _request: Request<GetAnalyticsGraphsRequest>,
+    ) -> Result<Response<GetAnalyticsGraphsResponse>, Status> {
+        Err(Status::unimplemented("get_analytics_graphs is not supported by the mock network"))
+    }
+
+    async fn get_overview_graphs(
+        &self,
+        _request: Request<GetOverviewGraphsRequest>,
+    ) -> Result<Response<GetOverviewGraphsResponse>, Status> {
+        Err(Status::unimplemented("get_overview_graphs is not supported by the mock network"))
+    }
+
+    async fn get_proof_request_params(
+        &self,
+        _request: Request<GetProofRequestParamsRequest>,
+    ) -> Result<Response<GetProofRequestParamsResponse>, Status> {
+        Err(Status::unimplemented("get_proof_request_params is not supported by the mock network"))
+    }
+
+    async fn get_nonce(
+        &self,
+        request: Request<GetNonceRequest>,
+    ) -> Result<Response<GetNonceResponse>, Status> {
+        let address = request.into_inner().address;
+        let nonce = self.state.lock().unwrap().nonces.get(&address).copied().unwrap_or_default();
+        Ok(Response::new(GetNonceResponse { nonce }))
+    }
+
+    async fn set_account_name(
+        &self,
+        _request: Request<SetAccountNameRequest>,
+    ) -> Result<Response<SetAccountNameResponse>, Status> {
+        Err(Status::unimplemented("set_account_name is not supported by the mock network"))
+    }
+
+    async fn get_account_name(
+        &self,
+        _request: Request<GetAccountNameRequest>,
+    ) -> Result<Response<GetAccountNameResponse>, Status> {
+        Err(Status::unimplemented("get_account_name is not supported by the mock network"))
+    }
+
+    async fn get_terms_signature(
+        &self,
+        _request: Request<GetTermsSignatureRequest>,
+    ) -> Result<Response<GetTermsSignatureResponse>, Status> {
+        Err(Status::unimplemented("get_terms_signature is not supported by the mock network"))
+    }
+
+    async fn set_terms_signature(
+        &self,
+        _request: Request<SetTermsSignatureRequest>,
+    ) -> Result<Response<SetTermsSignatureResponse>, Status> {
+        Err(Status::unimplemented("set_terms_signature is not supported by the mock network"))
+    }
+
+    async fn get_account(
+        &self,
+        _request: Request<GetAccountRequest>,
+    ) -> Result<Response<GetAccountResponse>, Status> {
+        Err(Status::unimplemented("get_account is not supported by the mock network"))
+    }
+
+    async fn get_owner(
+        &self,
+        request: Request<GetOwnerRequest>,
+    ) -> Result<Response<GetOwnerResponse>, Status> {
+        let address = request.into_inner().address;
+        let owner = self.state.lock().unwrap().owner(&address);
+        Ok(Response::new(GetOwnerResponse { owner }))
+    }
+
+    async fn get_program(
+        &self,
+        request: Request<GetProgramRequest>,
+    ) -> Result<Response<GetProgramResponse>, Status> {
+        let vk_hash = request.into_inner().vk_hash;
+        let program = self.state.lock().unwrap().programs.get(&vk_hash).cloned();
+        Ok(Response::new(GetProgramResponse { program }))
+    }
+
+    async fn create_program(
+        &self,
+        request: Request<CreateProgramRequest>,
+    ) -> Result<Response<CreateProgramResponse>, Status> {
+        let request = request.into_inner();
+        let body = request.body.ok_or_else(|| Status::invalid_argument("missing body"))?;
+        let mut state = self.state.lock().unwrap();
+        let signer = state.accept(&body, body.nonce, &request.signature)?;
+
+        if state.programs.contains_key(&body.vk_hash) {
+            return Err(Status::already_exists("program already exists"));
+        }
+        let program = Program {
+            vk_hash: body.vk_hash.clone(),
+            vk: body.vk,
+            program_uri: body.program_uri,
+            name: None,
+            owner: signer,
+            created_at: time_now(),
+        };
+        state.programs.insert(body.vk_hash, program);
+        Ok(Response::new(CreateProgramResponse::default()))
+    }
+
+    async fn set_program_name(
+        &self,
+        _request: Request<SetProgramNameRequest>,
+    ) -> Result<Response<SetProgramNameResponse>, Status> {
+        Err(Status::unimplemented("set_program_name is not supported by the mock network"))
+    }
+
+    async fn get_balance(
+        &self,
+        request: Request<GetBalanceRequest>,
+    ) -> Result<Response<GetBalanceResponse>, Status> {
+        let address = request.into_inner().address;
+        let amount = self.state.lock().unwrap().balances.get(&address).copied().unwrap_or_default();
+        Ok(Response::new(GetBalanceResponse { amount: amount.to_string() }))
+    }
+
+    async fn get_filtered_balance_logs(
+        &self,
+        _request: Request<GetFilteredBalanceLogsRequest>,
+    ) -> Result<Response<GetFilteredBalanceLogsResponse>, Status> {
+        Err(Status::unimplemented("get_filtered_balance_logs is not supported by the mock network"))
+    }
+
+    async fn get_latest_bridge_block(
+        &self,
+        _request: Request<GetLatestBridgeBlockRequest>,
+    ) -> Result<Response<GetLatestBridgeBlockResponse>, Status> {
+        Err(Status::unimplemented("get_latest_bridge_block is not supported by the mock network"))
+    }
+
+    async fn get_gas_price_estimate(
+        &self,
+        _request: Request<GetGasPriceEstimateRequest>,
+    ) -> Result<Response<GetGasPriceEstimateResponse>, Status> {
+        Err(Status::unimplemented("get_gas_price_estimate is not supported by the mock network"))
+    }
+
+    async fn get_transaction_details(
+        &self,
+        _request: Request<GetTransactionDetailsRequest>,
+    ) -> Result<Response<GetTransactionDetailsResponse>, Status> {
+        Err(Status::unimplemented("get_transaction_details is not supported by the mock network"))
+    }
+
+    async fn transfer(
+        &self,
+        _request: Request<TransferRequest>,
+    ) -> Result<Response<TransferResponse>, Status> {
+        Err(Status::unimplemented("transfer is not supported by the mock network"))
+    }
+
+    async fn get_transfer_params(
+        &self,
+        _request: Request<GetTransferParamsRequest>,
+    ) -> Result<Response<GetTransferParamsResponse>, Status> {
+        Err(Status::unimplemented("get_transfer_params is not supported by the mock network"))
+    }
+
+    async fn get_withdraw_params(
+        &self,
+        _request: Request<GetWithdrawParamsRequest>,
+    ) -> Result<Response<GetWithdrawParamsResponse>, Status> {
+        Err(Status::unimplemented("get_withdraw_params is not supported by the mock network"))
+    }
+
+    async fn withdraw(
+        &self,
+        _request: Request<WithdrawRequest>,
+    ) -> Result<Response<WithdrawResponse>, Status> {
+        Err(Status::unimplemented("withdraw is not supported by the mock network"))
+    }
+
+    async fn bid(&self, request: Request<BidRequest>) -> Result<Response<BidResponse>, Status> {
+        let request = request.into_inner();
+        let body = request.body.ok_or_else(|| Status::invalid_argument("missing body"))?;
+        let amount = parse_amount(&body.amount)?;
+        let mut state = self.state.lock().unwrap();
+        let signer = state.accept(&body, body.nonce, &request.signature)?;
+        let bidder = state.owner(&signer);
+
+        state.open_request(&body.request_id)?;
+        let bids = state.bids.entry(body.request_id.clone()).or_default();
+        if bids.iter().any(|b| b.bidder == bidder) {
+            return Err(Status::already_exists("already bid on this request"));
+        }
+        bids.push(MockBid { bidder, amount });
+
+        if self.settlement == Settlement::Immediate {
+            let winner = state.lowest_bidder(&body.request_id).expect("a bid was just accepted");
+            let request = state.assign(&body.request_id, &winner)?;
+            let _ = self.updates.send(request);
+        }
+        Ok(Response::new(BidResponse::default()))
+    }
+
+    async fn settle(
+        &self,
+        request: Request<SettleRequest>,
+    ) -> Result<Response<SettleResponse>, Status> {
+        let request = request.into_inner();
+        let body = request.body.ok_or_else(|| Status::invalid_argument("missing body"))?;
+        let mut state = self.state.lock().unwrap();
+        state.accept(&body, body.nonce, &request.signature)?;
+
+        let has_bid = state
+            .bids
+            .get(&body.request_id)
+            .is_some_and(|bids| bids.iter().any(|b| b.bidder == body.winner));
+        if !has_bid {
+            return Err(Status::failed_precondition("winner did not bid on the request"));
+        }
+        let request = state.assign(&body.request_id, &body.winner)?;
+        let _ = self.updates.send(request);
+        Ok(Response::new(SettleResponse::default()))
+    }
+
+    async fn get_provers_by_uptime(
+        &self,
+        _request: Request<GetProversByUptimeRequest>,
+    ) -> Result<Response<GetProversByUptimeResponse>, Status> {
+        Err(Status::unimplemented("get_provers_by_uptime is not supported by the mock network"))
+    }
+
+    async fn suspend_prover(
+        &self,
+        _request: Request<SuspendProverRequest>,
+    ) -> Result<Response<SuspendProverResponse>, Status> {
+        Err(Status::unimplemented("suspend_prover is not supported by the mock network"))
+    }
+
+    async fn sign_in(
+        &self,
+        _request: Request<SignInRequest>,
+    ) -> Result<Response<SignInResponse>, Status> {
+        Err(Status::unimplemented("sign_in is not supported by the mock network"))
+    }
+
+    async fn connect_twitter(
+        &self,
+        _request: Request<ConnectTwitterRequest>,
+    ) -> Result<Response<ConnectTwitterResponse>, Status> {
+        Err(Status::unimplemented("connect_twitter is not supported by the mock network"))
+    }
+
+    async fn get_prover_stats(
+        &self,
+        _request: Request<GetProverStatsRequest>,
+    ) -> Result<Response<GetProverStatsResponse>, Status> {
+        Err(Status::unimplemented("get_prover_stats is not supported by the mock network"))
+    }
+
+    async fn get_filtered_prover_stats(
+        &self,
+        _request: Request<GetFilteredProverStatsRequest>,
+    ) -> Result<Response<GetFilteredProverStatsResponse>, Status> {
+        Err(Status::unimplemented("get_filtered_prover_stats is not supported by the mock network"))
+    }
+
+    async fn get_prover_stats_detail(
+        &self,
+        _request: Request<GetProverStatsDetailRequest>,
+    ) -> Result<Response<GetProverStatsDetailResponse>, Status> {
+        Err(Status::unimplemented("get_prover_stats_detail is not supported by the mock network"))
+    }
+
+    async fn get_prover_search_results(
+        &self,
+        _request: Request<GetProverSearchResultsRequest>,
+    ) -> Result<Response<GetProverSearchResultsResponse>, Status> {
+        Err(Status::unimplemented("get_prover_search_results is not supported by the mock network"))
+    }
+
+    async fn get_staking_prover_search(
+        &self,
+        _request: Request<GetStakingProverSearchRequest>,
+    ) -> Result<Response<GetStakingProverSearchResponse>, Status> {
+        Err(Status::unimplemented("get_staking_prover_search is not supported by the mock network"))
+    }
+
+    async fn get_filtered_bid_history(
+        &self,
+        _request: Request<GetFilteredBidHistoryRequest>,
+    ) -> Result<Response<GetFilteredBidHistoryResponse>, Status> {
+        Err(Status::unimplemented("get_filtered_bid_history is not supported by the mock network"))
+    }
+
+    async fn get_tee_whitelist_status(
+        &self,
+        _request: Request<GetTeeWhitelistStatusRequest>,
+    ) -> Result<Response<GetTeeWhitelistStatusResponse>, Status> {
+        Err(Status::unimplemented("get_tee_whitelist_status is not supported by the mock network"))
+    }
+
+    async fn get_filtered_provers(
+        &self,
+        _request: Request<GetFilteredProversRequest>,
+    ) -> Result<Response<GetFilteredProversResponse>, Status> {
+        Err(Status::unimplemented("get_filtered_provers is not supported by the mock network"))
+    }
+
+    async fn get_prover_stake_balance(
+        &self,
+        request: Request<GetProverStakeBalanceRequest>,
+    ) -> Result<Response<GetProverStakeBalanceResponse>, Status> {
+        let prover = request.into_inner().prover;
+        let amount = self.state.lock().unwrap().stakes.get(&prover).copied().unwrap_or_default();
+        Ok(Response::new(GetProverStakeBalanceResponse { amount: amount.to_string() }))
+    }
+
+    async fn get_delegation_params(
+        &self,
+        _request: Request<GetDelegationParamsRequest>,
+    ) -> Result<Response<GetDelegationParamsResponse>, Status> {
+        Err(Status::unimplemented("get_delegation_params is not supported by the mock network"))
+    }
+
+    async fn set_delegation(
+        &self,
+        _request: Request<SetDelegationRequest>,
+    ) -> Result<Response<SetDelegationResponse>, Status> {
+        Err(Status::unimplemented("set_delegation is not supported by the mock network"))
+    }
+
+    async fn get_delegation(
+        &self,
+        _request: Request<GetDelegationRequest>,
+    ) -> Result<Response<GetDelegationResponse>, Status> {
+        Err(Status::unimplemented("get_delegation is not supported by the mock network"))
+    }
+}
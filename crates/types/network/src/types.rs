@@ -223,6 +223,10 @@ pub struct FulfillProofRequestBody {
     /// The variant of the transaction.
     #[prost(enumeration = "TransactionVariant", tag = "6")]
     pub variant: i32,
+    /// The optional URI of the proof artifact, set instead of the proof bytes for
+    /// proofs too large to send inline.
+    #[prost(string, optional, tag = "7")]
+    pub proof_uri: ::core::option::Option<::prost::alloc::string::String>,
 }
 #[derive(serde::Serialize, serde::Deserialize)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -684,6 +684,7 @@ pub fn create_clear_tx_with_options(
             reserved_metadata: None,
             domain: SPN_MAINNET_V1_DOMAIN.to_vec(),
             variant: TransactionVariant::FulfillVariant as i32,
+            proof_uri: None,
         };
 
         Some(FulfillProofRequest {
@@ -978,6 +979,7 @@ pub fn create_clear_tx_with_public_values_hash(
         proof: vec![0u8; 100],
         reserved_metadata: None,
         variant: TransactionVariant::FulfillVariant as i32,
+        proof_uri: None,
     };
 
     // Create and sign fulfill.